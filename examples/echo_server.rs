@@ -0,0 +1,67 @@
+// Smallest useful page server: answers every GET with a page describing
+// the request it received, and anything else with an error.
+//
+//   cargo run --example echo_server
+//
+// then open nym://<printed address>/any/path in NymView.
+use nym_sdk::mixnet::{self, MixnetMessageSender};
+use nymview_protocol::{ErrorStatus, Request, Response};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = mixnet::MixnetClientBuilder::new_ephemeral()
+        .build()?
+        .connect_to_mixnet()
+        .await?;
+    println!("Echo server listening on {}", client.nym_address());
+
+    while let Some(messages) = client.wait_for_messages().await {
+        for received in messages {
+            let Ok(message) = String::from_utf8(received.message) else {
+                continue;
+            };
+            let (response, request_id) = match Request::parse(&message) {
+                Ok(request) if request.method == "GET" => (Response::Ok(echo_page(&request)), request.request_id()),
+                Ok(request) => (
+                    Response::Error(ErrorStatus::ServerError, format!("Unknown command: {}", request.method)),
+                    request.request_id(),
+                ),
+                Err(e) => (Response::Error(ErrorStatus::ServerError, e), None),
+            };
+            // Echo the request ID so the browser can match the reply
+            let response = match request_id {
+                Some(request_id) => nymview_protocol::tag_reply(request_id, response.to_string().as_bytes()),
+                None => response.to_string().into_bytes(),
+            };
+
+            // Anonymous requests are answered over their reply SURBs
+            let sent = match (nymview_protocol::reply_address(&message), received.sender_tag) {
+                (Some(reply_to), _) => match reply_to.parse::<mixnet::Recipient>() {
+                    Ok(recipient) => client.send_plain_message(recipient, response).await,
+                    Err(_) => {
+                        eprintln!("Invalid reply address: {}", reply_to);
+                        continue;
+                    }
+                },
+                (None, Some(sender_tag)) => client.send_reply(sender_tag, response).await,
+                // Without a reply address there is no one to answer
+                (None, None) => continue,
+            };
+            if let Err(e) = sent {
+                eprintln!("Error sending response: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn echo_page(request: &Request) -> String {
+    let mut page = format!("# Echo\n\nYou asked for `{}`.\n\n## Headers\n\n", request.path);
+    if request.headers.is_empty() {
+        page.push_str("None sent.\n");
+    }
+    for (name, value) in &request.headers {
+        page.push_str(&format!("- **{}**: {}\n", name, value));
+    }
+    page
+}
@@ -0,0 +1,610 @@
+// Request and response format spoken between NymView and page servers.
+//
+// Request:  "<METHOD> <path> FROM <reply address>", then optional
+//           "<Name>: <value>" header lines, then optionally an empty
+//           line and a body. Headers go only to servers whose answer to
+//           "PING" says they read them (see PONG_WITH_HEADERS); older
+//           servers take everything after FROM as the reply address.
+// Response: "OK\n<body>", "<STATUS>: <message>" (see ErrorStatus) or, for
+//           content that is not markdown, "DATA <content type>\n<bytes>". Replies to requests
+//           with an Accept-Encoding header may be compressed, behind an
+//           "ENCODING <name>" line. An "ID <n>" line comes first when the
+//           request carried a Request-Id header.
+
+use std::fmt;
+
+// nym://<server>/<page>, with the page kept as written (no leading slash)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NymUrl {
+    pub server: String,
+    pub page: String,
+}
+
+impl NymUrl {
+    pub fn parse(url: &str) -> Option<Self> {
+        let rest = url.strip_prefix("nym://")?;
+        let (server, page) = rest.split_once('/').unwrap_or((rest, ""));
+        Some(Self {
+            server: server.to_string(),
+            page: page.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for NymUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "nym://{}/{}", self.server, self.page)
+    }
+}
+
+// Canonical request path: leading slash, no duplicate slashes and no
+// trailing slash except for the root. The query string is left as is.
+pub fn normalize_path(path: &str) -> String {
+    let (path_part, query) = match path.find('?') {
+        Some(pos) => path.split_at(pos),
+        None => (path, ""),
+    };
+
+    let mut normalized = String::with_capacity(path.len() + 1);
+    for segment in path_part.split('/').filter(|segment| !segment.is_empty()) {
+        normalized.push('/');
+        normalized.push_str(segment);
+    }
+    if normalized.is_empty() {
+        normalized.push('/');
+    }
+    normalized.push_str(query);
+    normalized
+}
+
+// Where the answer to a request goes. Searched from the back since the
+// path may itself contain " FROM ".
+pub fn reply_address(message: &str) -> Option<&str> {
+    message
+        .lines()
+        .next()
+        .and_then(|line| line.trim_end().rsplit_once(" FROM "))
+        .map(|(_, from)| from.trim())
+        .filter(|from| !from.is_empty())
+}
+
+// Answer to "PING". Servers that read request headers say so on a second
+// line; servers from before headers answer just "PONG".
+pub const PONG: &str = "PONG";
+pub const PONG_WITH_HEADERS: &str = "PONG\nHeaders: yes";
+
+// Whether a PING answer comes from a server that reads request headers;
+// None for replies that are not a PING answer
+pub fn pong_reads_headers(reply: &[u8]) -> Option<bool> {
+    let reply = std::str::from_utf8(reply).ok()?;
+    let mut lines = reply.lines();
+    if lines.next()?.trim_end() != PONG {
+        return None;
+    }
+    Some(lines.any(|line| line.trim_end() == "Headers: yes"))
+}
+
+// Sent by clients that need to match replies to requests; servers echo
+// the value back with tag_reply()
+pub const REQUEST_ID_HEADER: &str = "Request-Id";
+
+// "ID <n>\n<reply>"
+pub fn tag_reply(request_id: u64, reply: &[u8]) -> Vec<u8> {
+    let mut tagged = format!("ID {}\n", request_id).into_bytes();
+    tagged.extend_from_slice(reply);
+    tagged
+}
+
+// The request ID a reply was tagged with, and the reply without it. Replies
+// from servers that predate IDs come back untouched with None.
+pub fn split_reply_id(message: &[u8]) -> (Option<u64>, &[u8]) {
+    split_first_line(message)
+        .and_then(|(first, rest)| {
+            let id = first.strip_prefix("ID ")?.trim().parse().ok()?;
+            Some((Some(id), rest))
+        })
+        .unwrap_or((None, message))
+}
+
+// Lists the compressions a client can undo, e.g. "zstd"
+pub const ACCEPT_ENCODING_HEADER: &str = "Accept-Encoding";
+pub const ZSTD: &str = "zstd";
+
+// Uploads binary content, e.g. an image, to `path`. Request bodies are
+// text, so the bytes travel base64-encoded and the headers say what they are.
+pub const PUTBIN: &str = "PUTBIN";
+pub const CONTENT_TYPE_HEADER: &str = "Content-Type";
+pub const BODY_ENCODING_HEADER: &str = "Body-Encoding";
+pub const BASE64: &str = "base64";
+
+// "ENCODING <name>\n<compressed reply>"
+pub fn tag_encoding(encoding: &str, compressed: &[u8]) -> Vec<u8> {
+    let mut tagged = format!("ENCODING {}\n", encoding).into_bytes();
+    tagged.extend_from_slice(compressed);
+    tagged
+}
+
+// The compression a reply was sent with, and the still compressed reply.
+// Uncompressed replies come back untouched with None.
+pub fn split_encoding(message: &[u8]) -> (Option<&str>, &[u8]) {
+    split_first_line(message)
+        .and_then(|(first, rest)| Some((Some(first.strip_prefix("ENCODING ")?.trim()), rest)))
+        .unwrap_or((None, message))
+}
+
+// The first line as text, and the bytes after its newline
+fn split_first_line(message: &[u8]) -> Option<(&str, &[u8])> {
+    let end = message.iter().position(|&byte| byte == b'\n')?;
+    let first = std::str::from_utf8(&message[..end]).ok()?;
+    Some((first.trim_end(), &message[end + 1..]))
+}
+
+// What pages are served as; replies in the OK/ERROR format are this
+pub const MARKDOWN_TYPE: &str = "text/markdown";
+pub const DEFAULT_TYPE: &str = "application/octet-stream";
+
+// Content type by file extension, for servers handing out files
+pub fn content_type_for(path: &str) -> &'static str {
+    let extension = path.rsplit_once('.').map_or("", |(_, extension)| extension);
+    match extension.to_ascii_lowercase().as_str() {
+        "md" | "markdown" => MARKDOWN_TYPE,
+        "txt" => "text/plain",
+        "json" => "application/json",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "tar" => "application/x-tar",
+        _ => DEFAULT_TYPE,
+    }
+}
+
+// Text types can be shown as a page; anything else is kept as bytes
+pub fn is_text_type(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or("").trim();
+    essence.starts_with("text/") || essence == "application/json"
+}
+
+// Reply carrying bytes that must reach the browser unchanged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Data {
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+impl Data {
+    pub fn new(content_type: &str, bytes: Vec<u8>) -> Self {
+        Self {
+            content_type: content_type.to_string(),
+            bytes,
+        }
+    }
+
+    // None for replies in the OK/ERROR format
+    pub fn parse(message: &[u8]) -> Option<Self> {
+        let (first, rest) = split_first_line(message)?;
+        let content_type = first.strip_prefix("DATA ")?.trim();
+        if content_type.is_empty() {
+            return None;
+        }
+        Some(Self::new(content_type, rest.to_vec()))
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = format!("DATA {}\n", self.content_type).into_bytes();
+        encoded.extend_from_slice(&self.bytes);
+        encoded
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    // Empty for anonymous requests, which leave out FROM and are answered
+    // over the reply SURBs sent along with them
+    pub reply_to: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn new(method: &str, path: &str, reply_to: &str) -> Self {
+        Self {
+            method: method.to_string(),
+            path: path.to_string(),
+            reply_to: reply_to.to_string(),
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    pub fn get(path: &str, reply_to: &str) -> Self {
+        Self::new("GET", path, reply_to)
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_body(mut self, body: &str) -> Self {
+        self.body = body.to_string();
+        self
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.reply_to.is_empty()
+    }
+
+    // Header names are case-insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn request_id(&self) -> Option<u64> {
+        self.header(REQUEST_ID_HEADER).and_then(|id| id.parse().ok())
+    }
+
+    pub fn accepts_encoding(&self, encoding: &str) -> bool {
+        self.header(ACCEPT_ENCODING_HEADER).is_some_and(|accepted| {
+            accepted.split(',').any(|name| name.trim().eq_ignore_ascii_case(encoding))
+        })
+    }
+
+    // Errors are worded to be sent back as "SERVER_ERROR: <message>"
+    pub fn parse(message: &str) -> Result<Self, String> {
+        let (request_line, rest) = message.split_once('\n').unwrap_or((message, ""));
+        let request_line = request_line.trim_end();
+        let (request, reply_to) = request_line
+            .rsplit_once(" FROM ")
+            .unwrap_or((request_line, ""));
+        let (method, path) = request.split_once(' ').ok_or("Invalid request format")?;
+        if method.is_empty() {
+            return Err("Invalid request format".to_string());
+        }
+
+        let mut parsed = Self::new(method, path.trim(), reply_to.trim());
+        let mut lines = rest.split('\n');
+        for line in lines.by_ref() {
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                break;
+            }
+            // Lines that are not headers are ignored, as servers always did
+            if let Some((name, value)) = line.split_once(':') {
+                parsed.headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        parsed.body = lines.collect::<Vec<_>>().join("\n");
+        Ok(parsed)
+    }
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.path)?;
+        if !self.is_anonymous() {
+            write!(f, " FROM {}", self.reply_to)?;
+        }
+        for (name, value) in &self.headers {
+            write!(f, "\n{}: {}", name, value)?;
+        }
+        if !self.body.is_empty() {
+            write!(f, "\n\n{}", self.body)?;
+        }
+        Ok(())
+    }
+}
+
+// First word of a reply that is neither OK nor DATA
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorStatus {
+    NotFound,
+    ServerError,
+    // The message is where the page is now: a path on the same server or
+    // a nym:// URL
+    Redirect,
+    // The server will not send something this large over the mixnet
+    TooLarge,
+}
+
+impl ErrorStatus {
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::NotFound => "NOT_FOUND",
+            Self::ServerError => "SERVER_ERROR",
+            Self::Redirect => "REDIRECT",
+            Self::TooLarge => "TOO_LARGE",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "NOT_FOUND" => Some(Self::NotFound),
+            // "ERROR" is what servers sent before there were status codes
+            "SERVER_ERROR" | "ERROR" => Some(Self::ServerError),
+            "REDIRECT" => Some(Self::Redirect),
+            "TOO_LARGE" => Some(Self::TooLarge),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    Ok(String),
+    Error(ErrorStatus, String),
+}
+
+impl Response {
+    // None for anything else, e.g. "PONG" or replies from servers that
+    // predate the format
+    pub fn parse(message: &str) -> Option<Self> {
+        if let Some(body) = message.strip_prefix("OK\n") {
+            return Some(Self::Ok(body.to_string()));
+        }
+        if message.trim_end() == "OK" {
+            return Some(Self::Ok(String::new()));
+        }
+        let (code, error) = message.split_once(':')?;
+        let status = ErrorStatus::from_code(code)?;
+        Some(Self::Error(status, error.trim().to_string()))
+    }
+}
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ok(body) => write!(f, "OK\n{}", body),
+            Self::Error(status, message) => write!(f, "{}: {}", status.code(), message),
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+pub mod compression {
+    use super::{split_encoding, tag_encoding, ZSTD};
+    use std::io::Read;
+
+    // Shorter replies fit in a packet or two either way
+    const MIN_SIZE: usize = 512;
+    const LEVEL: i32 = 3;
+    // Larger decompressed replies are refused, so a small message cannot
+    // expand into gigabytes
+    pub const MAX_REPLY_SIZE: usize = 16 * 1024 * 1024;
+
+    // The reply compressed and tagged with its encoding, or None when
+    // compressing would not make it smaller
+    pub fn compress(reply: &[u8]) -> Option<Vec<u8>> {
+        if reply.len() < MIN_SIZE {
+            return None;
+        }
+        let compressed = zstd::bulk::compress(reply, LEVEL).ok()?;
+        let tagged = tag_encoding(ZSTD, &compressed);
+        (tagged.len() < reply.len()).then_some(tagged)
+    }
+
+    // Undoes compress(); replies sent without compression pass through
+    pub fn decompress(message: &[u8]) -> Result<Vec<u8>, String> {
+        match split_encoding(message) {
+            (None, reply) => Ok(reply.to_vec()),
+            (Some(ZSTD), compressed) => {
+                let mut reply = Vec::new();
+                zstd::stream::read::Decoder::with_buffer(compressed)
+                    .and_then(|decoder| decoder.take(MAX_REPLY_SIZE as u64 + 1).read_to_end(&mut reply))
+                    .map_err(|e| format!("Could not decompress the reply: {}", e))?;
+                if reply.len() > MAX_REPLY_SIZE {
+                    return Err("The reply is too large once decompressed".to_string());
+                }
+                Ok(reply)
+            }
+            (Some(encoding), _) => Err(format!("The reply uses an unknown encoding ({})", encoding)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nym_url_round_trips() {
+        let url = NymUrl::parse("nym://server.addr/docs/intro").unwrap();
+        assert_eq!(url.server, "server.addr");
+        assert_eq!(url.page, "docs/intro");
+        assert_eq!(url.to_string(), "nym://server.addr/docs/intro");
+
+        let bare = NymUrl::parse("nym://server.addr").unwrap();
+        assert_eq!(bare.page, "");
+        assert_eq!(NymUrl::parse("https://example.com"), None);
+    }
+
+    #[test]
+    fn normalize_path_collapses_slashes_and_keeps_query() {
+        let cases = [
+            ("", "/"),
+            ("/", "/"),
+            ("//", "/"),
+            ("index", "/index"),
+            ("/index/", "/index"),
+            ("a//b/", "/a/b"),
+            ("///a///b///", "/a/b"),
+            ("/Docs/ReadMe.md", "/Docs/ReadMe.md"),
+            ("/search?q=a//b", "/search?q=a//b"),
+            ("/search/?q=a/", "/search?q=a/"),
+            ("?q=1", "/?q=1"),
+            ("/a%2F/b", "/a%2F/b"),
+        ];
+        for (path, normalized) in cases {
+            assert_eq!(normalize_path(path), normalized, "normalize_path({:?})", path);
+        }
+    }
+
+    #[test]
+    fn request_round_trips() {
+        let request = Request::get("/page", "client.addr")
+            .with_header(REQUEST_ID_HEADER, "7")
+            .with_header(ACCEPT_ENCODING_HEADER, "gzip, zstd")
+            .with_body("line one\n\nline three");
+        let parsed = Request::parse(&request.to_string()).unwrap();
+        assert_eq!(parsed, request);
+        assert_eq!(parsed.request_id(), Some(7));
+        assert!(parsed.accepts_encoding("ZSTD"));
+        assert_eq!(parsed.header("request-id"), Some("7"));
+    }
+
+    #[test]
+    fn request_without_headers_keeps_body() {
+        let request = Request::new("REPORT", "/page", "client.addr").with_body("{}");
+        assert_eq!(request.to_string(), "REPORT /page FROM client.addr\n\n{}");
+        assert_eq!(Request::parse(&request.to_string()).unwrap(), request);
+    }
+
+    #[test]
+    fn request_path_may_contain_from() {
+        let request = Request::get("/talks/ FROM the start", "client.addr");
+        let message = request.to_string();
+        assert_eq!(reply_address(&message), Some("client.addr"));
+        let parsed = Request::parse(&message).unwrap();
+        assert_eq!(parsed.path, "/talks/ FROM the start");
+        assert_eq!(parsed.reply_to, "client.addr");
+    }
+
+    #[test]
+    fn anonymous_request_has_no_from() {
+        let request = Request::get("/page", "").with_header(REQUEST_ID_HEADER, "3");
+        let message = request.to_string();
+        assert_eq!(message, "GET /page\nRequest-Id: 3");
+        assert_eq!(reply_address(&message), None);
+        let parsed = Request::parse(&message).unwrap();
+        assert!(parsed.is_anonymous());
+        assert_eq!(parsed.request_id(), Some(3));
+    }
+
+    #[test]
+    fn request_ignores_lines_that_are_not_headers() {
+        let parsed = Request::parse("GET /page FROM client.addr\r\nnot a header\r\nX-Test: 1\r\n\r\nbody").unwrap();
+        assert_eq!(parsed.headers, vec![("X-Test".to_string(), "1".to_string())]);
+        assert_eq!(parsed.body, "body");
+    }
+
+    #[test]
+    fn request_parse_rejects_bare_words() {
+        assert!(Request::parse("GET").is_err());
+        assert!(Request::parse(" /page").is_err());
+    }
+
+    #[test]
+    fn reply_address_only_reads_the_request_line() {
+        assert_eq!(reply_address("GET /page\nX: a FROM b"), None);
+        assert_eq!(reply_address("GET /page FROM "), None);
+    }
+
+    #[test]
+    fn pong_tells_whether_headers_are_read() {
+        assert_eq!(pong_reads_headers(PONG.as_bytes()), Some(false));
+        assert_eq!(pong_reads_headers(PONG_WITH_HEADERS.as_bytes()), Some(true));
+        assert_eq!(pong_reads_headers(b"OK\nPONG"), None);
+        assert_eq!(pong_reads_headers(b"\xff\xfe"), None);
+    }
+
+    #[test]
+    fn reply_id_round_trips() {
+        let tagged = tag_reply(42, b"OK\nhello");
+        assert_eq!(split_reply_id(&tagged), (Some(42), &b"OK\nhello"[..]));
+        assert_eq!(split_reply_id(b"OK\nhello"), (None, &b"OK\nhello"[..]));
+        assert_eq!(split_reply_id(b"ID x\nOK"), (None, &b"ID x\nOK"[..]));
+    }
+
+    #[test]
+    fn reply_id_with_binary_reply() {
+        let tagged = tag_reply(1, b"DATA image/png\n\x89PNG\xff\x00");
+        assert_eq!(split_reply_id(&tagged), (Some(1), &b"DATA image/png\n\x89PNG\xff\x00"[..]));
+        // A first line that is not text is not an ID
+        assert_eq!(split_reply_id(b"\xffID 1\nOK"), (None, &b"\xffID 1\nOK"[..]));
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        let tagged = tag_encoding(ZSTD, b"\x28\xb5\x2f\xfd\xff");
+        assert_eq!(split_encoding(&tagged), (Some(ZSTD), &b"\x28\xb5\x2f\xfd\xff"[..]));
+        assert_eq!(split_encoding(b"OK\nbody"), (None, &b"OK\nbody"[..]));
+        assert_eq!(split_encoding(b"\xfe\xff\n"), (None, &b"\xfe\xff\n"[..]));
+    }
+
+    #[test]
+    fn data_round_trips_binary() {
+        let data = Data::new("image/png", vec![0x89, b'P', b'N', b'G', 0xff, b'\n', 0x00]);
+        assert_eq!(Data::parse(&data.encode()), Some(data));
+    }
+
+    #[test]
+    fn data_parse_rejects_other_replies() {
+        assert_eq!(Data::parse(b"OK\nDATA text/plain"), None);
+        assert_eq!(Data::parse(b"DATA \nbytes"), None);
+        assert_eq!(Data::parse(b"DATA image/png"), None);
+        assert_eq!(Data::parse(b"\xff\xfe\nDATA image/png"), None);
+    }
+
+    #[test]
+    fn content_types() {
+        assert_eq!(content_type_for("docs/INDEX.MD"), MARKDOWN_TYPE);
+        assert_eq!(content_type_for("photo.JPeG"), "image/jpeg");
+        assert_eq!(content_type_for("no-extension"), DEFAULT_TYPE);
+        assert!(is_text_type("application/json; charset=utf-8"));
+        assert!(!is_text_type("image/png"));
+    }
+
+    #[test]
+    fn response_round_trips() {
+        for response in [
+            Response::Ok("# Title\n\nText".to_string()),
+            Response::Ok(String::new()),
+            Response::Error(ErrorStatus::NotFound, "Page '/x' not found".to_string()),
+            Response::Error(ErrorStatus::Redirect, "nym://other/page".to_string()),
+        ] {
+            assert_eq!(Response::parse(&response.to_string()), Some(response));
+        }
+    }
+
+    #[test]
+    fn response_parse_edge_cases() {
+        assert_eq!(Response::parse("OK"), Some(Response::Ok(String::new())));
+        assert_eq!(
+            Response::parse("ERROR: old server"),
+            Some(Response::Error(ErrorStatus::ServerError, "old server".to_string())),
+        );
+        assert_eq!(Response::parse("OK: not a status line"), None);
+        assert_eq!(Response::parse("PONG"), None);
+        assert_eq!(Response::parse("# A page from an old server"), None);
+    }
+
+    #[test]
+    fn status_codes_round_trip() {
+        assert_eq!(ErrorStatus::from_code("OK"), None);
+        for status in [ErrorStatus::NotFound, ErrorStatus::ServerError, ErrorStatus::Redirect, ErrorStatus::TooLarge] {
+            assert_eq!(ErrorStatus::from_code(status.code()), Some(status));
+        }
+        assert_eq!(ErrorStatus::from_code("TEAPOT"), None);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compression_round_trips() {
+        let reply = "OK\n".to_string() + &"A fairly repetitive page. ".repeat(100);
+        let compressed = compression::compress(reply.as_bytes()).unwrap();
+        assert!(compressed.len() < reply.len());
+        assert_eq!(compression::decompress(&compressed).unwrap(), reply.as_bytes());
+        assert_eq!(compression::compress(b"OK\nshort"), None);
+        assert_eq!(compression::decompress(b"OK\nshort").unwrap(), b"OK\nshort");
+        assert!(compression::decompress(b"ENCODING gzip\n\x1f\x8b").is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use nym_sdk::mixnet::Recipient;
+
+// What the address bar input turned out to be
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inference {
+    // Use the input as typed
+    Unchanged,
+    // Input rewritten to a canonical nym:// URL
+    Corrected(String),
+    // Clearnet URL that cannot be fetched over the mixnet
+    External(String),
+}
+
+// Pure inference over messy address bar input such as "nym:/server/page",
+// "nym//server" or a bare recipient address
+pub fn infer(input: &str) -> Inference {
+    let trimmed = input.trim();
+    let lower = trimmed.to_ascii_lowercase();
+    if lower.starts_with("http://") || lower.starts_with("https://") {
+        return Inference::External(trimmed.to_string());
+    }
+
+    if let Some(rest) = strip_nym_scheme(trimmed) {
+        if rest.is_empty() {
+            return Inference::Unchanged;
+        }
+        let url = format!("nym://{}", rest);
+        return if url == trimmed {
+            Inference::Unchanged
+        } else {
+            Inference::Corrected(url)
+        };
+    }
+
+    let (server, page) = trimmed.split_once('/').unwrap_or((trimmed, ""));
+    if looks_like_recipient(server) {
+        return Inference::Corrected(format!("nym://{}/{}", server, page));
+    }
+
+    Inference::Unchanged
+}
+
+// "nym" followed by any mix of ':' and '/' with a colon or at least two
+// slashes ("nym:/", "nym//", "NYM:///")
+fn strip_nym_scheme(input: &str) -> Option<&str> {
+    let scheme = input.get(..3)?;
+    if !scheme.eq_ignore_ascii_case("nym") {
+        return None;
+    }
+    let rest = &input[3..];
+    let stripped = rest.trim_start_matches([':', '/']);
+    let separator = &rest[..rest.len() - stripped.len()];
+    // "nymphs" and "nym/about" are page paths, not a scheme
+    if !separator.contains(':') && separator.len() < 2 {
+        return None;
+    }
+    Some(stripped)
+}
+
+pub fn looks_like_recipient(text: &str) -> bool {
+    text.contains('.') && text.contains('@') && text.parse::<Recipient>().is_ok()
+}
+
+// Removes the named query parameters from a link target and returns the
+// result with the number removed. Only the query is rewritten: the path
+// and fragment stay as they are, and the remaining parameters keep their
+// order and encoding. Names are matched after percent-decoding, so an
+// encoded "%26" inside a value never splits it.
+pub fn strip_query_params(target: &str, names: &[String]) -> (String, usize) {
+    let (without_fragment, fragment) = match target.find('#') {
+        Some(index) => target.split_at(index),
+        None => (target, ""),
+    };
+    let Some((path, query)) = without_fragment.split_once('?') else {
+        return (target.to_string(), 0);
+    };
+
+    let mut removed = 0;
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|param| {
+            let name = param.split_once('=').map_or(*param, |(name, _)| name);
+            let strip = !name.is_empty() && names.iter().any(|n| *n == percent_decode(name));
+            if strip {
+                removed += 1;
+            }
+            !strip
+        })
+        .collect();
+
+    if removed == 0 {
+        return (target.to_string(), 0);
+    }
+    let mut result = path.to_string();
+    if kept.iter().any(|param| !param.is_empty()) {
+        result.push('?');
+        result.push_str(&kept.join("&"));
+    }
+    result.push_str(fragment);
+    (result, removed)
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVER: &str = "DguTcdkWWtDyUFLvQxRdcA8qZhardhE1ZXy1YCC7Zfmq.Dxreouj5RhQqMb3ZaAxgXFdGkmfbDKwk457FdeHGKmQQ@4kjgWmFU1tcGAZYRZR57yFuVAexjLbJ5M7jvo3X5Hkcf";
+
+    #[test]
+    fn messy_input_is_inferred() {
+        let corrected = |rest: &str| Inference::Corrected(format!("nym://{}{}", SERVER, rest));
+        let cases = [
+            (format!("nym://{}/page.md", SERVER), Inference::Unchanged),
+            (format!("nym://{}/", SERVER), Inference::Unchanged),
+            (format!("  nym://{}/  ", SERVER), Inference::Unchanged),
+            (format!("nym:/{}/page.md", SERVER), corrected("/page.md")),
+            (format!("nym//{}", SERVER), corrected("")),
+            (format!("nym:{}/a/b", SERVER), corrected("/a/b")),
+            (format!("NYM:///{}/x", SERVER), corrected("/x")),
+            (format!("Nym://{}", SERVER), corrected("")),
+            (format!("nym:://{}/", SERVER), corrected("/")),
+            (SERVER.to_string(), corrected("/")),
+            (format!("{}/page.md", SERVER), corrected("/page.md")),
+            (format!(" {}/a/b?q=1 ", SERVER), corrected("/a/b?q=1")),
+            ("nym://".to_string(), Inference::Unchanged),
+            ("nym:".to_string(), Inference::Unchanged),
+            ("http://example.com".to_string(), Inference::External("http://example.com".to_string())),
+            (" HTTPS://Example.com/x ".to_string(), Inference::External("HTTPS://Example.com/x".to_string())),
+            ("ftp://example.com".to_string(), Inference::Unchanged),
+            ("nymphs".to_string(), Inference::Unchanged),
+            ("nym/about".to_string(), Inference::Unchanged),
+            ("/about".to_string(), Inference::Unchanged),
+            ("example.com/page".to_string(), Inference::Unchanged),
+            ("".to_string(), Inference::Unchanged),
+            ("   ".to_string(), Inference::Unchanged),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(infer(&input), expected, "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn nym_scheme_needs_a_colon_or_two_slashes() {
+        let cases = [
+            ("nym://server", Some("server")),
+            ("nym:server", Some("server")),
+            ("nym//server", Some("server")),
+            ("NyM:/server/page", Some("server/page")),
+            ("nym:///", Some("")),
+            ("nym:/é", Some("é")),
+            ("nym/server", None),
+            ("nymphs", None),
+            ("nym", None),
+            ("ny", None),
+            ("", None),
+            ("ñym://server", None),
+            ("xnym://server", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(strip_nym_scheme(input), expected, "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn only_named_query_parameters_are_stripped() {
+        let names: Vec<String> = ["utm_source", "fbclid", "ref"].iter().map(|name| name.to_string()).collect();
+        let cases = [
+            ("/page", "/page", 0),
+            ("/page?a=1&b=2", "/page?a=1&b=2", 0),
+            ("/page?utm_source=x&a=1&b=2", "/page?a=1&b=2", 1),
+            ("/page?b=2&utm_source=x&a=1&fbclid=y", "/page?b=2&a=1", 2),
+            ("/page?utm_source=x", "/page", 1),
+            ("/page?utm_source=x&fbclid=y#top", "/page#top", 2),
+            ("/page?a=1&utm_source#top", "/page?a=1#top", 1),
+            ("/page?a=%20+&ref=x", "/page?a=%20+", 1),
+            ("nym://server.addr@gateway/page?b=2&utm_source=x&a=1", "nym://server.addr@gateway/page?b=2&a=1", 1),
+            // The path and fragment are never rewritten
+            ("/utm_source=x/page", "/utm_source=x/page", 0),
+            ("/ref/page?ref=1", "/ref/page", 1),
+            ("/page#utm_source=x", "/page#utm_source=x", 0),
+            ("/page#top?utm_source=x", "/page#top?utm_source=x", 0),
+            // Encoded separators stay inside their value; encoded names match
+            ("/page?a=x%26utm_source%3Dy&b=2", "/page?a=x%26utm_source%3Dy&b=2", 0),
+            ("/page?utm%5Fsource=x&a=1", "/page?a=1", 1),
+            ("/page?UTM_SOURCE=x", "/page?UTM_SOURCE=x", 0),
+            ("/page?=x&a=1", "/page?=x&a=1", 0),
+        ];
+        for (target, expected, removed) in cases {
+            assert_eq!(strip_query_params(target, &names), (expected.to_string(), removed), "target {:?}", target);
+        }
+    }
+
+    #[test]
+    fn percent_decoding_leaves_malformed_escapes() {
+        assert_eq!(percent_decode("utm%5Fsource"), "utm_source");
+        assert_eq!(percent_decode("a+b"), "a b");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%4"), "%zz%4");
+        assert_eq!(percent_decode("%C3%A9"), "é");
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use crate::storage::Storage;
+
+pub const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub title: String,
+    pub url: String,
+    // Empty for bookmarks outside any folder
+    #[serde(default)]
+    pub folder: String,
+}
+
+// Starred nym:// pages, in the order they were added
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bookmarks {
+    pub bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage.read(BOOKMARKS_FILE)
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &dyn Storage) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Could not serialize bookmarks: {}", e))?;
+        storage.write(BOOKMARKS_FILE, &content)
+    }
+
+    pub fn position(&self, url: &str) -> Option<usize> {
+        self.bookmarks.iter().position(|bookmark| bookmark.url == url)
+    }
+
+    // A page is bookmarked at most once
+    pub fn add(&mut self, title: &str, url: &str) {
+        if self.position(url).is_none() {
+            self.bookmarks.push(Bookmark {
+                title: title.trim().to_string(),
+                url: url.to_string(),
+                folder: String::new(),
+            });
+        }
+    }
+
+    // Named folders in alphabetical order
+    pub fn folders(&self) -> Vec<String> {
+        let mut folders: Vec<String> = self.bookmarks
+            .iter()
+            .map(|bookmark| bookmark.folder.clone())
+            .filter(|folder| !folder.is_empty())
+            .collect();
+        folders.sort_by_key(|folder| folder.to_lowercase());
+        folders.dedup();
+        folders
+    }
+}
@@ -0,0 +1,124 @@
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::config;
+use crate::metrics::Metrics;
+use crate::settings::Settings;
+use crate::storage;
+
+// Everything a bug report needs, without revealing which servers the user
+// talks to: server addresses, paths and local file names are replaced by
+// counts or flags.
+pub fn build_report(settings: &Settings, metrics: &Metrics, connection_status: &str) -> Result<String, String> {
+    let mut settings_value = serde_json::to_value(settings)
+        .map_err(|e| format!("Could not serialize settings: {}", e))?;
+    redact_settings(&mut settings_value);
+
+    let report = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "platform": {
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+        },
+        "connection_status": connection_status,
+        "metrics": {
+            "requests_sent": metrics.requests_sent,
+            "replies_received": metrics.replies_received,
+            "send_failures": metrics.send_failures,
+            "timeouts": metrics.timeouts,
+            "bytes_out": metrics.bytes_out,
+            "bytes_in": metrics.bytes_in,
+            "average_latency_ms": metrics.average_latency().map(|latency| latency.as_millis() as u64),
+        },
+        "settings": settings_value,
+    });
+
+    serde_json::to_string_pretty(&report).map_err(|e| format!("Could not serialize report: {}", e))
+}
+
+fn redact_settings(settings: &mut Value) {
+    let Some(settings) = settings.as_object_mut() else {
+        return;
+    };
+
+    for key in ["landing_paths", "request_templates", "request_formats", "header_servers"] {
+        if let Some(value) = settings.get_mut(key) {
+            *value = redacted_count(value);
+        }
+    }
+    if let Some(filter) = settings.get_mut("server_filter").and_then(Value::as_object_mut) {
+        for key in ["allowed", "denied"] {
+            if let Some(value) = filter.get_mut(key) {
+                *value = redacted_count(value);
+            }
+        }
+    }
+    if let Some(font) = settings.get_mut("content_font") {
+        *font = Value::Bool(!font.is_null());
+    }
+    // The client ID links requests to one install, like a token
+    if let Some(client_id) = settings.get_mut("client_id") {
+        *client_id = Value::String("<redacted>".to_string());
+    }
+    if let Some(address) = settings.get_mut("report_address") {
+        *address = Value::Bool(address.as_str().is_some_and(|address| !address.is_empty()));
+    }
+}
+
+fn redacted_count(value: &Value) -> Value {
+    let count = match value {
+        Value::Array(items) => items.len(),
+        Value::Object(items) => items.len(),
+        _ => 0,
+    };
+    Value::String(format!("<{} entries redacted>", count))
+}
+
+// Blocking; run off the UI thread
+pub fn write_report(report: &str) -> Result<PathBuf, String> {
+    let config_dir = config::ensure_config_dir("mixnet_browser")
+        .map_err(|e| format!("Could not create config directory: {}", e))?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let path = config_dir.join(format!("diagnostics-{}.json", timestamp));
+    storage::write_atomically(&path, report.as_bytes())
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server_filter::FilterMode;
+    use crate::templates::RequestTemplate;
+
+    #[test]
+    fn the_report_holds_no_secrets_or_addresses() {
+        let mut settings = Settings {
+            client_id: "secret-client-id".to_string(),
+            report_address: "secret-collector.addr@gateway".to_string(),
+            content_font: Some("/home/secret-user/font.ttf".to_string()),
+            ..Settings::default()
+        };
+        settings.landing_paths.insert("secret-landing.addr@gateway".to_string(), "/secret-path".to_string());
+        settings.request_templates.insert(
+            "secret-template.addr@gateway".to_string(),
+            vec![RequestTemplate { name: "secret-name".to_string(), path: "/secret-template".to_string() }],
+        );
+        settings.header_servers.insert("secret-header.addr@gateway".to_string());
+        settings.server_filter.mode = FilterMode::Deny;
+        settings.server_filter.allowed.push("secret-allowed.addr@gateway".to_string());
+        settings.server_filter.denied.push("secret-denied.addr@gateway".to_string());
+
+        let report = build_report(&settings, &Metrics::default(), "Connected").unwrap();
+        assert!(!report.contains("secret"), "{}", report);
+
+        let report: Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(report["settings"]["header_servers"], "<1 entries redacted>");
+        assert_eq!(report["settings"]["server_filter"]["denied"], "<1 entries redacted>");
+        assert_eq!(report["settings"]["report_address"], true);
+        assert_eq!(report["connection_status"], "Connected");
+    }
+}
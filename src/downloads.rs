@@ -0,0 +1,125 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use nymview_protocol::NymUrl;
+use crate::config;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadState {
+    // The whole file has arrived; only the disk write is left
+    Saving,
+    Done(PathBuf),
+    Failed(String),
+}
+
+// A reply that is not a page, saved to disk at the user's request
+#[derive(Debug, Clone)]
+pub struct Download {
+    pub id: u64,
+    pub url: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: u64,
+    pub state: DownloadState,
+}
+
+impl Download {
+    pub fn is_finished(&self) -> bool {
+        self.state != DownloadState::Saving
+    }
+}
+
+// The system's download folder, or one below NymView's config directory
+pub fn downloads_dir() -> PathBuf {
+    dirs::download_dir().unwrap_or_else(|| config::get_config_dir("downloads"))
+}
+
+// Servers send no file name, so it comes from the last segment of the URL.
+// Anything that could leave the folder or trip up a file system is
+// replaced, and a missing extension is taken from the content type.
+pub fn file_name_for(url: &str, content_type: &str) -> String {
+    let page = NymUrl::parse(url).map_or(String::new(), |url| url.page);
+    let path = page.split(['?', '#']).next().unwrap_or("");
+    let last = path.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    let mut name: String = last
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    name = name.trim_start_matches('.').to_string();
+    if name.is_empty() {
+        name = "download".to_string();
+    }
+    if !name.contains('.') {
+        if let Some(extension) = extension_for(content_type) {
+            name = format!("{}.{}", name, extension);
+        }
+    }
+    name
+}
+
+fn extension_for(content_type: &str) -> Option<&'static str> {
+    let essence = content_type.split(';').next().unwrap_or("").trim();
+    match essence {
+        "image/png" => Some("png"),
+        "image/jpeg" => Some("jpg"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        "image/svg+xml" => Some("svg"),
+        "application/pdf" => Some("pdf"),
+        "application/zip" => Some("zip"),
+        "application/gzip" => Some("gz"),
+        "application/x-tar" => Some("tar"),
+        _ => None,
+    }
+}
+
+// "name.zip", then "name (1).zip", "name (2).zip", …
+fn candidate_path(dir: &Path, file_name: &str, counter: u32) -> PathBuf {
+    if counter == 0 {
+        return dir.join(file_name);
+    }
+    let (stem, extension) = match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (file_name, String::new()),
+    };
+    dir.join(format!("{} ({}){}", stem, counter, extension))
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+    PathBuf::from(partial)
+}
+
+// Blocking. Bytes go to "<name>.part", which takes the final name once
+// complete, so an interrupted download never looks finished. Neither file
+// ever replaces one that exists, even one created meanwhile.
+pub fn save(dir: &Path, file_name: &str, bytes: &[u8]) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+
+    let mut counter = 0;
+    let (partial, mut file) = loop {
+        let partial = partial_path(&candidate_path(dir, file_name, counter));
+        match OpenOptions::new().write(true).create_new(true).open(&partial) {
+            Ok(file) => break (partial, file),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => counter += 1,
+            Err(e) => return Err(format!("Could not save {}: {}", partial.display(), e)),
+        }
+    };
+    let written = file.write_all(bytes).and_then(|_| file.sync_all());
+    drop(file);
+
+    let finished = written.and_then(|_| loop {
+        let path = candidate_path(dir, file_name, counter);
+        // Unlike a rename, a hard link fails rather than replace the target
+        match fs::hard_link(&partial, &path) {
+            Ok(()) => break Ok(path),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => counter += 1,
+            // File systems without hard links
+            Err(_) if !path.exists() => break fs::rename(&partial, &path).map(|_| path),
+            Err(e) => break Err(e),
+        }
+    });
+    let _ = fs::remove_file(&partial);
+    finished.map_err(|e| format!("Could not save {}: {}", file_name, e))
+}
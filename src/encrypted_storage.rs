@@ -0,0 +1,185 @@
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use crate::storage::Storage;
+
+// Kept in plain text next to the encrypted documents; its presence means
+// a passphrase is needed at startup
+pub const KEY_FILE: &str = "encryption.json";
+const PREFIX: &str = "nymview-encrypted-v1:";
+const CHECK_VALUE: &[u8] = b"NymView";
+const NONCE_LEN: usize = 12;
+
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    salt: String,
+    // CHECK_VALUE encrypted with the derived key, to recognise a wrong passphrase
+    check: String,
+}
+
+// Storage decorator that encrypts every document with a key derived from
+// the user's passphrase (Argon2id + ChaCha20-Poly1305). Documents written
+// before encryption was enabled are still readable and get encrypted on
+// their next write.
+pub struct EncryptedStorage {
+    inner: Arc<dyn Storage>,
+    cipher: ChaCha20Poly1305,
+}
+
+pub fn is_enabled(inner: &dyn Storage) -> bool {
+    inner.read(KEY_FILE).is_some()
+}
+
+impl EncryptedStorage {
+    // Fails with a user-facing message, e.g. for a wrong passphrase
+    pub fn unlock(inner: Arc<dyn Storage>, passphrase: &str) -> Result<Self, String> {
+        let key_file: KeyFile = inner.read(KEY_FILE)
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .ok_or("Encryption key file is missing or damaged")?;
+        let salt = STANDARD.decode(&key_file.salt)
+            .map_err(|_| "Encryption key file is damaged".to_string())?;
+
+        let storage = Self {
+            inner,
+            cipher: derive_cipher(passphrase, &salt)?,
+        };
+        match storage.decrypt(&key_file.check) {
+            Some(check) if check == CHECK_VALUE => Ok(storage),
+            _ => Err("Wrong passphrase".to_string()),
+        }
+    }
+
+    // Sets up a new key. Anything encrypted under a previous key becomes
+    // unreadable, which is also how "start fresh" discards old data.
+    pub fn enable(inner: Arc<dyn Storage>, passphrase: &str) -> Result<Self, String> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let storage = Self {
+            inner,
+            cipher: derive_cipher(passphrase, &salt)?,
+        };
+
+        let key_file = KeyFile {
+            salt: STANDARD.encode(salt),
+            check: storage.encrypt(CHECK_VALUE)?,
+        };
+        let content = serde_json::to_string_pretty(&key_file)
+            .map_err(|e| format!("Could not serialize encryption key file: {}", e))?;
+        storage.inner.write(KEY_FILE, &content)?;
+        Ok(storage)
+    }
+
+    // Documents are left as they are; callers rewrite them in plain text
+    pub fn disable(inner: &dyn Storage) -> Result<(), String> {
+        inner.remove(KEY_FILE)
+    }
+
+    fn encrypt(&self, plaintext: &[u8]) -> Result<String, String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, plaintext)
+            .map_err(|_| "Encryption failed".to_string())?;
+        let mut blob = nonce.to_vec();
+        blob.extend(ciphertext);
+        Ok(STANDARD.encode(blob))
+    }
+
+    fn decrypt(&self, encoded: &str) -> Option<Vec<u8>> {
+        let blob = STANDARD.decode(encoded.trim()).ok()?;
+        if blob.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+        self.cipher.decrypt(Nonce::from_slice(nonce), ciphertext).ok()
+    }
+}
+
+fn derive_cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305, String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Could not derive encryption key: {}", e))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+impl Storage for EncryptedStorage {
+    fn read(&self, name: &str) -> Option<String> {
+        self.read_checked(name).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            None
+        })
+    }
+
+    // A damaged or differently keyed document is an error, not a missing one
+    fn read_checked(&self, name: &str) -> Result<Option<String>, String> {
+        let Some(content) = self.inner.read(name) else {
+            return Ok(None);
+        };
+        match content.strip_prefix(PREFIX) {
+            Some(encoded) => self.decrypt(encoded)
+                .and_then(|plaintext| String::from_utf8(plaintext).ok())
+                .map(Some)
+                .ok_or_else(|| format!("{} could not be decrypted", name)),
+            None => Ok(Some(content)),
+        }
+    }
+
+    // A document that cannot be decrypted is not overwritten, so it is
+    // not lost to the defaults loaded in its place; the self-check offers
+    // to set it aside
+    fn write(&self, name: &str, content: &str) -> Result<(), String> {
+        if let Err(e) = self.read_checked(name) {
+            return Err(format!("{}; not overwriting it", e));
+        }
+        let encoded = self.encrypt(content.as_bytes())?;
+        self.inner.write(name, &format!("{}{}", PREFIX, encoded))
+    }
+
+    fn remove(&self, name: &str) -> Result<(), String> {
+        self.inner.remove(name)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), String> {
+        self.inner.rename(from, to)
+    }
+
+    // Encrypted documents cannot be edited in other programs
+    fn path(&self, _name: &str) -> Option<PathBuf> {
+        None
+    }
+
+    fn is_encrypted(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    #[test]
+    fn an_undecryptable_document_is_an_error_and_kept() {
+        let inner: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        let storage = EncryptedStorage::enable(inner.clone(), "passphrase").unwrap();
+        storage.write("settings.json", "{}").unwrap();
+        assert_eq!(storage.read_checked("settings.json"), Ok(Some("{}".to_string())));
+
+        // Cut off, as by a crash in the middle of a write
+        let stored = inner.read("settings.json").unwrap();
+        inner.write("settings.json", &stored[..stored.len() - 8]).unwrap();
+        assert!(storage.read_checked("settings.json").is_err());
+        assert_eq!(storage.read("settings.json"), None);
+        assert!(storage.write("settings.json", "{\"defaults\": true}").is_err());
+
+        storage.rename("settings.json", "settings.json.broken").unwrap();
+        assert_eq!(storage.read_checked("settings.json"), Ok(None));
+        storage.write("settings.json", "{}").unwrap();
+        assert_eq!(storage.read("settings.json").as_deref(), Some("{}"));
+    }
+}
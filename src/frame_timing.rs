@@ -0,0 +1,101 @@
+use egui::{Color32, Pos2, Stroke};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const FRAMES: usize = 120;
+const REPORT_WINDOW: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy)]
+pub enum Phase {
+    Messages,
+    Content,
+    Panels,
+}
+
+const PHASES: [(&str, Color32); 3] = [
+    ("messages", Color32::from_rgb(90, 160, 255)),
+    ("content", Color32::from_rgb(80, 200, 120)),
+    ("panels", Color32::from_rgb(230, 160, 60)),
+];
+
+// Time spent per frame in the phases of the browser's show(), for
+// diagnosing stutter. While disabled, start() returns None and nothing is
+// measured.
+#[derive(Debug, Clone, Default)]
+pub struct FrameTimings {
+    pub enabled: bool,
+    current: [Duration; 3],
+    frames: VecDeque<(Instant, [Duration; 3])>,
+}
+
+impl FrameTimings {
+    pub fn start(&self) -> Option<Instant> {
+        self.enabled.then(Instant::now)
+    }
+
+    pub fn record(&mut self, phase: Phase, started: Option<Instant>) {
+        if let Some(started) = started {
+            self.current[phase as usize] += started.elapsed();
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        if !self.enabled {
+            self.frames.clear();
+            return;
+        }
+        if self.frames.len() == FRAMES {
+            self.frames.pop_front();
+        }
+        self.frames.push_back((Instant::now(), std::mem::take(&mut self.current)));
+    }
+
+    // Milliseconds per phase for the last few seconds, one frame per line
+    pub fn report(&self) -> String {
+        let mut report = format!("frame_ago_ms {}\n", PHASES.map(|(name, _)| name).join(" "));
+        let now = Instant::now();
+        for (at, phases) in self.frames.iter().filter(|(at, _)| now - *at <= REPORT_WINDOW) {
+            report.push_str(&format!("{}", (now - *at).as_millis()));
+            for phase in phases {
+                report.push_str(&format!(" {:.2}", phase.as_secs_f64() * 1000.0));
+            }
+            report.push('\n');
+        }
+        report
+    }
+
+    // Stacked sparkline of the last FRAMES frames, 16.7 ms marked
+    pub fn show(&self, ctx: &egui::Context) {
+        egui::Window::new("Frame timings")
+            .resizable(false)
+            .default_pos(ctx.content_rect().right_top() + egui::vec2(-300.0, 40.0))
+            .show(ctx, |ui| {
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(FRAMES as f32 * 2.0, 80.0), egui::Sense::hover());
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+                // Full height is two 60 Hz frames
+                let scale = rect.height() / 33.3;
+                let budget_y = rect.bottom() - 16.7 * scale;
+                painter.hline(rect.x_range(), budget_y, Stroke::new(1.0, Color32::RED));
+
+                for (index, (_, phases)) in self.frames.iter().enumerate() {
+                    let x = rect.left() + index as f32 * 2.0;
+                    let mut y = rect.bottom();
+                    for (phase, (_, color)) in phases.iter().zip(PHASES) {
+                        let height = phase.as_secs_f32() * 1000.0 * scale;
+                        painter.line_segment([Pos2::new(x, y), Pos2::new(x, y - height)], Stroke::new(2.0, color));
+                        y -= height;
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    for (name, color) in PHASES {
+                        ui.colored_label(color, name);
+                    }
+                });
+                if ui.button("Copy last 5 seconds").clicked() {
+                    ui.ctx().copy_text(self.report());
+                }
+            });
+    }
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use crate::storage::Storage;
+
+pub const STATS_FILE: &str = "gateway_latency.json";
+const MAX_SESSIONS: usize = 50;
+
+// Average request round trip of one connection, by the gateway it used
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLatency {
+    pub gateway: String,
+    pub average_ms: u64,
+    pub samples: u32,
+}
+
+// Per-session latency history, kept across runs so a slow gateway can be
+// compared with earlier connections
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GatewayStats {
+    pub sessions: Vec<SessionLatency>,
+}
+
+impl GatewayStats {
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage.read(STATS_FILE)
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &dyn Storage) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Could not serialize gateway stats: {}", e))?;
+        storage.write(STATS_FILE, &content)
+    }
+
+    pub fn start_session(&mut self, client_address: &str) {
+        // Sessions that never completed a request say nothing about the gateway
+        self.sessions.retain(|session| session.samples > 0);
+        self.sessions.push(SessionLatency {
+            gateway: gateway_of(client_address).to_string(),
+            average_ms: 0,
+            samples: 0,
+        });
+        if self.sessions.len() > MAX_SESSIONS {
+            self.sessions.remove(0);
+        }
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        if let Some(session) = self.sessions.last_mut() {
+            let total = session.average_ms * session.samples as u64 + latency.as_millis() as u64;
+            session.samples += 1;
+            session.average_ms = total / session.samples as u64;
+        }
+    }
+
+    // Whether any earlier session averaged below `limit`
+    pub fn had_session_faster_than(&self, limit: Duration) -> bool {
+        let Some((_, earlier)) = self.sessions.split_last() else {
+            return false;
+        };
+        earlier
+            .iter()
+            .any(|session| session.samples > 0 && Duration::from_millis(session.average_ms) < limit)
+    }
+}
+
+// Nym addresses have the form "<identity>.<encryption key>@<gateway>"
+pub fn gateway_of(client_address: &str) -> &str {
+    client_address
+        .rsplit_once('@')
+        .map_or(client_address, |(_, gateway)| gateway)
+}
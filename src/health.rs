@@ -0,0 +1,324 @@
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::bookmarks;
+use crate::config;
+use crate::encrypted_storage;
+use crate::gateway_stats;
+use crate::history;
+use crate::mixnet_browser;
+use crate::report;
+use crate::settings::{self, Settings};
+use crate::storage::Storage;
+
+// Checks that take longer are reported as timed out
+pub const TIME_LIMIT: Duration = Duration::from_secs(2);
+
+// 2024-01-01; a clock before this has never been set
+const EARLIEST_PLAUSIBLE_TIME: u64 = 1_704_067_200;
+const CLOCK_TOLERANCE: u64 = 3600;
+
+// The key files the SDK's on-disk storage writes for a persistent Nym
+// address; the client cannot connect with only some of them
+const CLIENT_KEY_FILES: [&str; 5] = [
+    "private_identity.pem",
+    "public_identity.pem",
+    "private_encryption.pem",
+    "public_encryption.pem",
+    "ack_key.pem",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Degraded,
+}
+
+// What the health window can do about a degraded check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fix {
+    // Keep the unreadable document as "<name>.broken" and start it fresh
+    SetAside(&'static str),
+    // Turn off the persistent Nym address; the stored keys are left alone
+    UseEphemeralIdentity,
+    // Remove the saved back/forward history
+    DiscardSession,
+}
+
+impl Fix {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::SetAside(_) => "Keep a copy and start fresh",
+            Self::UseEphemeralIdentity => "Use a new address on each connection",
+            Self::DiscardSession => "Discard the saved session",
+        }
+    }
+
+    pub fn apply(&self, storage: &dyn Storage, settings: &mut Settings) -> Result<(), String> {
+        match self {
+            Self::SetAside(name) => storage.rename(name, &format!("{}.broken", name)),
+            Self::UseEphemeralIdentity => {
+                settings.persistent_identity = false;
+                settings.save(storage)
+            }
+            Self::DiscardSession => storage.remove(history::HISTORY_FILE),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub explanation: String,
+    pub fix: Option<Fix>,
+}
+
+impl Check {
+    fn ok(name: &'static str, explanation: impl Into<String>) -> Self {
+        Self { name, status: Status::Ok, explanation: explanation.into(), fix: None }
+    }
+
+    fn degraded(name: &'static str, explanation: impl Into<String>, fix: Option<Fix>) -> Self {
+        Self { name, status: Status::Degraded, explanation: explanation.into(), fix }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HealthReport {
+    pub checks: Vec<Check>,
+}
+
+impl HealthReport {
+    pub fn is_degraded(&self) -> bool {
+        self.checks.iter().any(|check| check.status == Status::Degraded)
+    }
+
+    pub fn timed_out() -> Self {
+        Self {
+            checks: vec![Check::degraded(
+                "Self-check",
+                format!("Did not finish within {} seconds; the disk may be slow or unavailable.", TIME_LIMIT.as_secs()),
+                None,
+            )],
+        }
+    }
+}
+
+// Blocking; run off the UI thread
+pub fn run(storage: &dyn Storage) -> HealthReport {
+    let checks = vec![
+        check_config_dir(storage),
+        check_document::<settings::Settings>(storage, "Settings", settings::SETTINGS_FILE),
+        check_document::<gateway_stats::GatewayStats>(storage, "Gateway latency history", gateway_stats::STATS_FILE),
+        check_document::<report::SentReports>(storage, "Sent reports", report::REPORTS_FILE),
+        check_document::<bookmarks::Bookmarks>(storage, "Bookmarks", bookmarks::BOOKMARKS_FILE),
+        check_session(storage),
+        check_encryption_key(storage),
+        check_client_identity(
+            Settings::load(storage).persistent_identity,
+            &config::get_config_dir(mixnet_browser::CLIENT_IDENTITY_DIR),
+        ),
+        check_clock(storage),
+    ];
+    HealthReport { checks }
+}
+
+fn check_config_dir(storage: &dyn Storage) -> Check {
+    const NAME: &str = "Config directory";
+    if storage.path(settings::SETTINGS_FILE).is_none() && !storage.is_encrypted() {
+        return Check::ok(NAME, "Nothing is written to disk in this session.");
+    }
+    let dir = config::get_config_dir("mixnet_browser");
+    let probe = dir.join(".write-test");
+    let result = fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(&probe, b"ok"))
+        .and_then(|_| fs::remove_file(&probe));
+    match result {
+        Ok(()) => Check::ok(NAME, format!("{} is writable.", dir.display())),
+        Err(e) => Check::degraded(
+            NAME,
+            format!("{} is not writable ({}); settings changes will not be saved.", dir.display(), e),
+            None,
+        ),
+    }
+}
+
+// Documents that fail to load are silently replaced by defaults at
+// startup. They are read as the type they are loaded as, since valid JSON
+// of the wrong shape is dropped just the same.
+fn check_document<T: DeserializeOwned>(storage: &dyn Storage, name: &'static str, document: &'static str) -> Check {
+    match storage.read_checked(document) {
+        Ok(None) => Check::ok(name, "Not created yet."),
+        Err(e) => Check::degraded(
+            name,
+            format!("{}; defaults are in use and changes are not saved until it is set aside.", e),
+            Some(Fix::SetAside(document)),
+        ),
+        Ok(Some(content)) => match serde_json::from_str::<T>(&content) {
+            Ok(_) => Check::ok(name, "Readable."),
+            Err(e) => Check::degraded(
+                name,
+                format!("{} could not be read ({}); defaults are in use and will overwrite it.", document, e),
+                Some(Fix::SetAside(document)),
+            ),
+        },
+    }
+}
+
+// The back/forward history restored at startup
+fn check_session(storage: &dyn Storage) -> Check {
+    let mut check = check_document::<history::SavedHistory>(storage, "Saved session", history::HISTORY_FILE);
+    if check.status == Status::Degraded {
+        check.fix = Some(Fix::DiscardSession);
+    }
+    check
+}
+
+fn check_encryption_key(storage: &dyn Storage) -> Check {
+    const NAME: &str = "Encryption key";
+    match storage.read(encrypted_storage::KEY_FILE) {
+        None => Check::ok(NAME, "Local data is not encrypted."),
+        Some(_) if storage.is_encrypted() => Check::ok(NAME, "Unlocked."),
+        // Only reachable when the key file appeared after startup
+        Some(_) => Check::degraded(
+            NAME,
+            "A key file exists but this session is not encrypted; restart NymView to unlock.",
+            None,
+        ),
+    }
+}
+
+// The SDK's own key storage, used only with a persistent Nym address.
+// It is read as text here; the SDK is the only one to parse the keys.
+fn check_client_identity(persistent: bool, dir: &Path) -> Check {
+    const NAME: &str = "Nym client keys";
+    if !persistent {
+        return Check::ok(NAME, "Not in use; each connection gets a new Nym address.");
+    }
+    let mut missing = Vec::new();
+    let mut damaged = Vec::new();
+    for file in CLIENT_KEY_FILES {
+        match fs::read_to_string(dir.join(file)) {
+            Ok(content) if content.contains("-----BEGIN") && content.contains("-----END") => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => missing.push(file),
+            _ => damaged.push(file),
+        }
+    }
+    if damaged.is_empty() && missing.len() == CLIENT_KEY_FILES.len() {
+        return Check::ok(NAME, "Created on the next connection.");
+    }
+    if damaged.is_empty() && missing.is_empty() {
+        return Check::ok(NAME, format!("{} holds a complete set of keys.", dir.display()));
+    }
+    let mut problems = Vec::new();
+    if !damaged.is_empty() {
+        problems.push(format!("{} unreadable", damaged.join(", ")));
+    }
+    if !missing.is_empty() {
+        problems.push(format!("{} missing", missing.join(", ")));
+    }
+    Check::degraded(
+        NAME,
+        format!(
+            "The keys in {} are incomplete ({}); the persistent Nym address cannot connect. \
+             Switching keeps the files, so they can be restored or removed with Reset.",
+            dir.display(),
+            problems.join("; "),
+        ),
+        Some(Fix::UseEphemeralIdentity),
+    )
+}
+
+fn check_clock(storage: &dyn Storage) -> Check {
+    const NAME: &str = "Clock";
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    if now < EARLIEST_PLAUSIBLE_TIME {
+        return Check::degraded(NAME, "The system clock is not set; page ages and reports will be wrong.", None);
+    }
+    let newest_report = report::SentReports::load(storage)
+        .reports
+        .iter()
+        .map(|sent| sent.report.timestamp)
+        .max();
+    match newest_report {
+        Some(timestamp) if timestamp > now + CLOCK_TOLERANCE => Check::degraded(
+            NAME,
+            format!(
+                "The clock is {} hours behind a report sent earlier; it may have been set back.",
+                (timestamp - now) / 3600,
+            ),
+            None,
+        ),
+        _ => Check::ok(NAME, "Plausible."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use std::path::PathBuf;
+
+    fn identity_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nymview-identity-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_keys(dir: &Path) {
+        for file in CLIENT_KEY_FILES {
+            fs::write(dir.join(file), "-----BEGIN KEY-----\nAAAA\n-----END KEY-----\n").unwrap();
+        }
+    }
+
+    #[test]
+    fn client_keys_are_only_checked_for_a_persistent_address() {
+        let dir = identity_dir("ephemeral");
+        fs::write(dir.join("ack_key.pem"), "").unwrap();
+        assert_eq!(check_client_identity(false, &dir).status, Status::Ok);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn complete_or_absent_client_keys_are_fine() {
+        let dir = identity_dir("complete");
+        assert_eq!(check_client_identity(true, &dir).status, Status::Ok);
+        write_keys(&dir);
+        assert_eq!(check_client_identity(true, &dir).status, Status::Ok);
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(check_client_identity(true, &dir).status, Status::Ok);
+    }
+
+    #[test]
+    fn damaged_client_keys_offer_an_ephemeral_address() {
+        let dir = identity_dir("damaged");
+        write_keys(&dir);
+        fs::write(dir.join("private_identity.pem"), "garbage").unwrap();
+        fs::remove_file(dir.join("ack_key.pem")).unwrap();
+
+        let check = check_client_identity(true, &dir);
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(check.status, Status::Degraded);
+        assert_eq!(check.fix, Some(Fix::UseEphemeralIdentity));
+        assert!(check.explanation.contains("private_identity.pem unreadable"), "{}", check.explanation);
+        assert!(check.explanation.contains("ack_key.pem missing"), "{}", check.explanation);
+    }
+
+    #[test]
+    fn a_broken_session_can_be_discarded() {
+        let storage = MemoryStorage::default();
+        storage.write(history::HISTORY_FILE, "{\"entries\": 3}").unwrap();
+        let check = check_session(&storage);
+        assert_eq!(check.status, Status::Degraded);
+
+        check.fix.unwrap().apply(&storage, &mut Settings::default()).unwrap();
+        assert_eq!(storage.read(history::HISTORY_FILE), None);
+        assert_eq!(check_session(&storage).status, Status::Ok);
+    }
+}
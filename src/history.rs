@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use crate::mixnet_browser::HistoryEntry;
+use crate::server_id::ServerIds;
+use crate::storage::Storage;
+
+pub const HISTORY_FILE: &str = "history.json";
+
+// Back/forward history as kept between runs, oldest first. Only pages that
+// were actually served are saved.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedHistory {
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl SavedHistory {
+    // Server addresses are interned again so restored entries share them
+    // with the rest of the session
+    pub fn load(storage: &dyn Storage, server_ids: &mut ServerIds) -> Self {
+        let mut saved: Self = storage.read(HISTORY_FILE)
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        for entry in &mut saved.entries {
+            entry.server = server_ids.intern(&entry.server);
+        }
+        saved
+    }
+
+    // The newest `limit` answered entries. Without `keep_content` only the
+    // addresses are kept, and the pages are fetched again when visited.
+    pub fn new(history: &[HistoryEntry], limit: usize, keep_content: bool) -> Self {
+        let answered: Vec<&HistoryEntry> = history.iter().filter(|entry| entry.fetched_at.is_some()).collect();
+        let skip = answered.len().saturating_sub(limit);
+        let entries = answered[skip..]
+            .iter()
+            .map(|entry| {
+                if keep_content || entry.content_dropped {
+                    (*entry).clone()
+                } else {
+                    HistoryEntry {
+                        server: entry.server.clone(),
+                        page: entry.page.clone(),
+                        content: String::new(),
+                        fetched_at: entry.fetched_at,
+                        server_error: entry.server_error,
+                        error_code: entry.error_code.clone(),
+                        content_dropped: true,
+                    }
+                }
+            })
+            .collect();
+        Self { entries }
+    }
+
+    // Blocking. Nothing to keep removes the file.
+    pub fn save(&self, storage: &dyn Storage) -> Result<(), String> {
+        if self.entries.is_empty() {
+            return storage.remove(HISTORY_FILE);
+        }
+        let content = serde_json::to_string(self)
+            .map_err(|e| format!("Could not serialize history: {}", e))?;
+        storage.write(HISTORY_FILE, &content)
+    }
+}
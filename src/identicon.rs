@@ -0,0 +1,89 @@
+use egui::{Color32, Rect, Sense, Ui, Vec2};
+use sha2::{Digest, Sha256};
+
+// 5×5 grid, mirrored around the middle column, derived from a server
+// address so servers can be told apart at a glance
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identicon {
+    pub color: Color32,
+    pub cells: [[bool; 5]; 5],
+}
+
+impl Identicon {
+    pub fn new(address: &str) -> Self {
+        let digest = Sha256::digest(address.trim().as_bytes());
+        let mut cells = [[false; 5]; 5];
+        for (row, cells) in cells.iter_mut().enumerate() {
+            for column in 0..3 {
+                let on = digest[row * 3 + column] & 1 == 1;
+                cells[column] = on;
+                cells[4 - column] = on;
+            }
+        }
+        let hue = f32::from(digest[15]) / 255.0;
+        Self {
+            color: egui::ecolor::Hsva::new(hue, 0.6, 0.75, 1.0).into(),
+            cells,
+        }
+    }
+}
+
+pub fn show(ui: &mut Ui, address: &str, size: f32) -> egui::Response {
+    // Focusable so keyboard users reach it and its hover text
+    let (rect, response) = ui.allocate_exact_size(Vec2::splat(size), Sense::focusable_noninteractive());
+    response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Image, true, "Server identicon"));
+    let identicon = Identicon::new(address);
+    let cell = size / 5.0;
+    let painter = ui.painter();
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    for (row, cells) in identicon.cells.iter().enumerate() {
+        for (column, on) in cells.iter().enumerate() {
+            if *on {
+                let min = rect.min + Vec2::new(column as f32 * cell, row as f32 * cell);
+                painter.rect_filled(Rect::from_min_size(min, Vec2::splat(cell)), 0.0, identicon.color);
+            }
+        }
+    }
+    if response.has_focus() {
+        painter.rect_stroke(rect.expand(2.0), 2.0, ui.visuals().selection.stroke, egui::StrokeKind::Outside);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(identicon: &Identicon) -> Vec<String> {
+        identicon.cells
+            .iter()
+            .map(|row| row.iter().map(|on| if *on { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn the_same_address_always_gives_the_same_identicon() {
+        let identicon = Identicon::new("server.addr@gateway");
+        assert_eq!(pattern(&identicon), vec![".###.", "#####", "#...#", "##.##", "#####"]);
+        assert_eq!(identicon.color, Color32::from(egui::ecolor::Hsva::new(242.0 / 255.0, 0.6, 0.75, 1.0)));
+        assert_eq!(Identicon::new(" server.addr@gateway\n"), identicon);
+    }
+
+    #[test]
+    fn a_one_character_change_gives_another_identicon() {
+        let identicon = Identicon::new("server.addr@gateway");
+        for other in ["server.addr@gatewaz", "Server.addr@gateway", "server.addr@gateway1", "erver.addr@gateway"] {
+            assert_ne!(Identicon::new(other), identicon, "{}", other);
+        }
+    }
+
+    #[test]
+    fn cells_mirror_around_the_middle_column() {
+        for address in ["a", "b.c@d", "server.addr@gateway"] {
+            for row in Identicon::new(address).cells {
+                assert_eq!(row[0], row[4]);
+                assert_eq!(row[1], row[3]);
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use egui::load::{ImageLoadResult, ImageLoader, ImagePoll, LoadError, SizeHint};
+use egui::{ColorImage, Context};
+use image::{ImageReader, Limits};
+use std::collections::{HashMap, VecDeque};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+// Images fetched at once; the rest of a page's images wait their turn
+const MAX_IN_FLIGHT: usize = 2;
+
+// Images larger than this in either direction are refused before decoding
+const MAX_DIMENSION: u32 = 4096;
+// Memory a decoder may hold at once; a 16-bit RGBA image at the size limit
+const MAX_ALLOC: u64 = MAX_DIMENSION as u64 * MAX_DIMENSION as u64 * 8;
+// Decoded images kept at once; the least recently shown go first. Two
+// images at the size limit fit.
+const MAX_CACHE_BYTES: usize = 2 * MAX_DIMENSION as usize * MAX_DIMENSION as usize * 4;
+
+#[derive(Debug, Clone)]
+enum Entry {
+    Queued,
+    Fetching,
+    Ready(Arc<ColorImage>),
+    Failed(String),
+}
+
+impl Entry {
+    fn byte_size(&self) -> usize {
+        match self {
+            Self::Ready(image) => image.pixels.len() * 4,
+            _ => 0,
+        }
+    }
+}
+
+// Loads nym:// images in markdown pages. egui asks for an image every
+// frame it is visible; the first time, its URL joins the page's fetch
+// queue and a spinner is shown until the browser hands back the bytes.
+// Textures made from the decoded images are cached by egui.
+pub struct NymImageLoader {
+    entries: Mutex<HashMap<String, Entry>>,
+    queue: Mutex<VecDeque<String>>,
+    // Ready images, least recently shown first
+    recent: Mutex<VecDeque<String>>,
+    max_bytes: usize,
+}
+
+impl Default for NymImageLoader {
+    fn default() -> Self {
+        Self::new(MAX_CACHE_BYTES)
+    }
+}
+
+impl NymImageLoader {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            queue: Mutex::new(VecDeque::new()),
+            recent: Mutex::new(VecDeque::new()),
+            max_bytes,
+        }
+    }
+
+    // Next URLs to fetch, keeping at most MAX_IN_FLIGHT fetches running
+    pub fn take_queued(&self) -> Vec<String> {
+        let mut entries = self.entries.lock().unwrap();
+        let in_flight = entries.values().filter(|entry| matches!(entry, Entry::Fetching)).count();
+        let mut queue = self.queue.lock().unwrap();
+        let count = MAX_IN_FLIGHT.saturating_sub(in_flight).min(queue.len());
+        let urls: Vec<String> = queue.drain(..count).collect();
+        for url in &urls {
+            entries.insert(url.clone(), Entry::Fetching);
+        }
+        urls
+    }
+
+    // Blocking; decodes off the UI thread
+    pub fn finish(&self, url: &str, result: Result<Vec<u8>, String>) {
+        let entry = match result.and_then(|bytes| decode(&bytes)) {
+            Ok(image) => Entry::Ready(Arc::new(image)),
+            Err(e) => Entry::Failed(e),
+        };
+        let ready = matches!(entry, Entry::Ready(_));
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(url.to_string(), entry);
+        let mut recent = self.recent.lock().unwrap();
+        recent.retain(|recent_url| recent_url != url);
+        if ready {
+            recent.push_back(url.to_string());
+        }
+
+        // The newest image stays even if it alone is over the limit
+        let mut bytes: usize = entries.values().map(Entry::byte_size).sum();
+        while bytes > self.max_bytes && recent.len() > 1 {
+            if let Some(evicted) = recent.pop_front().and_then(|oldest| entries.remove(&oldest)) {
+                bytes -= evicted.byte_size();
+            }
+        }
+    }
+
+    // Leaving a page drops the images it was still waiting for, so they
+    // are asked for again if the page is shown later. Failed images are
+    // dropped too, so a reload tries them again. Loaded images stay.
+    pub fn cancel_pending(&self) {
+        self.queue.lock().unwrap().clear();
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|_, entry| matches!(entry, Entry::Ready(_)));
+    }
+
+    // A reload asks again for the images that failed
+    pub fn retry_failed(&self) {
+        self.entries.lock().unwrap().retain(|_, entry| !matches!(entry, Entry::Failed(_)));
+    }
+}
+
+impl ImageLoader for NymImageLoader {
+    fn id(&self) -> &str {
+        "nymview::NymImageLoader"
+    }
+
+    fn load(&self, _ctx: &Context, uri: &str, _size_hint: SizeHint) -> ImageLoadResult {
+        if !uri.starts_with("nym://") {
+            return Err(LoadError::NotSupported);
+        }
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(uri) {
+            Some(Entry::Ready(image)) => {
+                let mut recent = self.recent.lock().unwrap();
+                if recent.back().is_some_and(|newest| newest != uri) {
+                    recent.retain(|recent_url| recent_url != uri);
+                    recent.push_back(uri.to_string());
+                }
+                Ok(ImagePoll::Ready { image: image.clone() })
+            }
+            Some(Entry::Failed(e)) => Err(LoadError::Loading(e.clone())),
+            Some(Entry::Queued | Entry::Fetching) => Ok(ImagePoll::Pending { size: None }),
+            None => {
+                entries.insert(uri.to_string(), Entry::Queued);
+                self.queue.lock().unwrap().push_back(uri.to_string());
+                Ok(ImagePoll::Pending { size: None })
+            }
+        }
+    }
+
+    fn forget(&self, uri: &str) {
+        self.entries.lock().unwrap().remove(uri);
+        self.recent.lock().unwrap().retain(|recent_url| recent_url != uri);
+    }
+
+    fn forget_all(&self) {
+        self.entries.lock().unwrap().clear();
+        self.queue.lock().unwrap().clear();
+        self.recent.lock().unwrap().clear();
+    }
+
+    fn byte_size(&self) -> usize {
+        self.entries.lock().unwrap().values().map(Entry::byte_size).sum()
+    }
+
+    fn has_pending(&self) -> bool {
+        self.entries
+            .lock()
+            .unwrap()
+            .values()
+            .any(|entry| matches!(entry, Entry::Queued | Entry::Fetching))
+    }
+}
+
+// The limits are checked against the image's header, so an oversized
+// image is refused without allocating its pixels
+fn decode(bytes: &[u8]) -> Result<ColorImage, String> {
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| format!("Could not read image: {}", e))?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_ALLOC);
+    reader.limits(limits);
+    let image = reader.decode().map_err(|e| match e {
+        image::ImageError::Limits(_) => format!("Image is larger than {0}×{0} or too large to decode", MAX_DIMENSION),
+        e => format!("Could not decode image: {}", e),
+    })?;
+    let rgba = image.to_rgba8();
+    Ok(ColorImage::from_rgba_unmultiplied(
+        [rgba.width() as usize, rgba.height() as usize],
+        rgba.as_raw(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upload::encode_png;
+    use egui::Color32;
+
+    const A: &str = "nym://server.addr@gateway/a.png";
+    const B: &str = "nym://server.addr@gateway/b.png";
+    const C: &str = "nym://server.addr@gateway/c.png";
+
+    // 100 pixels, 400 bytes decoded
+    fn png() -> Vec<u8> {
+        encode_png(&ColorImage::new([10, 10], vec![Color32::RED; 100])).unwrap()
+    }
+
+    fn is_ready(loader: &NymImageLoader, url: &str) -> bool {
+        matches!(loader.entries.lock().unwrap().get(url), Some(Entry::Ready(_)))
+    }
+
+    #[test]
+    fn the_least_recently_shown_images_are_evicted() {
+        let ctx = Context::default();
+        let loader = NymImageLoader::new(800);
+        loader.finish(A, Ok(png()));
+        loader.finish(B, Ok(png()));
+        // Showing A makes B the oldest
+        assert!(loader.load(&ctx, A, SizeHint::default()).is_ok());
+        loader.finish(C, Ok(png()));
+
+        assert!(is_ready(&loader, A));
+        assert!(!is_ready(&loader, B));
+        assert!(is_ready(&loader, C));
+        assert_eq!(loader.byte_size(), 800);
+    }
+
+    #[test]
+    fn failed_images_are_tried_again_after_leaving_the_page() {
+        let ctx = Context::default();
+        let loader = NymImageLoader::default();
+        loader.finish(A, Ok(png()));
+        loader.finish(B, Err("The image did not arrive in time".to_string()));
+        assert!(loader.load(&ctx, B, SizeHint::default()).is_err());
+
+        loader.cancel_pending();
+        assert!(is_ready(&loader, A));
+        assert!(matches!(loader.load(&ctx, B, SizeHint::default()), Ok(ImagePoll::Pending { .. })));
+        assert_eq!(loader.take_queued(), vec![B.to_string()]);
+    }
+}
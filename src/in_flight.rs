@@ -0,0 +1,182 @@
+use nymview_protocol as protocol;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+// What makes two requests the same: they would get the same reply
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestKey {
+    server: String,
+    method: String,
+    path: String,
+    body_hash: u64,
+}
+
+impl RequestKey {
+    pub fn new(server: &str, method: &str, path: &str, body: &[u8]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        body.hash(&mut hasher);
+        Self {
+            server: server.trim().to_string(),
+            method: method.to_ascii_uppercase(),
+            path: protocol::normalize_path(path),
+            body_hash: hasher.finish(),
+        }
+    }
+
+    pub fn get(server: &str, path: &str) -> Self {
+        Self::new(server, "GET", path, &[])
+    }
+
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+}
+
+// Requests sent and not answered yet, keyed by request ID, each with
+// whoever waits for its reply. A request identical to a live one is not
+// sent again; its initiator waits on the live one instead.
+#[derive(Debug)]
+pub struct InFlight<W> {
+    requests: HashMap<u64, Entry<W>>,
+    // Requests older than this are not expected to be answered any more
+    timeout: Duration,
+}
+
+#[derive(Debug)]
+struct Entry<W> {
+    key: RequestKey,
+    sent: Instant,
+    waiters: Vec<W>,
+}
+
+impl<W> InFlight<W> {
+    pub fn new(timeout: Duration) -> Self {
+        Self { requests: HashMap::new(), timeout }
+    }
+
+    pub fn insert(&mut self, request_id: u64, key: RequestKey, waiter: W) {
+        self.requests.insert(request_id, Entry { key, sent: Instant::now(), waiters: vec![waiter] });
+    }
+
+    // The newest live request for `key`. Requests past the timeout are
+    // dropped along the way, so a timed out request is sent again.
+    pub fn find(&mut self, key: &RequestKey) -> Option<u64> {
+        let timeout = self.timeout;
+        self.requests.retain(|_, entry| entry.sent.elapsed() <= timeout);
+        self.requests
+            .iter()
+            .filter(|(_, entry)| entry.key == *key)
+            .max_by_key(|(_, entry)| entry.sent)
+            .map(|(request_id, _)| *request_id)
+    }
+
+    pub fn key(&self, request_id: u64) -> Option<&RequestKey> {
+        self.requests.get(&request_id).map(|entry| &entry.key)
+    }
+
+    pub fn waiters(&self, request_id: u64) -> &[W] {
+        self.requests.get(&request_id).map_or(&[], |entry| &entry.waiters)
+    }
+
+    // Returns false if the request is no longer in flight
+    pub fn attach(&mut self, request_id: u64, waiter: W) -> bool {
+        match self.requests.get_mut(&request_id) {
+            Some(entry) => {
+                entry.waiters.push(waiter);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The reply or failure has arrived: everyone waiting for it, or None
+    // if the request was not in flight
+    pub fn complete(&mut self, request_id: u64) -> Option<Vec<W>> {
+        self.requests.remove(&request_id).map(|entry| entry.waiters)
+    }
+
+    // Cancels the waiters `is_gone` picks out. Once nobody waits, the
+    // request is forgotten, so the next identical one is sent anew.
+    pub fn detach(&mut self, request_id: u64, is_gone: impl Fn(&W) -> bool) {
+        if let Some(entry) = self.requests.get_mut(&request_id) {
+            entry.waiters.retain(|waiter| !is_gone(waiter));
+            if entry.waiters.is_empty() {
+                self.requests.remove(&request_id);
+            }
+        }
+    }
+
+    // A new connection will not receive replies to the old one's requests
+    pub fn clear(&mut self) {
+        self.requests.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVER: &str = "server.addr@gateway";
+
+    #[test]
+    fn requests_are_the_same_by_server_method_path_and_body() {
+        assert_eq!(RequestKey::get(SERVER, "about/"), RequestKey::get(&format!(" {}", SERVER), "/about"));
+        assert_eq!(RequestKey::get(SERVER, "/about"), RequestKey::new(SERVER, "get", "/about", b""));
+        assert_ne!(RequestKey::get(SERVER, "/about"), RequestKey::get("other.addr@gateway", "/about"));
+        assert_ne!(RequestKey::get(SERVER, "/about"), RequestKey::new(SERVER, "REPORT", "/about", b""));
+        assert_ne!(RequestKey::new(SERVER, "REPORT", "/", b"one"), RequestKey::new(SERVER, "REPORT", "/", b"two"));
+    }
+
+    #[test]
+    fn a_completed_request_answers_all_its_waiters_once() {
+        let mut in_flight = InFlight::new(Duration::from_secs(30));
+        let key = RequestKey::get(SERVER, "/about");
+        in_flight.insert(7, key.clone(), "page");
+        assert_eq!(in_flight.find(&key), Some(7));
+        assert!(in_flight.attach(7, "transclusion"));
+
+        assert_eq!(in_flight.complete(7), Some(vec!["page", "transclusion"]));
+        assert_eq!(in_flight.complete(7), None);
+        assert_eq!(in_flight.find(&key), None);
+        assert!(!in_flight.attach(7, "late"));
+    }
+
+    #[test]
+    fn timed_out_requests_are_not_waited_on() {
+        let mut in_flight = InFlight::new(Duration::ZERO);
+        let key = RequestKey::get(SERVER, "/slow");
+        in_flight.insert(1, key.clone(), "page");
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert_eq!(in_flight.find(&key), None);
+        assert_eq!(in_flight.complete(1), None);
+    }
+
+    #[test]
+    fn cancelling_one_waiter_keeps_the_request_for_the_others() {
+        let mut in_flight = InFlight::new(Duration::from_secs(30));
+        let key = RequestKey::get(SERVER, "/slow");
+        in_flight.insert(1, key.clone(), "page");
+        in_flight.attach(1, "transclusion");
+
+        in_flight.detach(1, |waiter| *waiter == "page");
+        assert_eq!(in_flight.waiters(1), ["transclusion"]);
+        assert_eq!(in_flight.find(&key), Some(1));
+
+        in_flight.detach(1, |waiter| *waiter == "transclusion");
+        assert_eq!(in_flight.find(&key), None);
+    }
+
+    #[test]
+    fn the_newest_identical_request_is_found() {
+        let mut in_flight = InFlight::new(Duration::from_secs(30));
+        let key = RequestKey::get(SERVER, "/");
+        in_flight.insert(1, key.clone(), "page");
+        std::thread::sleep(Duration::from_millis(1));
+        // A hard reload sends it again regardless
+        in_flight.insert(2, key.clone(), "page");
+        assert_eq!(in_flight.find(&key), Some(2));
+    }
+}
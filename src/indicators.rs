@@ -0,0 +1,19 @@
+use egui::{Color32, Ui};
+use std::time::Duration;
+
+// All loading animations go through here so "reduce motion" applies everywhere
+
+pub fn busy(ui: &mut Ui, reduce_motion: bool) {
+    if !reduce_motion {
+        ui.spinner();
+    }
+}
+
+pub fn loading(ui: &mut Ui, reduce_motion: bool, elapsed: Duration) {
+    if reduce_motion {
+        ui.colored_label(Color32::BLUE, format!("Loading… {} s", elapsed.as_secs()));
+    } else {
+        ui.spinner();
+        ui.colored_label(Color32::BLUE, format!("Loading for {:.1} seconds", elapsed.as_secs_f32()));
+    }
+}
@@ -0,0 +1,111 @@
+use egui::{CollapsingHeader, Color32, RichText, TextEdit, Ui};
+use serde_json::Value;
+
+const KEY_COLOR: Color32 = Color32::from_rgb(120, 40, 120);
+const STRING_COLOR: Color32 = Color32::from_rgb(20, 120, 40);
+const NUMBER_COLOR: Color32 = Color32::from_rgb(20, 80, 200);
+const LITERAL_COLOR: Color32 = Color32::from_rgb(180, 90, 0);
+
+// Structured view for API-style services that answer with JSON instead of markdown
+#[derive(Clone)]
+pub struct JsonDocument {
+    parsed: Result<Value, String>,
+    raw: bool,
+}
+
+impl JsonDocument {
+    // Only replies the server sent as JSON are shown this way; a markdown
+    // page that happens to be bracketed stays a page
+    pub fn detect(content_type: &str, content: &str) -> Option<Self> {
+        let essence = content_type.split(';').next().unwrap_or("").trim();
+        if !essence.eq_ignore_ascii_case("application/json") {
+            return None;
+        }
+
+        let trimmed = content.trim();
+        let parsed = serde_json::from_str::<Value>(trimmed).map_err(|e| e.to_string());
+        Some(Self { parsed, raw: false })
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, content: &str) {
+        ui.horizontal(|ui| {
+            if self.parsed.is_ok() {
+                ui.checkbox(&mut self.raw, "Raw text");
+            }
+            if let Ok(value) = &self.parsed {
+                if ui.button("Copy pretty").clicked() {
+                    if let Ok(pretty) = serde_json::to_string_pretty(value) {
+                        ui.ctx().copy_text(pretty);
+                    }
+                }
+                if ui.button("Copy minified").clicked() {
+                    ui.ctx().copy_text(value.to_string());
+                }
+            }
+        });
+        ui.separator();
+
+        match &self.parsed {
+            Ok(value) if !self.raw => show_value(ui, "$", value, "$", 0),
+            Ok(_) => show_raw(ui, content),
+            Err(e) => {
+                ui.colored_label(Color32::RED, format!("Invalid JSON: {}", e));
+                show_raw(ui, content);
+            }
+        }
+    }
+}
+
+fn show_raw(ui: &mut Ui, content: &str) {
+    ui.add(
+        TextEdit::multiline(&mut &*content)
+            .code_editor()
+            .desired_width(f32::INFINITY)
+    );
+}
+
+fn show_value(ui: &mut Ui, key: &str, value: &Value, path: &str, depth: usize) {
+    match value {
+        Value::Object(map) => {
+            let title = format!("{}  {{…}} {} keys", key, map.len());
+            CollapsingHeader::new(RichText::new(title).color(KEY_COLOR))
+                .id_salt(path)
+                .default_open(depth < 2)
+                .show(ui, |ui| {
+                    for (child_key, child) in map {
+                        let child_path = format!("{}.{}", path, child_key);
+                        show_value(ui, child_key, child, &child_path, depth + 1);
+                    }
+                })
+                .header_response
+                .on_hover_text(path);
+        }
+        Value::Array(items) => {
+            let title = format!("{}  […] {} items", key, items.len());
+            CollapsingHeader::new(RichText::new(title).color(KEY_COLOR))
+                .id_salt(path)
+                .default_open(depth < 2)
+                .show(ui, |ui| {
+                    for (index, child) in items.iter().enumerate() {
+                        let child_path = format!("{}[{}]", path, index);
+                        show_value(ui, &format!("[{}]", index), child, &child_path, depth + 1);
+                    }
+                })
+                .header_response
+                .on_hover_text(path);
+        }
+        leaf => {
+            let (text, color) = match leaf {
+                Value::String(text) => (format!("{:?}", text), STRING_COLOR),
+                Value::Number(number) => (number.to_string(), NUMBER_COLOR),
+                other => (other.to_string(), LITERAL_COLOR),
+            };
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(format!("{}:", key)).monospace().color(KEY_COLOR));
+                ui.label(RichText::new(text).monospace().color(color));
+            })
+            .response
+            .on_hover_text(path);
+        }
+    }
+}
@@ -1,13 +1,85 @@
+use clap::Parser;
 use eframe::egui;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 mod mixnet_browser;
+mod address;
+mod bookmarks;
+mod config;
+mod diagnostics;
+mod downloads;
+mod encrypted_storage;
+mod frame_timing;
+mod gateway_stats;
+mod health;
+mod history;
+mod identicon;
+mod images;
+mod in_flight;
+mod indicators;
+mod json_view;
+mod markdown;
+mod metrics;
+mod render_cache;
+mod replay;
+mod request_format;
+mod report;
+mod reset;
+mod scroll;
+mod server_filter;
+mod server_id;
+mod settings;
+mod storage;
+mod templates;
+mod theme;
+mod timefmt;
+mod transport;
+mod undo;
+mod unlock;
+mod upload;
+
+#[derive(Parser)]
+#[command(name = "nym-view-client")]
+#[command(about = "NymView - Browse MarkDown pages on the Nym Mixnet")]
+struct Cli {
+    #[arg(long, value_name = "FILE", conflicts_with = "replay", help = "Write every reply of this session to FILE")]
+    record: Option<PathBuf>,
+    #[arg(long, value_name = "FILE", help = "Serve pages from a recording instead of the mixnet")]
+    replay: Option<PathBuf>,
+}
 
 fn main() -> Result<(), eframe::Error> {
+    let cli = Cli::parse();
+
+    // NYMVIEW_EPHEMERAL=1 keeps settings and history in memory only
+    let storage: Arc<dyn storage::Storage> = if std::env::var_os("NYMVIEW_EPHEMERAL").is_some() {
+        Arc::new(storage::MemoryStorage::default())
+    } else {
+        Arc::new(storage::FileStorage::default())
+    };
+
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size(settings::DEFAULT_WINDOW_SIZE)
+        .with_min_inner_size([500.0, 600.0])
+        .with_title("NymView");
+
+    // Encrypted settings cannot be read before the passphrase prompt, so
+    // the window then opens at its default size
+    let locked = encrypted_storage::is_enabled(storage.as_ref());
+
+    // Windows larger than their monitor, or on no monitor at all, are
+    // corrected once the window is open
+    let saved_window = if locked { None } else { settings::Settings::load(storage.as_ref()).window };
+    if let Some(window) = saved_window {
+        viewport = viewport
+            .with_inner_size([window.width, window.height])
+            .with_position([window.x, window.y])
+            .with_maximized(window.maximized);
+    }
+
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([700.0, 800.0])
-            .with_min_inner_size([500.0, 600.0])
-            .with_title("NymView"),
+        viewport,
         ..Default::default()
     };
 
@@ -16,7 +88,25 @@ fn main() -> Result<(), eframe::Error> {
         options,
         Box::new(|cc| {
             cc.egui_ctx.set_visuals(egui::Visuals::light());
-            Ok(Box::new(mixnet_browser::NymMixnetBrowser::new()))
+            if locked {
+                return Ok(Box::new(unlock::UnlockApp::new(storage)));
+            }
+            let mut browser = mixnet_browser::NymMixnetBrowser::with_storage(storage);
+            if let Some(file) = cli.replay {
+                browser.set_session_mode(replay::SessionMode::Replay(file));
+            } else if let Some(file) = cli.record {
+                browser.set_session_mode(replay::SessionMode::Record(file));
+            }
+            // NYMVIEW_MEMORY_PAGES=<dir> serves that directory without a mixnet
+            #[cfg(feature = "memory-transport")]
+            if let Some(dir) = std::env::var_os("NYMVIEW_MEMORY_PAGES") {
+                let responses = transport::MemoryResponses::new();
+                if let Err(e) = responses.load_dir(std::path::Path::new(&dir)) {
+                    eprintln!("Could not load memory pages: {}", e);
+                }
+                browser.use_memory_transport(responses);
+            }
+            Ok(Box::new(browser))
         }),
     )
 }
@@ -0,0 +1,520 @@
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use nymview_protocol::{ErrorStatus, Response};
+use crate::render_cache::RenderCaches;
+
+// Normalizes responses from servers with differing conventions: line
+// endings become "\n" and, optionally, trailing whitespace is trimmed.
+// Lines inside fenced code blocks keep their whitespace verbatim.
+pub fn normalize_response(content: &str, trim_trailing: bool) -> String {
+    let content = content.replace("\r\n", "\n").replace('\r', "\n");
+    if !trim_trailing {
+        return content;
+    }
+
+    let mut in_fence = false;
+    let lines: Vec<&str> = content
+        .split('\n')
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+                line.trim_end()
+            } else if in_fence {
+                line
+            } else {
+                line.trim_end()
+            }
+        })
+        .collect();
+
+    lines.join("\n")
+}
+
+// Empty, whitespace-only or BOM-only bodies have nothing to render
+pub fn is_blank(content: &str) -> bool {
+    content.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}').is_empty()
+}
+
+// Text of the first level-one heading, outside fenced code blocks
+pub fn page_title(content: &str) -> Option<String> {
+    let mut in_fence = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some(title) = trimmed.strip_prefix("# ") {
+            let title = title.trim().trim_end_matches('#').trim();
+            if !title.is_empty() {
+                return Some(title.to_string());
+            }
+        }
+    }
+    None
+}
+
+// Words of prose, for the reading time estimate. Fenced code blocks are
+// skipped and markup-only tokens ("#", "-", "|") do not count.
+pub fn word_count(content: &str) -> usize {
+    let mut in_fence = false;
+    let mut words = 0;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        words += trimmed
+            .split_whitespace()
+            .filter(|word| word.chars().any(char::is_alphanumeric))
+            .count();
+    }
+    words
+}
+
+// "~14 min, 3,200 words" at an average of 230 words per minute
+pub fn reading_time(words: usize) -> String {
+    let minutes = words.div_ceil(230).max(1);
+    let digits = words.to_string();
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("~{} min, {} word{}", minutes, grouped, if words == 1 { "" } else { "s" })
+}
+
+// "[title](url)" with the title escaped so it cannot break out of the brackets
+pub fn link_to(title: &str, url: &str) -> String {
+    let title = title.replace('\\', "\\\\").replace('[', "\\[").replace(']', "\\]");
+    if url.contains([' ', '(', ')']) {
+        format!("[{}](<{}>)", title, url)
+    } else {
+        format!("[{}]({})", title, url)
+    }
+}
+
+// Rewrites applied to served markdown before it reaches CommonMarkViewer.
+// GFM task lists ("- [ ]" / "- [x]") are rendered by the viewer itself;
+// definition lists are not, so they are turned into a bold term followed
+// by a bulleted definition. Fenced code blocks are left untouched.
+pub fn prepare_for_display(content: &str) -> String {
+    #[derive(PartialEq)]
+    enum Last {
+        Blank,
+        Text,
+        Definition,
+        Other,
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_fence = false;
+    let mut last = Last::Blank;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            lines.push(line.to_string());
+            last = Last::Other;
+            continue;
+        }
+        if in_fence {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        if let Some(definition) = line.strip_prefix(": ") {
+            match last {
+                Last::Text => {
+                    if let Some(term) = lines.last_mut() {
+                        *term = format!("**{}**", term.trim());
+                    }
+                    lines.push(format!("- {}", definition.trim()));
+                    last = Last::Definition;
+                    continue;
+                }
+                Last::Definition => {
+                    lines.push(format!("- {}", definition.trim()));
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        last = if trimmed.is_empty() {
+            Last::Blank
+        } else if is_block_syntax(trimmed) {
+            Last::Other
+        } else {
+            Last::Text
+        };
+        lines.push(line.to_string());
+    }
+
+    lines.join("\n")
+}
+
+// Lines that already start a markdown block and cannot be a definition term
+fn is_block_syntax(line: &str) -> bool {
+    line.starts_with('#')
+        || line.starts_with('>')
+        || line.starts_with('|')
+        || line.starts_with("- ")
+        || line.starts_with("* ")
+        || line.starts_with("+ ")
+        || line.chars().next().is_some_and(|c| c.is_ascii_digit())
+}
+
+// Image references relative to the page ("logo.png", "/img/logo.png")
+// become nym://<server>/… so they are fetched from the server that sent
+// the page. Fenced code blocks are left untouched.
+pub fn resolve_image_urls(content: &str, server: &str) -> String {
+    if server.is_empty() {
+        return content.to_string();
+    }
+    let mut in_fence = false;
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+            }
+            if in_fence || !line.contains("![") {
+                return line.to_string();
+            }
+            resolve_line_images(line, server)
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn resolve_line_images(line: &str, server: &str) -> String {
+    let mut resolved = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find("![") {
+        let Some(target) = rest[start..].find("](").map(|pos| start + pos + 2) else {
+            break;
+        };
+        resolved.push_str(&rest[..target]);
+        rest = &rest[target..];
+        let url_end = rest.find([')', ' ']).unwrap_or(rest.len());
+        let url = &rest[..url_end];
+        // Like push_target: a ':' before the first '/' is a scheme (nym:, data:, …)
+        let has_scheme = url.split('/').next().is_some_and(|first| first.contains(':'));
+        if !url.is_empty() && !has_scheme && !url.starts_with('<') {
+            resolved.push_str(&format!("nym://{}/{}", server, url.trim_start_matches('/')));
+        } else {
+            resolved.push_str(url);
+        }
+        rest = &rest[url_end..];
+    }
+    resolved.push_str(rest);
+    resolved
+}
+
+// Destinations of the links NymView follows itself: nym:// links and links
+// relative to the current server. Other schemes (https:, mailto:) and
+// in-page anchors are left out. The page is parsed the way the viewer
+// parses it, so each destination is exactly the link it registers a hook
+// for, titles, escapes and parentheses included.
+pub fn link_targets(content: &str) -> Vec<String> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_FOOTNOTES;
+    let mut targets = Vec::new();
+    for event in Parser::new_ext(content, options) {
+        if let Event::Start(Tag::Link { dest_url, .. }) = event {
+            push_target(&mut targets, &dest_url);
+        }
+    }
+    targets
+}
+
+fn push_target(targets: &mut Vec<String>, target: &str) {
+    let followed = target.starts_with("nym://")
+        || !(target.is_empty() || target.starts_with('#') || target.contains(':'));
+    if followed && !targets.iter().any(|known| known == target) {
+        targets.push(target.to_string());
+    }
+}
+
+// A page cut at its transclusions, which the browser shows as expanders
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Markdown(String),
+    Transclusion { label: String, target: String },
+}
+
+// Splits a page at lines that consist of nothing but a transclusion:
+// `[label](path "transclude")` or `!include(path)`. Only pages on the
+// same server can be transcluded, so other targets stay ordinary links.
+// Fenced code blocks are never split.
+pub fn split_transclusions(content: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        match transclusion(trimmed).filter(|_| !in_fence) {
+            Some((label, target)) => {
+                if lines.iter().any(|line| !line.trim().is_empty()) {
+                    segments.push(Segment::Markdown(lines.join("\n")));
+                }
+                lines.clear();
+                segments.push(Segment::Transclusion { label, target });
+            }
+            None => lines.push(line),
+        }
+    }
+    if lines.iter().any(|line| !line.trim().is_empty()) {
+        segments.push(Segment::Markdown(lines.join("\n")));
+    }
+    segments
+}
+
+// Label and target of a transclusion line
+fn transclusion(line: &str) -> Option<(String, String)> {
+    let (label, target) = if let Some(path) = line.strip_prefix("!include(").and_then(|rest| rest.strip_suffix(')')) {
+        (path.trim(), path.trim())
+    } else {
+        let rest = line.strip_prefix('[')?.strip_suffix("\"transclude\")")?;
+        let (label, target) = rest.split_once("](")?;
+        (label.trim(), target.trim())
+    };
+    let same_server = !(target.is_empty() || target.starts_with('#') || target.contains([':', ' ', '"', '(', ')', '[', ']']));
+    let label = if label.is_empty() { target } else { label };
+    same_server.then(|| (label.to_string(), target.to_string()))
+}
+
+// A page body with everything the browser derives from it for display.
+// Served content is untrusted, so a panic in the rewrite leaves the body
+// to be shown as raw text instead of taking the browser down.
+#[derive(Debug, Clone)]
+pub struct PreparedPage {
+    pub content: String,
+    pub display_content: String,
+    // Render cache key of display_content
+    pub display_key: u64,
+    // Preparation panicked and display_content is the body as it came
+    pub fallback: bool,
+    pub links: Vec<String>,
+    pub segments: Vec<Segment>,
+    pub words: usize,
+}
+
+impl PreparedPage {
+    pub fn new(content: String, server: &str) -> Self {
+        Self::with_rewrite(content, server, |content, server| resolve_image_urls(&prepare_for_display(content), server))
+    }
+
+    // `rewrite` turns the body into what the viewer is given
+    pub fn with_rewrite(content: String, server: &str, rewrite: fn(&str, &str) -> String) -> Self {
+        let prepared = std::panic::catch_unwind(|| rewrite(&content, server));
+        let (display_content, fallback) = match prepared {
+            Ok(display_content) => (display_content, false),
+            Err(_) => (content.clone(), true),
+        };
+        Self {
+            display_key: RenderCaches::key(&display_content),
+            links: link_targets(&display_content),
+            segments: split_transclusions(&display_content),
+            words: word_count(&content),
+            content,
+            display_content,
+            fallback,
+        }
+    }
+}
+
+// What the status line of a text reply said
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplyStatus {
+    Ok,
+    Error(ErrorStatus),
+    // No status line; the whole reply is the page
+    Unframed,
+}
+
+// A text reply parsed and its page prepared, which is the slow part of
+// showing a large page. For a redirect the page is its target.
+#[derive(Debug, Clone)]
+pub struct PreparedReply {
+    pub status: ReplyStatus,
+    pub page: PreparedPage,
+}
+
+impl PreparedReply {
+    pub fn new(data: &[u8], trim_trailing_whitespace: bool, server: &str) -> Self {
+        let content = normalize_response(&String::from_utf8_lossy(data), trim_trailing_whitespace);
+        let (status, body) = match Response::parse(&content) {
+            Some(Response::Ok(body)) => (ReplyStatus::Ok, body),
+            Some(Response::Error(status, message)) => (ReplyStatus::Error(status), message),
+            None => (ReplyStatus::Unframed, content),
+        };
+        Self { status, page: PreparedPage::new(body, server) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_endings_become_newlines() {
+        let cases = [
+            ("a\r\nb\r\n", "a\nb\n"),
+            ("a\rb\r", "a\nb\n"),
+            ("a\r\nb\rc\nd", "a\nb\nc\nd"),
+            ("a\r\r\nb", "a\n\nb"),
+            ("\r\n", "\n"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_response(input, false), expected, "input {:?}", input);
+            assert_eq!(normalize_response(input, true), expected, "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn trailing_whitespace_is_kept_unless_asked() {
+        assert_eq!(normalize_response("a  \r\nb\t\n", false), "a  \nb\t\n");
+        assert_eq!(normalize_response("a  \r\nb\t\n", true), "a\nb\n");
+        // Indentation is not trailing
+        assert_eq!(normalize_response("  indented  \n", true), "  indented\n");
+    }
+
+    #[test]
+    fn code_blocks_keep_their_whitespace() {
+        let input = "text  \r\n```\r\ncode  \r\n\tmore\t\r\n```  \r\nafter \r\n~~~\r\nx \r\n~~~\r\n";
+        assert_eq!(
+            normalize_response(input, true),
+            "text\n```\ncode  \n\tmore\t\n```\nafter\n~~~\nx \n~~~\n"
+        );
+    }
+
+    #[test]
+    fn blank_bodies_are_recognised() {
+        for blank in ["", " ", "\n\n", " \t\r\n", "\u{feff}", "\u{feff}\n  ", "\u{feff}\u{feff}", "\u{a0}\u{2003}"] {
+            assert!(is_blank(blank), "input {:?}", blank);
+        }
+        for content in ["a", " .", "\u{feff}# Title", "\u{200b}", "<!-- -->"] {
+            assert!(!is_blank(content), "input {:?}", content);
+        }
+    }
+
+    #[test]
+    fn word_count_skips_code_blocks_and_markup() {
+        let cases = [
+            ("", 0),
+            ("one two three", 3),
+            ("# Title\n\n- item one\n- item two", 5),
+            ("| a | b |\n|---|---|\n| c | d |", 4),
+            ("before\n```rust\nlet x = 1;\nfn main() {}\n```\nafter", 2),
+            ("before\n~~~\ncode here\n~~~\nafter", 2),
+            ("  ```\n  indented fence\n  ```\nprose", 1),
+            ("text\n```\nnever closed\nmore code", 1),
+            ("**bold** _it_ `code` 42", 4),
+            ("— … --- ***", 0),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(word_count(content), expected, "content {:?}", content);
+        }
+    }
+
+    #[test]
+    fn reading_time_is_at_least_a_minute() {
+        assert_eq!(reading_time(0), "~1 min, 0 words");
+        assert_eq!(reading_time(1), "~1 min, 1 word");
+        assert_eq!(reading_time(230), "~1 min, 230 words");
+        assert_eq!(reading_time(231), "~2 min, 231 words");
+        assert_eq!(reading_time(3200), "~14 min, 3,200 words");
+        assert_eq!(reading_time(1_234_567), "~5368 min, 1,234,567 words");
+    }
+
+    #[test]
+    fn relative_images_are_fetched_from_the_server() {
+        let cases = [
+            ("![Logo](logo.png)", "![Logo](nym://srv/logo.png)"),
+            ("![Logo](/img/logo.png)", "![Logo](nym://srv/img/logo.png)"),
+            ("![Logo](img/a:b.png)", "![Logo](nym://srv/img/a:b.png)"),
+            ("![Logo](logo.png \"The logo\")", "![Logo](nym://srv/logo.png \"The logo\")"),
+            ("![Logo](nym://other/logo.png)", "![Logo](nym://other/logo.png)"),
+            ("![Logo](https://example.com/logo.png)", "![Logo](https://example.com/logo.png)"),
+            ("![Dot](data:image/png;base64,iVBORw0KGgo=)", "![Dot](data:image/png;base64,iVBORw0KGgo=)"),
+            ("![Logo](<my logo.png>)", "![Logo](<my logo.png>)"),
+            ("![Logo]()", "![Logo]()"),
+            ("![A](a.png) and ![B](/b.png)", "![A](nym://srv/a.png) and ![B](nym://srv/b.png)"),
+            ("```\n![Logo](logo.png)\n```", "```\n![Logo](logo.png)\n```"),
+            ("[Link](logo.png)", "[Link](logo.png)"),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(resolve_image_urls(content, "srv"), expected, "content {:?}", content);
+        }
+        assert_eq!(resolve_image_urls("![Logo](logo.png)", ""), "![Logo](logo.png)");
+    }
+
+    #[test]
+    fn only_followed_links_are_targets() {
+        let cases: [(&str, &[&str]); 12] = [
+            ("[About](/about)", &["/about"]),
+            ("[About](about.md)", &["about.md"]),
+            ("[Other](nym://other.addr@gateway/page)", &["nym://other.addr@gateway/page"]),
+            ("[Top](#top)", &[]),
+            ("[Mail](mailto:someone@example.com)", &[]),
+            ("[Web](https://example.com/page)", &[]),
+            ("[About](/about \"About us\")", &["/about"]),
+            ("[Paren](/a\\(b\\))", &["/a(b)"]),
+            ("[Angle](</a b>)", &["/a b"]),
+            ("[One](/a) and [again](/a) and [two](/b)", &["/a", "/b"]),
+            ("`[Code](/code)`\n\n```\n[Fenced](/fenced)\n```", &[]),
+            ("[Empty]()", &[]),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(link_targets(content), expected, "content {:?}", content);
+        }
+    }
+
+    #[test]
+    fn transclusion_lines_become_segments() {
+        let part = |label: &str, target: &str| Segment::Transclusion { label: label.to_string(), target: target.to_string() };
+        let text = |text: &str| Segment::Markdown(text.to_string());
+        let cases = [
+            ("plain page", vec![text("plain page")]),
+            ("[Intro](/intro \"transclude\")", vec![part("Intro", "/intro")]),
+            ("before\n[Intro](intro \"transclude\")\nafter", vec![text("before"), part("Intro", "intro"), text("after")]),
+            ("  !include(/parts/a)  ", vec![part("/parts/a", "/parts/a")]),
+            ("[](/intro \"transclude\")", vec![part("/intro", "/intro")]),
+            // Blank lines around an expander are not segments of their own
+            ("\n[A](/a \"transclude\")\n\n!include(b)\n", vec![part("A", "/a"), part("b", "b")]),
+            // Not alone on its line, other titles and other servers stay links
+            ("see [Intro](/intro \"transclude\") here", vec![text("see [Intro](/intro \"transclude\") here")]),
+            ("[Intro](/intro \"Introduction\")", vec![text("[Intro](/intro \"Introduction\")")]),
+            ("[Other](nym://other.addr/intro \"transclude\")", vec![text("[Other](nym://other.addr/intro \"transclude\")")]),
+            ("!include(https://example.com)", vec![text("!include(https://example.com)")]),
+            ("[Anchor](#intro \"transclude\")", vec![text("[Anchor](#intro \"transclude\")")]),
+            ("```\n!include(/a)\n```\n!include(/b)", vec![text("```\n!include(/a)\n```"), part("/b", "/b")]),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(split_transclusions(content), expected, "content {:?}", content);
+        }
+    }
+
+    #[test]
+    fn an_unclosed_code_block_runs_to_the_end() {
+        assert_eq!(normalize_response("a \n  ```rust\nb \nc \n", true), "a\n  ```rust\nb \nc \n");
+    }
+}
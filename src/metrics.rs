@@ -0,0 +1,218 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use crate::server_id::ServerId;
+
+// Counters for the request/response path, kept independent of the UI
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    pub requests_sent: u64,
+    pub replies_received: u64,
+    pub send_failures: u64,
+    pub timeouts: u64,
+    // Requests not sent because an identical one was still in flight
+    pub coalesced: u64,
+    pub bytes_out: u64,
+    pub bytes_in: u64,
+    latency_total: Duration,
+    latency_samples: u32,
+    requests_per_server: HashMap<ServerId, u64>,
+    // Servers in order of last request, most recent first
+    recent_servers: Vec<ServerId>,
+    // Round trips of the last few replies on the current connection
+    recent_latencies: VecDeque<Duration>,
+    // Recent round trips per server, for load time estimates
+    server_latencies: HashMap<ServerId, VecDeque<Duration>>,
+}
+
+// What a request to a server usually takes, and beyond what it is slow
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadEstimate {
+    pub typical: Duration,
+    pub slow: Duration,
+}
+
+// Median and 90th percentile of the samples; None without any. A single
+// outlier in a short history raises `slow` but not `typical`.
+pub fn estimate_load_time(samples: &[Duration]) -> Option<LoadEstimate> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+    let percentile_90 = (sorted.len() * 9).div_ceil(10) - 1;
+    Some(LoadEstimate {
+        typical: sorted[sorted.len() / 2],
+        slow: sorted[percentile_90],
+    })
+}
+
+const RECENT_LATENCY_WINDOW: usize = 5;
+const SERVER_LATENCY_WINDOW: usize = 20;
+
+impl Metrics {
+    pub fn record_request(&mut self, server: &ServerId, bytes: usize) {
+        self.requests_sent += 1;
+        self.bytes_out += bytes as u64;
+        *self.requests_per_server.entry(server.clone()).or_default() += 1;
+        self.recent_servers.retain(|recent| recent != server);
+        self.recent_servers.insert(0, server.clone());
+    }
+
+    pub fn recent_servers(&self) -> &[ServerId] {
+        &self.recent_servers
+    }
+
+    pub fn requests_to(&self, server: &str) -> u64 {
+        self.requests_per_server.get(server).copied().unwrap_or(0)
+    }
+
+    pub fn record_reply(&mut self, bytes: usize, latency: Option<Duration>) {
+        self.replies_received += 1;
+        self.bytes_in += bytes as u64;
+        if let Some(latency) = latency {
+            self.latency_total += latency;
+            self.latency_samples += 1;
+            if self.recent_latencies.len() == RECENT_LATENCY_WINDOW {
+                self.recent_latencies.pop_front();
+            }
+            self.recent_latencies.push_back(latency);
+        }
+    }
+
+    pub fn record_server_latency(&mut self, server: &ServerId, latency: Duration) {
+        let samples = self.server_latencies.entry(server.clone()).or_default();
+        if samples.len() == SERVER_LATENCY_WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(latency);
+    }
+
+    pub fn load_estimate(&self, server: &str) -> Option<LoadEstimate> {
+        let samples = self.server_latencies.get(server)?;
+        estimate_load_time(&samples.iter().copied().collect::<Vec<_>>())
+    }
+
+    pub fn record_send_failure(&mut self) {
+        self.send_failures += 1;
+    }
+
+    pub fn record_timeout(&mut self) {
+        self.timeouts += 1;
+    }
+
+    pub fn record_coalesced(&mut self) {
+        self.coalesced += 1;
+    }
+
+    pub fn average_latency(&self) -> Option<Duration> {
+        if self.latency_samples == 0 {
+            None
+        } else {
+            Some(self.latency_total / self.latency_samples)
+        }
+    }
+
+    // Median of the recent window, once it is full
+    pub fn recent_median_latency(&self) -> Option<Duration> {
+        if self.recent_latencies.len() < RECENT_LATENCY_WINDOW {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.recent_latencies.iter().copied().collect();
+        sorted.sort();
+        Some(sorted[sorted.len() / 2])
+    }
+
+    // A new connection starts a new window
+    pub fn reset_recent_latency(&mut self) {
+        self.recent_latencies.clear();
+    }
+
+    pub fn summary(&self) -> String {
+        let latency = match self.average_latency() {
+            Some(latency) => format!("{:.1} s", latency.as_secs_f32()),
+            None => "n/a".to_string(),
+        };
+        format!(
+            "Requests sent: {}\nCoalesced: {}\nReplies received: {}\nSend failures: {}\nTimeouts: {}\nBytes out: {}\nBytes in: {}\nAverage latency: {}",
+            self.requests_sent,
+            self.coalesced,
+            self.replies_received,
+            self.send_failures,
+            self.timeouts,
+            self.bytes_out,
+            self.bytes_in,
+            latency,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server_id::ServerIds;
+
+    fn secs(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|value| Duration::from_secs(*value)).collect()
+    }
+
+    #[test]
+    fn no_estimate_without_samples() {
+        assert_eq!(estimate_load_time(&[]), None);
+        let metrics = Metrics::default();
+        assert_eq!(metrics.load_estimate("server.addr@gateway"), None);
+    }
+
+    #[test]
+    fn a_single_sample_is_both_typical_and_slow() {
+        let estimate = estimate_load_time(&secs(&[4])).unwrap();
+        assert_eq!(estimate, LoadEstimate { typical: Duration::from_secs(4), slow: Duration::from_secs(4) });
+    }
+
+    #[test]
+    fn an_outlier_raises_only_the_slow_estimate() {
+        let estimate = estimate_load_time(&secs(&[3, 60, 2, 4, 3])).unwrap();
+        assert_eq!(estimate.typical, Duration::from_secs(3));
+        assert_eq!(estimate.slow, Duration::from_secs(60));
+
+        // In a longer history one outlier is above the 90th percentile
+        let mut samples = secs(&[3; 19]);
+        samples.push(Duration::from_secs(60));
+        let estimate = estimate_load_time(&samples).unwrap();
+        assert_eq!(estimate, LoadEstimate { typical: Duration::from_secs(3), slow: Duration::from_secs(3) });
+    }
+
+    #[test]
+    fn estimates_are_per_server_over_recent_replies() {
+        let mut ids = ServerIds::default();
+        let fast = ids.intern("fast.addr@gateway");
+        let slow = ids.intern("slow.addr@gateway");
+        let mut metrics = Metrics::default();
+        for _ in 0..SERVER_LATENCY_WINDOW {
+            metrics.record_server_latency(&slow, Duration::from_secs(30));
+        }
+        for _ in 0..SERVER_LATENCY_WINDOW {
+            metrics.record_server_latency(&slow, Duration::from_secs(5));
+        }
+        metrics.record_server_latency(&fast, Duration::from_secs(1));
+
+        assert_eq!(metrics.load_estimate("slow.addr@gateway").unwrap().slow, Duration::from_secs(5));
+        assert_eq!(metrics.load_estimate("fast.addr@gateway").unwrap().typical, Duration::from_secs(1));
+        assert_eq!(metrics.load_estimate("other.addr@gateway"), None);
+    }
+
+    #[test]
+    fn the_recent_median_waits_for_a_full_window() {
+        let mut metrics = Metrics::default();
+        for latency in secs(&[1, 9, 2, 8]) {
+            metrics.record_reply(10, Some(latency));
+        }
+        assert_eq!(metrics.recent_median_latency(), None);
+        metrics.record_reply(10, Some(Duration::from_secs(3)));
+        assert_eq!(metrics.recent_median_latency(), Some(Duration::from_secs(3)));
+        assert_eq!(metrics.average_latency(), Some(Duration::from_millis(4600)));
+
+        metrics.reset_recent_latency();
+        assert_eq!(metrics.recent_median_latency(), None);
+        assert_eq!(metrics.average_latency(), Some(Duration::from_millis(4600)));
+    }
+}
@@ -1,14 +1,57 @@
 use nym_sdk::mixnet;
-use nym_sdk::mixnet::MixnetMessageSender;
 use egui::{Ui, TextEdit, ScrollArea, Color32};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use tokio::runtime::Runtime;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::OnceLock;
 use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
 use eframe::App;
-use std::time::{Duration, Instant};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use nymview_protocol::{self as protocol, compression, Data, ErrorStatus, NymUrl, Request, Response};
+use crate::address::{self, Inference};
+use crate::bookmarks::Bookmarks;
+use crate::config;
+use crate::frame_timing::{FrameTimings, Phase};
+use crate::gateway_stats::GatewayStats;
+use crate::health::{self, Fix, HealthReport, Status as HealthStatus};
+use crate::history::SavedHistory;
+use crate::diagnostics;
+use crate::downloads::{self, Download, DownloadState};
+use crate::identicon;
+use crate::images::NymImageLoader;
+use crate::in_flight::{InFlight, RequestKey};
+use crate::indicators;
+use crate::json_view::JsonDocument;
+use crate::markdown::{self, PreparedPage, PreparedReply, ReplyStatus};
+use crate::metrics::Metrics;
+use crate::render_cache::RenderCaches;
+use crate::replay::{self, RecordingTransport, ReplayTransport, SessionMode};
+use crate::request_format::{self, RequestFormat};
+use crate::report::{Report, ReportDraft, SentReport, SentReports};
+use crate::reset::{self, Category, ResetDialog, ResetSummary};
+use crate::scroll::{ScrollMemory, ScrollPolicy};
+use crate::server_filter::FilterMode;
+use crate::server_id::{ServerId, ServerIds};
+use crate::settings::{self, Settings, WindowGeometry};
+use crate::encrypted_storage::EncryptedStorage;
+use crate::storage::{FileStorage, Storage};
+use crate::templates::{PendingTemplate, RequestTemplate};
+use crate::theme::{self, Theme};
+use crate::timefmt;
+use crate::transport::{SendFailure, Transport};
+use crate::undo::{Removed, UndoStack};
+use crate::upload::{self, PastedImage};
+#[cfg(any(test, feature = "memory-transport"))]
+use crate::transport::{MemoryResponses, MemoryTransport};
 
 // Global runtime for async operations
 static RUNTIME: Lazy<Runtime> = Lazy::new(|| {
@@ -19,20 +62,229 @@ static RUNTIME: Lazy<Runtime> = Lazy::new(|| {
 static GUI_TO_MIXNET_SENDER: OnceLock<Arc<Mutex<Option<mpsc::UnboundedSender<BrowserMessage>>>>> =
     OnceLock::new();
 
-#[derive(Debug)]
+// Requests in flight and who waits for each reply. Replies to requests
+// not in here, or with nobody waiting, go to the GUI.
+static IN_FLIGHT: Lazy<Mutex<InFlight<Waiter>>> = Lazy::new(|| Mutex::new(InFlight::new(PAGE_LOAD_TIMEOUT)));
+// IDs for navigate() count down from the top, so they never meet the GUI's
+static NEXT_AWAITED_ID: AtomicU64 = AtomicU64::new(u64::MAX);
+
+const CONTENT_FONT: &str = "content";
+const NAVIGATION_DEBOUNCE: Duration = Duration::from_millis(500);
+const MAX_MESSAGES_PER_FRAME: usize = 8;
+const SEND_RETRIES: u32 = 3;
+const SEND_RETRY_BASE: Duration = Duration::from_millis(200);
+// Sent with each anonymous request; the server's client asks for more when
+// a reply needs them
+const ANONYMOUS_REPLY_SURBS: u32 = 20;
+const HEARTBEAT: Duration = Duration::from_secs(1);
+const SUSPEND_GAP: Duration = Duration::from_secs(10);
+// Sent by the client to its own address to see that the mixnet still
+// carries its traffic, e.g. after the system slept
+const CONNECTION_CHECK: &str = "NYMVIEW CONNECTION-CHECK";
+// How long the outcome of that check stays in the status line
+const RESUME_NOTICE_DURATION: Duration = Duration::from_secs(10);
+const MAX_LINK_HINTS: usize = 9;
+// Pages inside pages inside the page, and no deeper
+const MAX_TRANSCLUSION_DEPTH: usize = 2;
+const LONG_PAGE_WORDS: usize = 500;
+// Pages whose render state is kept for going back and forth
+const RENDER_CACHE_PAGES: usize = 8;
+const HEALTH_URL: &str = "nymview://health";
+const REPORTS_URL: &str = "nymview://reports";
+const PAGE_LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+// REDIRECT replies followed in a row before giving up on a loop
+const MAX_REDIRECTS: u32 = 5;
+const GATEWAY_STATS_SAVE_DELAY: Duration = Duration::from_secs(10);
+// How long a window layout reset may take to reach the window
+const WINDOW_RESET_WAIT: Duration = Duration::from_secs(1);
+// Config directory with the client keys for a persistent Nym address
+pub const CLIENT_IDENTITY_DIR: &str = "mixnet_client";
+pub const WELCOME_FILE: &str = "welcome.md";
+
+// Restoring further down than this is pointed out, with a way back to the top
+const FAR_SCROLL_OFFSET: f32 = 600.0;
+const SCROLL_NOTICE_DURATION: Duration = Duration::from_secs(4);
+
+const OFFLINE_MODE: &str = "Offline mode is on \u{2014} nothing is sent until you go online.";
+const BANDWIDTH_EXHAUSTED: &str = "Gateway bandwidth exhausted \u{2014} top up or wait. Sending is paused.";
+
+const DEFAULT_WELCOME_PAGE: &str = r#"# NymView for Nym Mixnet
+
+## Features:
+- **Secure** communication via Nym Mixnet
+- **Markdown** support
+- **Private** navigation
+- **History** navigation (◀ ▶ buttons)
+- **Auto-reload** (🔄 button)
+- **30-second timeout** for unresponsive servers
+
+### Example content:
+- `nym://server/` - Homepage
+- `nym://server/about` - About us
+- `nym://server/help` - Help
+
+*Enter an address to begin*"#;
+
+// A send waiting for another attempt after a transient failure
+struct QueuedSend {
+    request_id: u64,
+    recipient: String,
+    message: String,
+    reply_surbs: u32,
+    attempt: u32,
+    due: tokio::time::Instant,
+}
+
+enum BookmarkAction {
+    Open(String),
+    Delete(usize),
+    // Index, new title and folder
+    Save(usize, String, String),
+}
+
+#[derive(Debug, Clone)]
 pub(crate) enum BrowserMessage {
-    SendRequest { recipient: String, message: String },
-    ReceivedMessage { content: String},
+    // reply_surbs > 0 sends anonymously, see Transport::send
+    SendRequest { request_id: u64, recipient: String, message: String, reply_surbs: u32 },
+    // request_id is None for replies from servers that predate request IDs.
+    // Replies in the OK/ERROR format arrive as protocol::MARKDOWN_TYPE.
+    // `prepared` is a text reply worked out for the page view ahead of time
+    ReceivedMessage { request_id: Option<u64>, content_type: String, data: Vec<u8>, prepared: Option<Box<PreparedReply>> },
+    RequestFailed { request_id: u64, error: String },
+    BandwidthExhausted { request_id: u64, error: String },
+    DiagnosticsExported { result: Result<PathBuf, String> },
+    ImagePasted { result: Result<PastedImage, String> },
+    DownloadFinished { id: u64, result: Result<PathBuf, String> },
+    HealthChecked { report: HealthReport },
+    Transcluded { url: String, result: Result<String, String> },
+    Disconnect,
+    // Sends CONNECTION_CHECK; answered by ConnectionChecked
+    CheckConnection,
+    ConnectionChecked { result: Result<(), String> },
     ConnectionStatus { status: String, loading: bool, client_address: String },
 }
 
+// How the connection check after waking from sleep went
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ResumeCheck {
+    Checking,
+    Healthy,
+    // The client was replaced; why the check failed
+    Reconnected(String),
+}
+
+// Who a reply is handed to
+#[derive(Debug)]
+enum Waiter {
+    // The page view, which drops replies it no longer waits for. Its
+    // page is prepared with the setting in effect when it was requested.
+    Gui { trim_trailing_whitespace: bool },
+    // A navigate() caller
+    Awaited(oneshot::Sender<BrowserMessage>),
+}
+
+// Why navigate() has no page to give back
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FetchError {
+    // Offline, not connected, blocked by the server filter and the like
+    NotSent(String),
+    // Only replies tagged with the request ID can be awaited
+    NoRequestIds(String),
+    // Sent, but lost on the way
+    Failed(String),
+    // The server answered NOT_FOUND, REDIRECT, …
+    Status(ErrorStatus, String),
+    // The reply was a file of this content type
+    NotAPage(String),
+    TimedOut,
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotSent(e) | Self::Failed(e) => write!(f, "{}", e),
+            Self::NoRequestIds(server) => write!(f, "{} is not known to echo request IDs", server),
+            Self::Status(status, message) => write!(f, "{}: {}", status.code(), message),
+            Self::NotAPage(content_type) => write!(f, "The server sent {} content, not a page", content_type),
+            Self::TimedOut => write!(f, "The page did not arrive in time"),
+        }
+    }
+}
+
+// Reply with content that cannot be shown as a page
+#[derive(Debug, Clone)]
+pub(crate) struct ReceivedFile {
+    pub url: String,
+    pub data: Data,
+}
+
+// A page shown inside the displayed one, fetched when its expander is
+// first opened
+#[derive(Debug, Clone)]
+pub(crate) enum Transclusion {
+    Loading,
+    // Its segments, and the links in them to hook
+    Loaded(Vec<markdown::Segment>, Vec<String>),
+    Failed(String),
+}
+
+impl Transclusion {
+    fn loaded(content: &str, server: &str) -> Self {
+        let display_content = markdown::resolve_image_urls(&markdown::prepare_for_display(content), server);
+        let segments = markdown::split_transclusions(&display_content);
+        let mut links = markdown::link_targets(&display_content);
+        // Transclusions past the depth limit are shown as links
+        for segment in &segments {
+            if let markdown::Segment::Transclusion { target, .. } = segment {
+                if !links.contains(target) {
+                    links.push(target.clone());
+                }
+            }
+        }
+        Self::Loaded(segments, links)
+    }
+}
+
+// Last request actually handed to the mixnet, for the privacy summary
 #[derive(Debug, Clone)]
+pub(crate) struct SentRequest {
+    pub server: ServerId,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct HistoryEntry {
-    pub server: String,
+    pub server: ServerId,
     pub page: String,
     pub content: String,
-    #[allow(dead_code)]
-    pub timestamp: Instant,
+    // None until the reply for this entry arrives
+    pub fetched_at: Option<SystemTime>,
+    pub server_error: bool,
+    // Status of an error page; empty for entries saved before status codes
+    #[serde(default)]
+    pub error_code: String,
+    // Saved without its page, which is fetched again when shown
+    #[serde(default)]
+    pub content_dropped: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PageSource {
+    // Fetched from the server for this view
+    Live,
+    // Replayed from history without a new request
+    History,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PageState {
+    // Nothing navigated to yet; the welcome page is shown
+    None,
+    Loaded,
+    // The server answered with an error status; its message is the content
+    ServerError(ErrorStatus),
+    // No answer arrived (timeout, send failure); nothing was served
+    TransportError(String),
 }
 
 pub struct NymMixnetBrowser {
@@ -48,14 +300,171 @@ pub struct NymMixnetBrowser {
     pub(crate) message_sender: Option<mpsc::UnboundedSender<BrowserMessage>>,
     pub(crate) history: Vec<HistoryEntry>,
     pub(crate) connection_attempted: bool,
-    pub(crate) md_cache: CommonMarkCache,
+    pub(crate) md_caches: RenderCaches,
+    pub(crate) welcome_md_cache: CommonMarkCache,
     pub(crate) pending_navigation: Option<String>,
     pub(crate) current_history_index: usize,
     pub(crate) page_load_start_time: Option<Instant>,
+    pub(crate) welcome_content: String,
+    pub(crate) next_request_id: u64,
+    pub(crate) pending_request_id: Option<u64>,
+    pub(crate) settings: Settings,
+    pub(crate) show_settings: bool,
+    pub(crate) json_document: Option<JsonDocument>,
+    pub(crate) address_edited: bool,
+    pub(crate) focus_content_pending: bool,
+    pub(crate) metrics: Metrics,
+    pub(crate) offline: bool,
+    pub(crate) address_before_correction: Option<String>,
+    pub(crate) external_url: Option<String>,
+    pub(crate) pending_template: Option<PendingTemplate>,
+    pub(crate) new_template: RequestTemplate,
+    pub(crate) window_geometry: Option<WindowGeometry>,
+    pub(crate) window_placement_checked: bool,
+    pub(crate) new_filter_entry: String,
+    pub(crate) last_request: Option<SentRequest>,
+    pub(crate) show_page_info: bool,
+    pub(crate) display_content: String,
+    // Render cache key of display_content, set along with it
+    pub(crate) display_key: u64,
+    pub(crate) content_font_loaded: bool,
+    pub(crate) content_font_input: String,
+    // Current page could not be rendered as markdown and is shown as raw text
+    pub(crate) render_fallback: bool,
+    // Set when the gateway ran out of bandwidth; sends stay paused until cleared
+    pub(crate) bandwidth_exhausted_since: Option<Instant>,
+    pub(crate) scroll_memory: ScrollMemory,
+    // Offset of the content area and the page it belongs to
+    pub(crate) scroll_offset: f32,
+    pub(crate) scroll_page: String,
+    // Next content comes from reload or back/forward and may restore its offset
+    pub(crate) restore_scroll: bool,
+    pub(crate) pending_scroll: Option<f32>,
+    pub(crate) scroll_restored_at: Option<Instant>,
+    pub(crate) diagnostics_path: Option<PathBuf>,
+    pub(crate) exporting_diagnostics: bool,
+    pub(crate) page_state: PageState,
+    pub(crate) gateway_stats: GatewayStats,
+    pub(crate) slow_gateway_warning: bool,
+    pub(crate) slow_gateway_dismissed: bool,
+    // Persistence for settings and other state kept between runs
+    pub(crate) storage: Arc<dyn Storage>,
+    pub(crate) encryption_passphrase: String,
+    pub(crate) encryption_confirm: String,
+    // Target and start of the latest navigation, for debouncing
+    pub(crate) last_navigation: Option<(String, Instant)>,
+    // Where the displayed page came from and when it was fetched
+    pub(crate) page_source: PageSource,
+    pub(crate) page_fetched_at: Option<SystemTime>,
+    // Numbered overlay of the page's first links (F, Alt+1..9)
+    pub(crate) show_link_hints: bool,
+    // One shared copy of each server address in history and requests
+    pub(crate) server_ids: ServerIds,
+    // Prose words on the current page, for the reading time estimate
+    pub(crate) page_words: usize,
+    // Link followed with tracking parameters removed, and how many
+    pub(crate) stripped_link: Option<(String, usize)>,
+    // Follow the next link exactly as written
+    pub(crate) keep_query_once: bool,
+    pub(crate) stripped_params_input: String,
+    // Highlighted entry while Ctrl+` is held
+    pub(crate) server_switcher: Option<usize>,
+    // Report being written, the local copy of sent ones and the collector's answer
+    pub(crate) report_draft: Option<ReportDraft>,
+    pub(crate) sent_reports: SentReports,
+    // ID of the report waiting for the collector's answer
+    pub(crate) report_request_id: Option<u64>,
+    pub(crate) report_status: Option<String>,
+    // ID of the image upload sent along with the report, and its outcome
+    pub(crate) report_upload_id: Option<u64>,
+    pub(crate) report_upload_status: Option<String>,
+    // The clipboard is being read for an image to attach
+    pub(crate) pasting_image: bool,
+    // Content theme from settings, and the state of its file for live reload
+    pub(crate) theme: Theme,
+    pub(crate) theme_error: Option<String>,
+    pub(crate) theme_modified: Option<SystemTime>,
+    pub(crate) theme_checked_at: Option<Instant>,
+    pub(crate) theme_input: String,
+    // Connection check after the last suspend, and when it started or ended
+    pub(crate) resume_check: Option<(ResumeCheck, Instant)>,
+    // Start of the previous frame on both clocks, to notice suspends
+    pub(crate) last_frame: Option<(Instant, SystemTime)>,
+    // Page whose request offline mode blocked, opened once connected if
+    // the user asked to go online and retry
+    pub(crate) offline_blocked_url: Option<String>,
+    pub(crate) retry_when_online: bool,
+    // Developer overlay, off unless enabled in Settings
+    pub(crate) frame_timings: FrameTimings,
+    // "Reset NymView…" window, open while Some
+    pub(crate) reset_dialog: Option<ResetDialog>,
+    // Custom request format being edited, and the server it belongs to
+    pub(crate) request_format_input: String,
+    pub(crate) request_format_server: String,
+    // Recent removals that can still be undone
+    pub(crate) undo: UndoStack,
+    // Result of the startup self-check, shown at nymview://health
+    pub(crate) health: Option<HealthReport>,
+    pub(crate) health_checking: bool,
+    pub(crate) show_health: bool,
+    // Local copies of sent reports, listed at nymview://reports
+    pub(crate) show_sent_reports: bool,
+    // Starred pages, and the one being renamed (index, title, folder)
+    pub(crate) bookmarks: Bookmarks,
+    pub(crate) bookmark_editing: Option<(usize, String, String)>,
+    pub(crate) show_bookmarks: bool,
+    // Images in the displayed page, fetched over the mixnet; request ID to
+    // image URL and when it was asked for
+    pub(crate) image_loader: Arc<NymImageLoader>,
+    pub(crate) image_requests: HashMap<u64, (String, Instant)>,
+    // Last reply that is not a page, until it is saved or navigated away from
+    pub(crate) received_file: Option<ReceivedFile>,
+    pub(crate) downloads: Vec<Download>,
+    pub(crate) next_download_id: u64,
+    pub(crate) show_downloads: bool,
+    // Links on the displayed page that are followed through handle_link_click
+    pub(crate) page_links: Vec<String>,
+    // The first of them, reachable with Alt+1..9
+    pub(crate) link_hints: Vec<String>,
+    // The displayed page cut at its transclusions
+    pub(crate) page_segments: Vec<markdown::Segment>,
+    // Pages transcluded into it by nym:// URL, kept while it is shown
+    pub(crate) transclusions: HashMap<String, Transclusion>,
+    // REDIRECT replies followed since the last page arrived
+    pub(crate) redirects_followed: u32,
+    // history.json has changes not yet written, and a write is running
+    pub(crate) history_dirty: bool,
+    pub(crate) history_saving: Arc<AtomicBool>,
+    // Server waiting to answer whether it reads request headers, and the
+    // servers asked this session
+    pub(crate) header_probe: Option<(String, Instant)>,
+    pub(crate) probed_servers: HashSet<String>,
+    // Images on the current page from other servers, waiting for a click,
+    // and the servers images may be loaded from this session
+    pub(crate) blocked_images: Vec<String>,
+    pub(crate) image_servers_allowed: HashSet<String>,
+    // Redirect to another server, waiting for the user to follow it
+    pub(crate) redirect_to_confirm: Option<String>,
+    // Set by a layout reset until the window has taken it on
+    pub(crate) window_reset_at: Option<Instant>,
+    // First gateway latency not yet written to disk
+    pub(crate) gateway_stats_changed: Option<Instant>,
+    // How this session reaches servers when set from the command line, which
+    // wins over the settings; and how the current connection does
+    pub(crate) session_override: Option<SessionMode>,
+    pub(crate) session_mode: SessionMode,
+    // Serve pages from memory instead of connecting to the mixnet
+    #[cfg(any(test, feature = "memory-transport"))]
+    pub(crate) memory_responses: Option<MemoryResponses>,
 }
 
 impl NymMixnetBrowser {
-    pub fn new() -> Self {
+    pub fn with_storage(storage: Arc<dyn Storage>) -> Self {
+        let settings = Settings::load(storage.as_ref());
+        let stripped_params_input = settings.stripped_query_params.join(", ");
+        let offline = settings.offline_mode;
+        let mut server_ids = ServerIds::default();
+        let history = SavedHistory::load(storage.as_ref(), &mut server_ids).entries;
         Self {
             address_bar: String::new(),
             current_content: String::new(),
@@ -67,29 +476,190 @@ impl NymMixnetBrowser {
             client_address: String::new(),
             message_receiver: None,
             message_sender: None,
-            history: Vec::new(),
+            // Starts on the welcome page after the restored entries, so
+            // back leads into the previous session
+            current_history_index: history.len(),
+            history,
             connection_attempted: false,
-            md_cache: CommonMarkCache::default(),
+            md_caches: RenderCaches::new(RENDER_CACHE_PAGES),
+            welcome_md_cache: CommonMarkCache::default(),
             pending_navigation: None,
-            current_history_index: 0,
             page_load_start_time: None,
+            welcome_content: Self::load_welcome_page(storage.as_ref()),
+            next_request_id: 0,
+            pending_request_id: None,
+            window_geometry: settings.window,
+            settings,
+            show_settings: false,
+            json_document: None,
+            address_edited: false,
+            focus_content_pending: false,
+            metrics: Metrics::default(),
+            offline,
+            address_before_correction: None,
+            external_url: None,
+            pending_template: None,
+            new_template: RequestTemplate { name: String::new(), path: String::new() },
+            window_placement_checked: false,
+            new_filter_entry: String::new(),
+            last_request: None,
+            show_page_info: false,
+            display_content: String::new(),
+            content_font_loaded: false,
+            content_font_input: String::new(),
+            render_fallback: false,
+            bandwidth_exhausted_since: None,
+            scroll_memory: ScrollMemory::default(),
+            scroll_offset: 0.0,
+            scroll_page: String::new(),
+            restore_scroll: false,
+            pending_scroll: None,
+            scroll_restored_at: None,
+            diagnostics_path: None,
+            exporting_diagnostics: false,
+            page_state: PageState::None,
+            gateway_stats: GatewayStats::load(storage.as_ref()),
+            slow_gateway_warning: false,
+            slow_gateway_dismissed: false,
+            storage: storage.clone(),
+            encryption_passphrase: String::new(),
+            encryption_confirm: String::new(),
+            last_navigation: None,
+            page_source: PageSource::Live,
+            page_fetched_at: None,
+            show_link_hints: false,
+            server_ids,
+            page_words: 0,
+            stripped_link: None,
+            keep_query_once: false,
+            stripped_params_input,
+            server_switcher: None,
+            report_draft: None,
+            sent_reports: SentReports::load(storage.as_ref()),
+            report_request_id: None,
+            report_status: None,
+            report_upload_id: None,
+            report_upload_status: None,
+            pasting_image: false,
+            theme: Theme::default(),
+            theme_error: None,
+            theme_modified: None,
+            theme_checked_at: None,
+            theme_input: String::new(),
+            resume_check: None,
+            last_frame: None,
+            offline_blocked_url: None,
+            retry_when_online: false,
+            frame_timings: FrameTimings::default(),
+            reset_dialog: None,
+            request_format_input: String::new(),
+            request_format_server: String::new(),
+            undo: UndoStack::default(),
+            health: None,
+            health_checking: false,
+            show_health: false,
+            show_sent_reports: false,
+            bookmarks: Bookmarks::load(storage.as_ref()),
+            bookmark_editing: None,
+            show_bookmarks: false,
+            image_loader: Arc::new(NymImageLoader::default()),
+            image_requests: HashMap::new(),
+            received_file: None,
+            downloads: Vec::new(),
+            next_download_id: 0,
+            show_downloads: false,
+            page_links: Vec::new(),
+            link_hints: Vec::new(),
+            page_segments: Vec::new(),
+            transclusions: HashMap::new(),
+            redirects_followed: 0,
+            history_dirty: false,
+            history_saving: Arc::new(AtomicBool::new(false)),
+            header_probe: None,
+            probed_servers: HashSet::new(),
+            blocked_images: Vec::new(),
+            image_servers_allowed: HashSet::new(),
+            redirect_to_confirm: None,
+            window_reset_at: None,
+            display_key: 0,
+            gateway_stats_changed: None,
+            session_override: None,
+            session_mode: SessionMode::Live,
+            #[cfg(any(test, feature = "memory-transport"))]
+            memory_responses: None,
         }
     }
 
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
     pub fn init(&mut self) {
         if !self.connection_attempted {
             let (tx, rx) = mpsc::unbounded_channel::<BrowserMessage>();
             self.message_sender = Some(tx);
             self.message_receiver = Some(rx);
             self.connection_attempted = true;
-            self.start_connection();
+            if self.offline {
+                self.loading = false;
+                self.connection_status = "Offline".to_string();
+            } else {
+                self.start_connection();
+            }
+        }
+    }
+
+    // Must be called before the first frame, i.e. before init() connects
+    #[cfg(any(test, feature = "memory-transport"))]
+    pub fn use_memory_transport(&mut self, responses: MemoryResponses) {
+        self.memory_responses = Some(responses);
+    }
+
+    // Must be called before the first frame, i.e. before init() connects
+    pub fn set_session_mode(&mut self, mode: SessionMode) {
+        self.session_override = Some(mode);
+    }
+
+    fn configured_session_mode(&self) -> SessionMode {
+        if let Some(mode) = &self.session_override {
+            mode.clone()
+        } else if self.settings.replay_session {
+            SessionMode::Replay(replay::default_file())
+        } else if self.settings.record_session {
+            SessionMode::Record(replay::default_file())
+        } else {
+            SessionMode::Live
         }
     }
 
     fn start_connection(&mut self) {
+        #[cfg(any(test, feature = "memory-transport"))]
+        if let (Some(sender), Some(responses)) = (self.message_sender.clone(), self.memory_responses.clone()) {
+            Self::connect_memory(sender, responses);
+            return;
+        }
+
+        self.session_mode = self.configured_session_mode();
+        if let (Some(sender), SessionMode::Replay(file)) = (self.message_sender.clone(), &self.session_mode) {
+            let latency = Duration::from_millis(self.settings.replay_latency_ms);
+            match ReplayTransport::load(file, latency) {
+                Ok(transport) => Self::start_mixnet_task(Box::new(transport), sender),
+                Err(e) => {
+                    self.loading = false;
+                    self.connection_status = format!("Replay failed: {}", e);
+                }
+            }
+            return;
+        }
+
         if let Some(sender) = self.message_sender.clone() {
+            let persistent = self.settings.persistent_identity;
+            let record = match &self.session_mode {
+                SessionMode::Record(file) => Some(file.clone()),
+                _ => None,
+            };
             RUNTIME.spawn(async move {
-                match Self::connect_with_status(sender).await {
+                match Self::connect_with_status(sender, persistent, record).await {
                     Ok(_) => {},
                     Err(e) => eprintln!("Connection failed: {}", e),
                 }
@@ -97,220 +667,1098 @@ impl NymMixnetBrowser {
         }
     }
 
-    async fn connect_with_status(sender: mpsc::UnboundedSender<BrowserMessage>) -> Result<(), String> {
+    fn go_offline(&mut self) {
+        if let Some(arc) = GUI_TO_MIXNET_SENDER.get() {
+            if let Some(sender) = arc.lock().unwrap().take() {
+                let _ = sender.send(BrowserMessage::Disconnect);
+            }
+        }
+
+        self.offline = true;
+        self.save_offline_mode();
+        self.connection_status = "Offline".to_string();
+        self.client_address.clear();
+        if self.page_loading {
+            self.page_loading = false;
+            self.page_load_start_time = None;
+            self.pending_request_id = None;
+        }
+    }
+
+    fn go_online(&mut self) {
+        self.offline = false;
+        self.save_offline_mode();
+        self.bandwidth_exhausted_since = None;
+        self.loading = true;
+        self.connection_status = "Connecting to Mixnet...".to_string();
+        self.start_connection();
+    }
+
+    // Whether the mixnet client survived a suspend. The gateway may have
+    // dropped it meanwhile, which nothing else would notice. Recordings
+    // are replayed without a mixnet, so there is nothing to check.
+    fn check_connection(&mut self) {
+        if self.session_mode != SessionMode::Live {
+            return;
+        }
+        let Ok(sender) = self.mixnet_sender() else {
+            return;
+        };
+        if sender.send(BrowserMessage::CheckConnection).is_ok() {
+            self.resume_check = Some((ResumeCheck::Checking, Instant::now()));
+        }
+    }
+
+    // The check failed, so the client is replaced by a new one
+    fn connection_lost(&mut self, error: String) {
+        eprintln!("Connection check after sleep failed: {}", error);
+        self.resume_check = Some((ResumeCheck::Reconnected(error), Instant::now()));
+        self.reconnect();
+    }
+
+    // Drops the current client and connects anew with the current settings
+    fn reconnect(&mut self) {
+        if let Some(arc) = GUI_TO_MIXNET_SENDER.get() {
+            if let Some(sender) = arc.lock().unwrap().take() {
+                let _ = sender.send(BrowserMessage::Disconnect);
+            }
+        }
+        self.client_address.clear();
+        self.loading = true;
+        self.connection_status = "Reconnecting to Mixnet...".to_string();
+        self.start_connection();
+    }
+
+    fn show_resume_check(&mut self, ui: &mut Ui) {
+        let expired = self.resume_check.as_ref().is_some_and(|(check, since)| {
+            *check != ResumeCheck::Checking && since.elapsed() > RESUME_NOTICE_DURATION
+        });
+        if expired {
+            self.resume_check = None;
+        }
+        let Some((check, _)) = &self.resume_check else {
+            return;
+        };
+        match check {
+            ResumeCheck::Checking => {
+                indicators::busy(ui, self.settings.reduce_motion);
+                ui.weak("Resumed from sleep, checking the connection");
+            }
+            ResumeCheck::Healthy => {
+                ui.weak("Resumed from sleep, the connection is fine");
+                ui.ctx().request_repaint_after(RESUME_NOTICE_DURATION);
+            }
+            ResumeCheck::Reconnected(error) => {
+                ui.colored_label(Color32::from_rgb(200, 150, 0), "Resumed from sleep with the connection lost; reconnected")
+                    .on_hover_text(error.as_str());
+                ui.ctx().request_repaint_after(RESUME_NOTICE_DURATION);
+            }
+        }
+    }
+
+    fn save_offline_mode(&mut self) {
+        self.settings.offline_mode = self.offline;
+        if let Err(e) = self.settings.save(self.storage.as_ref()) {
+            eprintln!("Could not save settings: {}", e);
+        }
+    }
+
+    // Every send over the mixnet goes through here, so offline mode holds
+    // for all of them
+    fn mixnet_sender(&self) -> Result<mpsc::UnboundedSender<BrowserMessage>, String> {
+        if self.offline {
+            return Err(OFFLINE_MODE.to_string());
+        }
+        Self::get_gui_sender().ok_or_else(|| "Not connected to Mixnet".to_string())
+    }
+
+    // With `persistent`, keys and gateway registration are kept on disk so
+    // the client address stays the same across restarts. With `record`,
+    // every reply is also written to that file.
+    async fn connect_with_status(
+        sender: mpsc::UnboundedSender<BrowserMessage>,
+        persistent: bool,
+        record: Option<PathBuf>,
+    ) -> Result<(), String> {
         let _ = sender.send(BrowserMessage::ConnectionStatus {
             status: "Connecting to Mixnet...".to_string(),
             loading: true,
             client_address: String::new(),
         });
 
-        let client = mixnet::MixnetClientBuilder::new_ephemeral()
-            .build()
-            .map_err(|e| format!("Client creation error: {}", e))?;
+        // The two builders produce differently typed clients until connected
+        let connected_client = if persistent {
+            let storage = Self::identity_storage().await?;
+            mixnet::MixnetClientBuilder::new_with_storage(storage)
+                .build()
+                .map_err(|e| format!("Client creation error: {}", e))?
+                .connect_to_mixnet()
+                .await
+        } else {
+            mixnet::MixnetClientBuilder::new_ephemeral()
+                .build()
+                .map_err(|e| format!("Client creation error: {}", e))?
+                .connect_to_mixnet()
+                .await
+        }
+        .map_err(|e| format!("Connection error: {}", e))?;
+
+        let transport: Box<dyn Transport> = match record {
+            Some(file) => Box::new(RecordingTransport::new(Box::new(connected_client), file)),
+            None => Box::new(connected_client),
+        };
+        Self::start_mixnet_task(transport, sender);
+        Ok(())
+    }
 
-        let connected_client = client
-            .connect_to_mixnet()
+    // The SDK keeps these files itself, outside Storage, so passphrase
+    // encryption does not cover them
+    async fn identity_storage() -> Result<mixnet::OnDiskPersistent, String> {
+        let dir = config::ensure_config_dir(CLIENT_IDENTITY_DIR)
+            .map_err(|e| format!("Could not create identity directory: {}", e))?;
+        let paths = mixnet::StoragePaths::new_from_dir(&dir)
+            .map_err(|e| format!("Invalid identity directory: {}", e))?;
+        mixnet::OnDiskPersistent::from_paths(paths.into(), &Default::default())
             .await
-            .map_err(|e| format!("Connection error: {}", e))?;
+            .map_err(|e| format!("Could not open client identity: {}", e))
+    }
 
-        let client_address = connected_client.nym_address().to_string();
+    #[cfg(any(test, feature = "memory-transport"))]
+    fn connect_memory(sender: mpsc::UnboundedSender<BrowserMessage>, responses: MemoryResponses) {
+        Self::start_mixnet_task(Box::new(MemoryTransport::new("memory-client", responses)), sender);
+    }
 
-        let _ = sender.send(BrowserMessage::ConnectionStatus {
-            status: "Connected".to_string(),
-            loading: false,
-            client_address: client_address.clone(),
-        });
+    fn start_mixnet_task(transport: Box<dyn Transport>, sender: mpsc::UnboundedSender<BrowserMessage>) {
+        let client_address = transport.address();
 
         GUI_TO_MIXNET_SENDER.get_or_init(|| Arc::new(Mutex::new(None)));
+        IN_FLIGHT.lock().unwrap().clear();
         let (gui_to_mixnet_tx, gui_to_mixnet_rx) = mpsc::unbounded_channel::<BrowserMessage>();
         *GUI_TO_MIXNET_SENDER.get().unwrap().lock().unwrap() = Some(gui_to_mixnet_tx);
 
+        // Only report "Connected" once requests can actually be sent
+        let _ = sender.send(BrowserMessage::ConnectionStatus {
+            status: "Connected".to_string(),
+            loading: false,
+            client_address,
+        });
+
         RUNTIME.spawn(Self::mixnet_task(
-            connected_client,
+            transport,
             gui_to_mixnet_rx,
             sender,
         ));
-
-        Ok(())
     }
 
     async fn mixnet_task(
-        mut client: mixnet::MixnetClient,
+        mut transport: Box<dyn Transport>,
         mut from_gui: mpsc::UnboundedReceiver<BrowserMessage>,
         to_gui: mpsc::UnboundedSender<BrowserMessage>,
     ) {
+        let own_address = transport.address();
+        let mut retries: Vec<QueuedSend> = Vec::new();
+
         loop {
+            let next_retry = retries.iter().map(|queued| queued.due).min();
             tokio::select! {
-                messages = client.wait_for_messages() => {
+                messages = transport.next_messages() => {
                     if let Some(messages) = messages {
-                        for received in messages {
-                            let text_message = String::from_utf8_lossy(&received.message).into_owned();
-                            let _sender_info = if let Some(sender_tag) = &received.sender_tag {
-                                format!("{:?}", sender_tag)
-                            } else {
-                                "unknown".to_string()
+                        for message in messages {
+                            if message == CONNECTION_CHECK.as_bytes() {
+                                let _ = to_gui.send(BrowserMessage::ConnectionChecked { result: Ok(()) });
+                                continue;
+                            }
+                            if Self::is_own_request(&message, &own_address) {
+                                eprintln!("Ignoring echo of our own request");
+                                continue;
+                            }
+                            let (request_id, reply) = protocol::split_reply_id(&message);
+                            // Decompressed here so the GUI thread never waits on it
+                            let reply = match compression::decompress(reply) {
+                                Ok(reply) => reply,
+                                Err(error) => {
+                                    eprintln!("Reply to request {:?}: {}", request_id, error);
+                                    if let Some(request_id) = request_id {
+                                        Self::deliver(&to_gui, BrowserMessage::RequestFailed { request_id, error });
+                                    }
+                                    continue;
+                                }
                             };
-                            let _ = to_gui.send(BrowserMessage::ReceivedMessage {
-                                content: text_message,
+                            let (content_type, data) = match Data::parse(&reply) {
+                                Some(Data { content_type, bytes }) => (content_type, bytes),
+                                None => (protocol::MARKDOWN_TYPE.to_string(), reply),
+                            };
+                            Self::deliver(&to_gui, BrowserMessage::ReceivedMessage {
+                                request_id,
+                                content_type,
+                                data,
+                                prepared: None,
                             });
                         }
                     }
                 }
                 Some(gui_message) = from_gui.recv() => {
-                    if let BrowserMessage::SendRequest { recipient, message } = gui_message {
-                        match recipient.parse::<nym_sdk::mixnet::Recipient>() {
-                            Ok(recipient_addr) => {
-                                if let Err(e) = client.send_plain_message(recipient_addr, message).await {
-                                    let _ = to_gui.send(BrowserMessage::ReceivedMessage {
-                                        content: format!("ERROR: {}", e),
-                                    });
-                                }
-                            }
-                            Err(e) => {
-                                let _ = to_gui.send(BrowserMessage::ReceivedMessage {
-                                    content: format!("ERROR: Invalid address - {}", e),
-                                });
+                    match gui_message {
+                        BrowserMessage::SendRequest { request_id, recipient, message, reply_surbs } => {
+                            let queued = QueuedSend {
+                                request_id,
+                                recipient,
+                                message,
+                                reply_surbs,
+                                attempt: 0,
+                                due: tokio::time::Instant::now(),
+                            };
+                            Self::attempt_send(transport.as_mut(), queued, &mut retries, &to_gui).await;
+                        }
+                        BrowserMessage::CheckConnection => {
+                            let sent = transport.send(own_address.clone(), CONNECTION_CHECK.to_string(), 0).await;
+                            if let Err(failure) = sent {
+                                let _ = to_gui.send(BrowserMessage::ConnectionChecked { result: Err(failure.to_string()) });
                             }
                         }
+                        BrowserMessage::Disconnect => {
+                            // Frees the gateway connection; a new client is built on reconnect
+                            transport.disconnect().await;
+                            return;
+                        }
+                        _ => {}
+                    }
+                }
+                _ = tokio::time::sleep_until(next_retry.unwrap_or_else(tokio::time::Instant::now)), if next_retry.is_some() => {
+                    let now = tokio::time::Instant::now();
+                    let (due, waiting) = std::mem::take(&mut retries)
+                        .into_iter()
+                        .partition(|queued| queued.due <= now);
+                    retries = waiting;
+                    for queued in due {
+                        Self::attempt_send(transport.as_mut(), queued, &mut retries, &to_gui).await;
                     }
                 }
             }
         }
     }
 
+    // Transient failures go back in the queue with jittered backoff, so
+    // other requests are sent in the meantime; anything else is reported
+    // right away since retrying cannot change the outcome
+    async fn attempt_send(
+        transport: &mut dyn Transport,
+        mut queued: QueuedSend,
+        retries: &mut Vec<QueuedSend>,
+        to_gui: &mpsc::UnboundedSender<BrowserMessage>,
+    ) {
+        let failure = match transport.send(queued.recipient.clone(), queued.message.clone(), queued.reply_surbs).await {
+            Ok(()) => return,
+            Err(failure) => failure,
+        };
+        if failure.is_transient() && queued.attempt < SEND_RETRIES {
+            queued.attempt += 1;
+            let jitter = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.subsec_nanos() as u64 % 100);
+            let delay = SEND_RETRY_BASE * 2u32.pow(queued.attempt - 1) + Duration::from_millis(jitter);
+            eprintln!("Request {}: {} (retry {}/{} in {:?})", queued.request_id, failure, queued.attempt, SEND_RETRIES, delay);
+            queued.due = tokio::time::Instant::now() + delay;
+            retries.push(queued);
+            return;
+        }
+
+        let request_id = queued.request_id;
+        let error = failure.to_string();
+        let failure = match failure {
+            SendFailure::BandwidthExhausted(_) => BrowserMessage::BandwidthExhausted { request_id, error },
+            _ => BrowserMessage::RequestFailed { request_id, error },
+        };
+        Self::deliver(to_gui, failure);
+    }
+
+    // Hands the outcome of a request to everyone waiting for it: the GUI,
+    // navigate() callers, or both when identical requests were coalesced.
+    // Anything else goes to the GUI as it is.
+    fn deliver(to_gui: &mpsc::UnboundedSender<BrowserMessage>, message: BrowserMessage) {
+        let request_id = match &message {
+            BrowserMessage::ReceivedMessage { request_id: Some(request_id), .. }
+            | BrowserMessage::RequestFailed { request_id, .. }
+            | BrowserMessage::BandwidthExhausted { request_id, .. } => Some(*request_id),
+            _ => None,
+        };
+        let completed = request_id.and_then(|request_id| {
+            let mut in_flight = IN_FLIGHT.lock().unwrap();
+            let server = in_flight.key(request_id)?.server().to_string();
+            Some((server, in_flight.complete(request_id)?))
+        });
+        let Some((server, waiters)) = completed else {
+            let _ = to_gui.send(message);
+            return;
+        };
+        for waiter in waiters {
+            match waiter {
+                Waiter::Gui { trim_trailing_whitespace } => {
+                    let _ = to_gui.send(Self::prepare_for_gui(message.clone(), &server, trim_trailing_whitespace));
+                }
+                Waiter::Awaited(waiter) => {
+                    let _ = waiter.send(message.clone());
+                }
+            }
+        }
+    }
+
+    // Parses a page reply and prepares it for display here, so the UI
+    // thread only has to show it
+    fn prepare_for_gui(message: BrowserMessage, server: &str, trim_trailing_whitespace: bool) -> BrowserMessage {
+        match message {
+            BrowserMessage::ReceivedMessage { request_id, content_type, data, prepared: None }
+                if protocol::is_text_type(&content_type) => {
+                let prepared = Some(Box::new(PreparedReply::new(&data, trim_trailing_whitespace, server)));
+                BrowserMessage::ReceivedMessage { request_id, content_type, data, prepared }
+            }
+            message => message,
+        }
+    }
+
+    // A request we sent that looped back to us, e.g. when the server
+    // address is our own client address
+    fn is_own_request(message: &[u8], own_address: &str) -> bool {
+        std::str::from_utf8(message).is_ok_and(|message| protocol::reply_address(message) == Some(own_address))
+    }
+
     fn get_gui_sender() -> Option<mpsc::UnboundedSender<BrowserMessage>> {
         GUI_TO_MIXNET_SENDER
             .get()
             .and_then(|arc| arc.lock().unwrap().clone())
     }
 
-    pub fn send_request(&self, request_path: &str) -> Result<(), String> {
+    // Navigation needs both a finished connection and an installed sender
+    fn is_mixnet_ready(&self) -> bool {
+        !self.loading && Self::get_gui_sender().is_some()
+    }
+
+    // Empty in anonymous mode, where requests carry no FROM
+    fn reply_address(&self) -> &str {
+        if self.settings.anonymous_requests {
+            ""
+        } else {
+            self.client_address.trim()
+        }
+    }
+
+    fn reply_surbs(&self) -> u32 {
+        if self.settings.anonymous_requests {
+            ANONYMOUS_REPLY_SURBS
+        } else {
+            0
+        }
+    }
+
+    // A request format without the reply address can only be answered over
+    // reply SURBs, anonymous or not
+    fn reply_surbs_for(&self, recipient: &str) -> u32 {
+        let carries_address = self.settings.request_formats
+            .get(recipient)
+            .is_none_or(RequestFormat::carries_address);
+        if carries_address {
+            self.reply_surbs()
+        } else {
+            ANONYMOUS_REPLY_SURBS
+        }
+    }
+
+    pub fn send_request(&mut self, request_path: &str) -> Result<(), String> {
+        self.send_request_with(request_path, true)
+    }
+
+    // With `coalesce`, a request identical to one still in flight is not
+    // sent again, even if the page it was for has been left since; the
+    // page view waits for that reply instead. Hard reloads pass false.
+    fn send_request_with(&mut self, request_path: &str, coalesce: bool) -> Result<(), String> {
         let recipient = self.server_address.trim();
         if recipient.is_empty() {
             return Err("No server address specified".to_string());
         }
+        if self.offline {
+            self.offline_blocked_url = Some(NymUrl {
+                server: recipient.to_string(),
+                page: request_path.trim_start_matches('/').to_string(),
+            }.to_string());
+        }
+        let sender = self.mixnet_sender()?;
         
         let my_address = self.client_address.trim();
         if my_address.is_empty() {
             return Err("Not connected yet - waiting for client address".to_string());
         }
+        if recipient == my_address {
+            return Err("That's your own client address - enter a server address instead".to_string());
+        }
+        self.settings.server_filter.check(recipient)?;
+        if self.bandwidth_exhausted_since.is_some() {
+            return Err(BANDWIDTH_EXHAUSTED.to_string());
+        }
 
-        let request = format!("GET {} FROM {}", request_path, my_address);
-
-        if let Some(sender) = Self::get_gui_sender() {
-            sender.send(BrowserMessage::SendRequest {
-                recipient: recipient.to_string(),
-                message: request,
-            }).map_err(|e| format!("Send error: {}", e))?;
-        } else {
-            return Err("Not connected to Mixnet".to_string());
+        // Only reload opts back in to scroll restoration
+        self.restore_scroll = false;
+        let key = RequestKey::get(recipient, request_path);
+        if coalesce {
+            let mut in_flight = IN_FLIGHT.lock().unwrap();
+            if let Some(request_id) = in_flight.find(&key) {
+                if !in_flight.waiters(request_id).iter().any(|waiter| matches!(waiter, Waiter::Gui { .. })) {
+                    in_flight.attach(request_id, self.gui_waiter());
+                }
+                drop(in_flight);
+                self.pending_request_id = Some(request_id);
+                self.metrics.record_coalesced();
+                self.address_edited = false;
+                return Ok(());
+            }
         }
-        Ok(())
-    }
 
-    fn parse_and_set_url(&mut self, url: &str) {
-        if let Some((server, page)) = Self::parse_nym_url(url) {
-            self.server_address = server.trim().to_string();
-            self.address_bar = if page.is_empty() { String::new() } else { page };
-        } else {
-            self.address_bar = url.trim().to_string();
+        let request_id = self.next_request_id;
+        let request = self.render_request(recipient, request_path, request_id);
+        let sent = SentRequest {
+            server: self.server_ids.intern(recipient),
+            message: request.clone(),
+        };
+
+        // Registered first, as the reply may be back before send() returns
+        IN_FLIGHT.lock().unwrap().insert(request_id, key, self.gui_waiter());
+        let result = sender.send(BrowserMessage::SendRequest {
+            request_id,
+            recipient: recipient.to_string(),
+            message: request,
+            reply_surbs: self.reply_surbs_for(recipient),
+        });
+        if let Err(e) = result {
+            IN_FLIGHT.lock().unwrap().complete(request_id);
+            return Err(format!("Send error: {}", e));
         }
+        self.next_request_id += 1;
+        self.pending_request_id = Some(request_id);
+        self.metrics.record_request(&sent.server, sent.message.len());
+        self.last_request = Some(sent);
+        self.address_edited = false;
+        let recipient = recipient.to_string();
+        self.probe_header_support(&recipient, &sender);
+        Ok(())
     }
 
-    fn parse_nym_url(url: &str) -> Option<(String, String)> {
-        if !url.starts_with("nym://") {
-            return None;
+    // Asks a server once per session whether it reads request headers. The
+    // answer comes back untagged and is recognised by its PONG.
+    fn probe_header_support(&mut self, server: &str, sender: &mpsc::UnboundedSender<BrowserMessage>) {
+        let probing = self.header_probe
+            .as_ref()
+            .is_some_and(|(_, sent)| sent.elapsed() < PAGE_LOAD_TIMEOUT);
+        if probing
+            || self.settings.header_servers.contains(server)
+            || self.settings.request_formats.contains_key(server)
+            || !self.probed_servers.insert(server.to_string()) {
+            return;
         }
-        let without_protocol = &url[6..];
-        if let Some(slash_pos) = without_protocol.find('/') {
-            let server = without_protocol[..slash_pos].to_string();
-            let page = without_protocol.get(slash_pos + 1..).unwrap_or("").to_string();
-            Some((server, page))
-        } else {
-            Some((without_protocol.to_string(), "".to_string()))
+        let probe = Request::new("PING", "/", self.reply_address()).to_string();
+        let sent = sender.send(BrowserMessage::SendRequest {
+            request_id: self.next_request_id,
+            recipient: server.to_string(),
+            message: probe,
+            reply_surbs: self.reply_surbs(),
+        });
+        if sent.is_ok() {
+            self.next_request_id += 1;
+            self.header_probe = Some((server.to_string(), Instant::now()));
         }
     }
 
-    fn handle_navigation(&mut self) {
-        let address = self.address_bar.clone();
-        self.parse_and_set_url(&address);
-        self.page_loading = true;
-        self.page_load_start_time = Some(Instant::now());
-
-        let path = if self.address_bar.is_empty() {
-            "/".to_string()
-        } else if self.address_bar.starts_with('/') {
-            self.address_bar.clone()
-        } else {
-            format!("/{}", self.address_bar)
+    fn handle_header_probe_reply(&mut self, reply: &[u8]) {
+        let Some((server, _)) = self.header_probe.take() else {
+            return;
         };
-
-        match self.send_request(&path) {
-            Ok(()) => {
-                self.add_to_history();
-            },
-            Err(e) => {
-                self.error = Some(e);
-                self.page_loading = false;
-                self.page_load_start_time = None;
+        if protocol::pong_reads_headers(reply) == Some(true) {
+            eprintln!("{} reads request headers", server);
+            self.settings.header_servers.insert(server);
+            if let Err(e) = self.settings.save(self.storage.as_ref()) {
+                eprintln!("{}", e);
             }
         }
     }
 
-    pub fn show(&mut self, ui: &mut Ui) {
-        ui.style_mut().url_in_tooltip = true;
+    // Reports only go to collectors that read headers, which echo the
+    // report's ID. An untagged answer could not be told apart from a page's.
+    fn is_report_reply(&self, request_id: Option<u64>) -> bool {
+        self.report_request_id.is_some() && self.report_request_id == request_id
+    }
 
-        if !self.connection_attempted {
-            self.init();
+    // The GET for `path` as the recipient expects it written
+    fn render_request(&self, recipient: &str, path: &str, request_id: u64) -> String {
+        let mut request = Request::get(path, self.reply_address());
+        // Older servers take everything after FROM as the reply address
+        if self.settings.header_servers.contains(recipient) {
+            let client_id = self.settings.client_id.trim();
+            // It would tie anonymous requests together
+            if self.settings.send_client_id && !self.settings.anonymous_requests && !client_id.is_empty() {
+                request = request.with_header("Client", client_id);
+            }
+            request = request.with_header(protocol::REQUEST_ID_HEADER, &request_id.to_string());
+            if self.settings.accept_compression {
+                request = request.with_header(protocol::ACCEPT_ENCODING_HEADER, protocol::ZSTD);
+            }
         }
+        self.settings.request_formats
+            .get(recipient)
+            .unwrap_or(&RequestFormat::NymView)
+            .render(&request, request_id)
+    }
 
-        // Check for page load timeout (30 seconds)
-        if self.page_loading {
-            if let Some(start_time) = self.page_load_start_time {
-                if start_time.elapsed() > Duration::from_secs(30) {
-                    self.error = Some("Page load timeout - server not responding".to_string());
-                    self.page_loading = false;
-                    self.page_load_start_time = None;
-                }
+    // Hands queued page images to the mixnet once connected, and gives up
+    // on those that took as long as a page may
+    fn fetch_page_images(&mut self) {
+        let timed_out: Vec<u64> = self.image_requests
+            .iter()
+            .filter(|(_, (_, requested))| requested.elapsed() > PAGE_LOAD_TIMEOUT)
+            .map(|(request_id, _)| *request_id)
+            .collect();
+        for request_id in timed_out {
+            if let Some((url, _)) = self.image_requests.remove(&request_id) {
+                self.image_loader.finish(&url, Err("The image did not arrive in time".to_string()));
             }
         }
 
-        // Process pending navigation first
-        if let Some(url) = self.pending_navigation.take() {
-            self.handle_link_click(&url);
+        if self.client_address.trim().is_empty() && !self.offline {
+            return;
         }
-
-        let mut messages_to_process = Vec::new();
-        if let Some(receiver) = &mut self.message_receiver {
-            while let Ok(message) = receiver.try_recv() {
-                messages_to_process.push(message);
+        // Whether the server echoes request IDs is not known yet
+        if self.header_probe.as_ref().is_some_and(|(server, _)| server == self.server_address.trim()) {
+            return;
+        }
+        for url in self.image_loader.take_queued() {
+            if let Err(e) = self.request_image(&url) {
+                self.image_loader.finish(&url, Err(e));
             }
         }
+    }
 
-        for message in messages_to_process {
-            match message {
-                BrowserMessage::ReceivedMessage { content} => {
-                    self.handle_server_message(content);
-                }
-                BrowserMessage::ConnectionStatus { status, loading, client_address } => {
-                    self.connection_status = status;
-                    self.loading = loading;
-                    if !client_address.is_empty() {
-                        self.client_address = client_address;
-                    }
-                }
-                _ => {}
-            }
+    // Image requests never become the pending request, so the page and
+    // its images do not wait for each other
+    fn request_image(&mut self, url: &str) -> Result<(), String> {
+        let sender = self.mixnet_sender()?;
+        let NymUrl { server, page } = NymUrl::parse(url).ok_or("Not a nym:// address")?;
+        self.settings.server_filter.check(&server)?;
+        if self.bandwidth_exhausted_since.is_some() {
+            return Err(BANDWIDTH_EXHAUSTED.to_string());
+        }
+        if server != self.server_address.trim()
+            && !self.settings.cross_server_images
+            && !self.image_servers_allowed.contains(&server) {
+            self.blocked_images.push(url.to_string());
+            return Err("Image from another server, not loaded".to_string());
+        }
+        // An untagged reply would be taken for the page
+        if !self.settings.header_servers.contains(&server) {
+            return Err("This server does not tag its replies, so its images are not loaded".to_string());
         }
 
-        // Status line
-        ui.horizontal(|ui| {
+        let request_id = self.next_request_id;
+        let message = self.render_request(&server, &protocol::normalize_path(&page), request_id);
+        let reply_surbs = self.reply_surbs_for(&server);
+        sender.send(BrowserMessage::SendRequest {
+            request_id,
+            recipient: server,
+            message,
+            reply_surbs,
+        }).map_err(|e| format!("Send error: {}", e))?;
+        self.next_request_id += 1;
+        self.image_requests.insert(request_id, (url.to_string(), Instant::now()));
+        Ok(())
+    }
+
+    fn handle_image_reply(&mut self, ctx: &egui::Context, request_id: u64, content_type: &str, data: Vec<u8>) {
+        let Some((url, _)) = self.image_requests.remove(&request_id) else {
+            return;
+        };
+        self.metrics.record_reply(data.len(), None);
+        let result = if content_type.starts_with("image/") {
+            Ok(data)
+        } else {
+            match Response::parse(&String::from_utf8_lossy(&data)) {
+                Some(Response::Error(_, message)) => Err(message),
+                _ => Err(format!("Not an image ({})", content_type)),
+            }
+        };
+        let loader = self.image_loader.clone();
+        let ctx = ctx.clone();
+        RUNTIME.spawn_blocking(move || {
+            loader.finish(&url, result);
+            ctx.request_repaint();
+        });
+    }
+
+    fn parse_and_set_url(&mut self, url: &str) {
+        if let Some(NymUrl { server, page }) = NymUrl::parse(url) {
+            self.server_address = server.trim().to_string();
+            self.address_bar = if page.is_empty() { String::new() } else { page };
+        } else {
+            self.address_bar = url.trim().to_string();
+        }
+    }
+
+    // Fetches `url` and resolves with the page once it has arrived,
+    // leaving what the browser shows alone; transclusions are loaded this
+    // way. The request is sent right away, so the returned future borrows
+    // nothing and can be spawned. Only servers known to echo request IDs
+    // can be awaited, as that is how their reply is told apart. Must be
+    // awaited on a Tokio runtime.
+    pub(crate) fn navigate(&self, url: &NymUrl) -> impl Future<Output = Result<String, FetchError>> + 'static {
+        let sent = self.send_awaited(url);
+        let trim_trailing_whitespace = self.settings.trim_trailing_whitespace;
+        async move {
+            let (request_id, reply_rx) = sent?;
+            let reply = tokio::time::timeout(PAGE_LOAD_TIMEOUT, reply_rx).await;
+            // Dropped on timeout, which leaves the request to other waiters
+            IN_FLIGHT.lock().unwrap().detach(request_id, |waiter| {
+                matches!(waiter, Waiter::Awaited(waiter) if waiter.is_closed())
+            });
+            match reply {
+                Ok(Ok(BrowserMessage::ReceivedMessage { content_type, data, .. })) => {
+                    if !protocol::is_text_type(&content_type) {
+                        return Err(FetchError::NotAPage(content_type));
+                    }
+                    let content = String::from_utf8_lossy(&data);
+                    let content = markdown::normalize_response(&content, trim_trailing_whitespace);
+                    match Response::parse(&content) {
+                        Some(Response::Ok(body)) => Ok(body),
+                        Some(Response::Error(status, message)) => Err(FetchError::Status(status, message)),
+                        None => Ok(content),
+                    }
+                }
+                Ok(Ok(BrowserMessage::RequestFailed { error, .. } | BrowserMessage::BandwidthExhausted { error, .. })) => {
+                    Err(FetchError::Failed(error))
+                }
+                Ok(Ok(_)) | Ok(Err(_)) => Err(FetchError::Failed("The connection closed".to_string())),
+                Err(_) => Err(FetchError::TimedOut),
+            }
+        }
+    }
+
+    fn gui_waiter(&self) -> Waiter {
+        Waiter::Gui { trim_trailing_whitespace: self.settings.trim_trailing_whitespace }
+    }
+
+    // Sends the request for navigate() and registers the reply waiter. If
+    // the same page is already on its way, e.g. for the page view, the
+    // waiter is attached to that request instead.
+    fn send_awaited(&self, url: &NymUrl) -> Result<(u64, oneshot::Receiver<BrowserMessage>), FetchError> {
+        let server = url.server.trim();
+        if !self.settings.header_servers.contains(server) || self.settings.request_formats.contains_key(server) {
+            return Err(FetchError::NoRequestIds(server.to_string()));
+        }
+        let sender = self.mixnet_sender().map_err(FetchError::NotSent)?;
+        if self.client_address.trim().is_empty() {
+            return Err(FetchError::NotSent("Not connected yet - waiting for client address".to_string()));
+        }
+        self.settings.server_filter.check(server).map_err(FetchError::NotSent)?;
+        if self.bandwidth_exhausted_since.is_some() {
+            return Err(FetchError::NotSent(BANDWIDTH_EXHAUSTED.to_string()));
+        }
+
+        let key = RequestKey::get(server, &url.page);
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let mut in_flight = IN_FLIGHT.lock().unwrap();
+        if let Some(request_id) = in_flight.find(&key) {
+            in_flight.attach(request_id, Waiter::Awaited(reply_tx));
+            return Ok((request_id, reply_rx));
+        }
+        let request_id = NEXT_AWAITED_ID.fetch_sub(1, Ordering::Relaxed);
+        in_flight.insert(request_id, key, Waiter::Awaited(reply_tx));
+        drop(in_flight);
+        let sent = sender.send(BrowserMessage::SendRequest {
+            request_id,
+            recipient: server.to_string(),
+            message: self.render_request(server, &protocol::normalize_path(&url.page), request_id),
+            reply_surbs: self.reply_surbs_for(server),
+        });
+        if let Err(e) = sent {
+            IN_FLIGHT.lock().unwrap().complete(request_id);
+            return Err(FetchError::NotSent(format!("Send error: {}", e)));
+        }
+        Ok((request_id, reply_rx))
+    }
+
+    // Request path for a page on the current server, honouring its landing path
+    fn request_path(&self, page: &str) -> String {
+        let page = page.trim();
+        if page.is_empty() || page == "/" {
+            if let Some(landing) = self.settings.landing_paths.get(self.server_address.trim()) {
+                return protocol::normalize_path(landing);
+            }
+        }
+        protocol::normalize_path(page)
+    }
+
+    fn select_all_text(ctx: &egui::Context, id: egui::Id, text: &str) {
+        let mut state = TextEdit::load_state(ctx, id).unwrap_or_default();
+        let range = egui::text::CCursorRange::two(
+            egui::text::CCursor::new(0),
+            egui::text::CCursor::new(text.chars().count()),
+        );
+        state.cursor.set_char_range(Some(range));
+        state.store(ctx, id);
+    }
+
+
+    // One navigation at a time: a new one supersedes the load still in
+    // flight, whose late failure is then ignored, and repeating the same
+    // navigation within NAVIGATION_DEBOUNCE (e.g. a double Enter) does
+    // nothing. Returns false when the navigation should not start.
+    fn begin_navigation(&mut self, path: &str) -> bool {
+        let key = format!("{} {}", self.server_address.trim(), path);
+        if let Some((last_key, started)) = &self.last_navigation {
+            if *last_key == key && started.elapsed() < NAVIGATION_DEBOUNCE {
+                return false;
+            }
+        }
+        self.last_navigation = Some((key, Instant::now()));
+
+        self.abandon_pending_request();
+        self.blocked_images.clear();
+        self.redirect_to_confirm = None;
+        self.stripped_link = None;
+        self.error = None;
+        self.page_loading = true;
+        self.page_load_start_time = Some(Instant::now());
+        true
+    }
+
+    // The page request in flight and the page's images are no longer
+    // wanted, so their late replies and failures are ignored
+    fn abandon_pending_request(&mut self) {
+        if let Some(request_id) = self.pending_request_id.take() {
+            eprintln!("Request #{} superseded", request_id);
+        }
+        self.image_loader.cancel_pending();
+        self.image_requests.clear();
+    }
+
+    // Typos like "nym:/server" or a bare recipient are rewritten when the
+    // address is submitted, never while it is typed. The correction is
+    // only shown; submitting again goes there, Escape takes it back.
+    fn handle_navigation(&mut self) {
+        self.address_before_correction = None;
+        self.external_url = None;
+        match address::infer(&self.address_bar) {
+            Inference::External(url) => {
+                self.external_url = Some(url);
+                return;
+            }
+            Inference::Corrected(url) => {
+                self.address_before_correction = Some(std::mem::replace(&mut self.address_bar, url));
+                return;
+            }
+            Inference::Unchanged => {}
+        }
+
+        if self.address_bar.trim().eq_ignore_ascii_case(HEALTH_URL) {
+            self.show_health = true;
+            self.start_health_check();
+            return;
+        }
+        if self.address_bar.trim().eq_ignore_ascii_case(REPORTS_URL) {
+            self.show_sent_reports = true;
+            return;
+        }
+
+        let address = self.address_bar.clone();
+        self.parse_and_set_url(&address);
+
+        let path = self.request_path(&self.address_bar);
+        self.address_bar = path.trim_start_matches('/').to_string();
+        if !self.begin_navigation(&path) {
+            return;
+        }
+
+        match self.send_request(&path) {
+            Ok(()) => {
+                self.add_to_history();
+            },
+            Err(e) => {
+                self.error = Some(e);
+                self.page_loading = false;
+                self.page_load_start_time = None;
+            }
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui) {
+        ui.style_mut().url_in_tooltip = true;
+
+        if !self.connection_attempted {
+            self.apply_motion_preference(ui.ctx());
+            self.apply_content_font(ui.ctx());
+            ui.ctx().add_image_loader(self.image_loader.clone());
+            self.load_theme();
+            self.init();
+            self.start_health_check();
+        }
+
+        // The monotonic clock stops while the system sleeps and the wall
+        // clock does not, so the wall clock running ahead of it means the
+        // process was suspended. A frame that was merely late moves both.
+        // The request did not get its time in the meantime.
+        let now = Instant::now();
+        let wall_now = SystemTime::now();
+        if self.page_loading {
+            let suspended = self.last_frame.is_some_and(|(last_frame, last_wall)| {
+                let wall_gap = wall_now.duration_since(last_wall).unwrap_or_default();
+                wall_gap.saturating_sub(now - last_frame) > SUSPEND_GAP
+            });
+            if suspended {
+                eprintln!("System resumed from sleep \u{2014} request timers restarted");
+                self.page_load_start_time = Some(now);
+                self.check_connection();
+            }
+            ui.ctx().request_repaint_after(HEARTBEAT);
+        }
+        if self.health_checking {
+            ui.ctx().request_repaint_after(HEARTBEAT);
+        }
+        self.last_frame = Some((now, wall_now));
+
+        if let Some((ResumeCheck::Checking, started)) = self.resume_check {
+            if started.elapsed() > PAGE_LOAD_TIMEOUT {
+                self.connection_lost("The check message did not come back".to_string());
+            } else {
+                ui.ctx().request_repaint_after(HEARTBEAT);
+            }
+        }
+
+        // Check for page load timeout (30 seconds)
+        if self.page_loading {
+            if let Some(start_time) = self.page_load_start_time {
+                if start_time.elapsed() > PAGE_LOAD_TIMEOUT {
+                    self.metrics.record_timeout();
+                    self.fail_navigation("Page load timeout - server not responding".to_string());
+                }
+            }
+        }
+
+        self.poll_theme_file(ui.ctx());
+        self.flush_history(ui.ctx());
+        self.flush_gateway_stats(false);
+        self.handle_link_hint_keys(ui.ctx());
+        self.handle_server_switcher(ui.ctx());
+
+        // Process pending navigation first
+        if let Some(url) = self.pending_navigation.take() {
+            self.handle_link_click(&url);
+        }
+        self.fetch_page_images();
+
+        // A burst of replies is applied over several frames instead of
+        // stalling one; the rest stays queued in the channel
+        let messages_started = self.frame_timings.start();
+        let mut messages_to_process = Vec::new();
+        if let Some(receiver) = &mut self.message_receiver {
+            while messages_to_process.len() < MAX_MESSAGES_PER_FRAME {
+                match receiver.try_recv() {
+                    Ok(message) => messages_to_process.push(message),
+                    Err(_) => break,
+                }
+            }
+            if messages_to_process.len() == MAX_MESSAGES_PER_FRAME {
+                ui.ctx().request_repaint();
+            }
+        }
+
+        for message in messages_to_process {
+            match message {
+                BrowserMessage::ReceivedMessage { request_id: Some(request_id), content_type, data, .. }
+                    if self.image_requests.contains_key(&request_id) => {
+                    self.handle_image_reply(ui.ctx(), request_id, &content_type, data);
+                }
+                // PONG never answers a page request
+                BrowserMessage::ReceivedMessage { request_id: None, data, .. }
+                    if self.header_probe.is_some() && protocol::pong_reads_headers(&data).is_some() => {
+                    self.handle_header_probe_reply(&data);
+                }
+                BrowserMessage::ReceivedMessage { request_id: Some(request_id), data, .. }
+                    if self.report_upload_id == Some(request_id) => {
+                    self.report_upload_id = None;
+                    let content = String::from_utf8_lossy(&data);
+                    let answer = content.lines().next().unwrap_or("").trim().to_string();
+                    self.report_upload_status = Some(if answer == "OK" {
+                        "The collector stored the image.".to_string()
+                    } else {
+                        format!("The collector did not take the image: {}", answer)
+                    });
+                }
+                BrowserMessage::ReceivedMessage { request_id, data, .. } if self.is_report_reply(request_id) => {
+                    self.report_request_id = None;
+                    let content = String::from_utf8_lossy(&data);
+                    let answer = content.lines().next().unwrap_or("").trim().to_string();
+                    self.report_status = Some(if answer == "OK" {
+                        "The collector confirmed the report.".to_string()
+                    } else {
+                        format!("The collector answered: {}", answer)
+                    });
+                }
+                BrowserMessage::ReceivedMessage { request_id, content_type, data, prepared } => {
+                    self.handle_server_message(request_id, &content_type, data, prepared);
+                }
+                BrowserMessage::RequestFailed { request_id, error } => {
+                    self.handle_request_failed(request_id, error);
+                }
+                BrowserMessage::BandwidthExhausted { request_id, error } => {
+                    eprintln!("Gateway bandwidth exhausted: {}", error);
+                    self.bandwidth_exhausted_since.get_or_insert_with(Instant::now);
+                    self.handle_request_failed(request_id, BANDWIDTH_EXHAUSTED.to_string());
+                }
+                BrowserMessage::DownloadFinished { id, result } => {
+                    if let Some(download) = self.downloads.iter_mut().find(|download| download.id == id) {
+                        download.state = match result {
+                            Ok(path) => DownloadState::Done(path),
+                            Err(e) => DownloadState::Failed(e),
+                        };
+                    }
+                }
+                BrowserMessage::ImagePasted { result } => {
+                    self.pasting_image = false;
+                    match (result, &mut self.report_draft) {
+                        (Ok(image), Some(draft)) => {
+                            draft.image = Some(image);
+                            draft.preview = None;
+                            draft.upload_preview = None;
+                        }
+                        // The report window was closed meanwhile
+                        (Ok(_), None) => {}
+                        (Err(e), _) => self.report_status = Some(e),
+                    }
+                }
+                BrowserMessage::DiagnosticsExported { result } => {
+                    self.exporting_diagnostics = false;
+                    match result {
+                        Ok(path) => self.diagnostics_path = Some(path),
+                        Err(e) => self.error = Some(e),
+                    }
+                }
+                BrowserMessage::ConnectionChecked { result } if matches!(self.resume_check, Some((ResumeCheck::Checking, _))) => {
+                    match result {
+                        Ok(()) => self.resume_check = Some((ResumeCheck::Healthy, Instant::now())),
+                        Err(e) => self.connection_lost(e),
+                    }
+                }
+                BrowserMessage::HealthChecked { report } => {
+                    self.health = Some(report);
+                    self.health_checking = false;
+                }
+                BrowserMessage::Transcluded { url, result } => {
+                    // Dropped if the page that asked for it is gone
+                    if let Some(transclusion) = self.transclusions.get_mut(&url) {
+                        *transclusion = match result {
+                            Ok(content) => Transclusion::loaded(&content, self.server_address.trim()),
+                            Err(e) => Transclusion::Failed(e),
+                        };
+                    }
+                }
+                BrowserMessage::ConnectionStatus { status, loading, client_address } => {
+                    self.connection_status = status;
+                    self.loading = loading;
+                    if !client_address.is_empty() {
+                        // Each connection may use a different gateway
+                        self.gateway_stats.start_session(&client_address);
+                        self.metrics.reset_recent_latency();
+                        self.slow_gateway_warning = false;
+                        self.slow_gateway_dismissed = false;
+                        self.client_address = client_address;
+                        if std::mem::take(&mut self.retry_when_online) {
+                            self.pending_navigation = self.offline_blocked_url.take();
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.frame_timings.record(Phase::Messages, messages_started);
+
+        // Status line
+        ui.horizontal(|ui| {
             ui.label("Status:");
-            ui.colored_label(Color32::BLUE, &self.connection_status);
+            ui.colored_label(Color32::BLUE, &self.connection_status)
+                .on_hover_text(self.metrics().summary());
             if self.loading {
-                ui.spinner();
+                indicators::busy(ui, self.settings.reduce_motion);
                 ui.colored_label(Color32::BLUE, "Connecting...");
             }
+            match &self.session_mode {
+                SessionMode::Replay(file) => {
+                    ui.label(egui::RichText::new(" REPLAY ")
+                        .strong()
+                        .color(Color32::WHITE)
+                        .background_color(Color32::from_rgb(200, 100, 0)))
+                        .on_hover_text(format!("Pages come from {}, not the mixnet", file.display()));
+                }
+                SessionMode::Record(file) => {
+                    ui.label(egui::RichText::new(" REC ")
+                        .strong()
+                        .color(Color32::WHITE)
+                        .background_color(Color32::DARK_RED))
+                        .on_hover_text(format!("Every reply is written to {}", file.display()));
+                }
+                SessionMode::Live => {}
+            }
+            if self.settings.anonymous_requests {
+                ui.label(egui::RichText::new(" ANONYMOUS ")
+                    .strong()
+                    .color(Color32::WHITE)
+                    .background_color(Color32::DARK_GREEN))
+                    .on_hover_text("Requests leave out your address; servers reply over SURBs");
+            } else {
+                ui.weak("Address shared")
+                    .on_hover_text("Servers learn your client address from each request (FROM). Anonymous requests can be turned on in Settings.");
+            }
+            self.show_resume_check(ui);
+            if self.health.as_ref().is_some_and(HealthReport::is_degraded)
+                && ui.small_button("⚠ Self-check")
+                    .on_hover_text("Some local data or system settings need attention")
+                    .clicked() {
+                self.show_health = true;
+            }
+            if matches!(self.page_state, PageState::Loaded | PageState::ServerError(_)) && !self.page_loading {
+                self.show_source_badge(ui);
+                // Short pages are read at a glance
+                if self.page_words >= LONG_PAGE_WORDS {
+                    ui.weak(markdown::reading_time(self.page_words));
+                }
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if let Some(since) = self.bandwidth_exhausted_since {
+                    if ui.button("Resume sending")
+                        .on_hover_text("Try again after topping up or waiting for the allowance to renew")
+                        .clicked() {
+                        self.bandwidth_exhausted_since = None;
+                        self.error = None;
+                    }
+                    // The SDK does not report when the allowance renews
+                    ui.colored_label(
+                        Color32::DARK_RED,
+                        format!("Bandwidth exhausted {} s ago", since.elapsed().as_secs()),
+                    );
+                    ui.ctx().request_repaint_after(Duration::from_secs(1));
+                }
+                if self.offline {
+                    ui.label(egui::RichText::new(" OFFLINE MODE ")
+                        .strong()
+                        .color(Color32::WHITE)
+                        .background_color(Color32::from_rgb(160, 60, 0)))
+                        .on_hover_text("Nothing is sent over the Mixnet; history stays available");
+                    if ui.button("Go online")
+                        .on_hover_text("Reconnect to the Mixnet")
+                        .clicked() {
+                        self.go_online();
+                    }
+                } else if ui.add_enabled(!self.loading, egui::Button::new("Go offline"))
+                    .on_hover_text("Disconnect from the Mixnet and free the gateway")
+                    .clicked() {
+                    self.go_offline();
+                }
+            });
         });
 
         ui.separator();
@@ -321,42 +1769,116 @@ impl NymMixnetBrowser {
             let can_go_back = self.current_history_index > 0;
             let can_go_forward = self.current_history_index < self.history.len().saturating_sub(1);
             
-            if ui.add_enabled(can_go_back, egui::Button::new("◀"))
-                .on_hover_text("Go back")
-                .clicked() {
+            if Self::toolbar_button(ui, can_go_back, "◀", "Back", "Go back").clicked() {
                 self.go_back();
             }
             
-            if ui.add_enabled(can_go_forward, egui::Button::new("▶"))
-                .on_hover_text("Go forward") 
-                .clicked() {
+            if Self::toolbar_button(ui, can_go_forward, "▶", "Forward", "Go forward").clicked() {
                 self.go_forward();
             }
             
-            if ui.button("🔄")
-                .on_hover_text("Reload page")
-                .clicked() {
-                self.reload_current_page();
+            if Self::toolbar_button(ui, true, "🔄", "Reload", "Reload page (Shift+click: hard reload)").clicked() {
+                let hard_reload = ui.input(|i| i.modifiers.shift);
+                self.reload_current_page(hard_reload);
+            }
+            
+            if Self::toolbar_button(ui, true, "⚙", "Settings", "Settings").clicked() {
+                self.show_settings = !self.show_settings;
+            }
+
+            if Self::toolbar_button(ui, true, "ℹ", "Page info", "Page info").clicked() {
+                self.show_page_info = !self.show_page_info;
+            }
+
+            let url = self.current_url();
+            let bookmarked = self.bookmarks.position(&url);
+            let can_bookmark = matches!(self.page_state, PageState::Loaded | PageState::ServerError(_));
+            let (star, name) = match bookmarked {
+                Some(_) => ("★", "Remove bookmark"),
+                None => ("☆", "Bookmark this page"),
+            };
+            if Self::toolbar_button(ui, can_bookmark || bookmarked.is_some(), star, name, name).clicked() {
+                match bookmarked {
+                    Some(index) => self.remove_bookmark(index),
+                    None => {
+                        let title = markdown::page_title(&self.current_content).unwrap_or_else(|| url.clone());
+                        self.bookmarks.add(&title, &url);
+                        self.save_bookmarks();
+                    }
+                }
+            }
+            if Self::toolbar_button(ui, true, "📑", "Bookmarks", "Bookmarks").clicked() {
+                self.show_bookmarks = !self.show_bookmarks;
+            }
+            let saving = self.downloads.iter().filter(|download| !download.is_finished()).count();
+            let downloads_hint = match saving {
+                0 => "Downloads".to_string(),
+                count => format!("Downloads ({} saving)", count),
+            };
+            if Self::toolbar_button(ui, true, "⬇", "Downloads", &downloads_hint).clicked() {
+                self.show_downloads = !self.show_downloads;
             }
             
             ui.label("Address:");
+            if !self.server_address.trim().is_empty() {
+                identicon::show(ui, &self.server_address, 16.0)
+                    .on_hover_text(format!("Server {}", self.server_address.trim()));
+            }
             
-            // Address text field
+            // Address text field, focused with Ctrl+L
+            let address_bar_id = egui::Id::new("address_bar");
+            let focus_shortcut = ui.input_mut(|i| {
+                i.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::L))
+            });
+            if focus_shortcut {
+                ui.memory_mut(|memory| memory.request_focus(address_bar_id));
+            }
+            let had_focus = ui.memory(|memory| memory.has_focus(address_bar_id));
+
             let available_width = ui.available_width();
             let text_width = available_width - 40.0;
             let response = ui.add(
                 TextEdit::singleline(&mut self.address_bar)
+                    .id(address_bar_id)
                     .hint_text("nym://server/page")
                     .desired_width(text_width)
                     .min_size(egui::Vec2::new(300.0, 0.0))
             );
 
-            let can_navigate = !self.loading && !self.address_bar.trim().is_empty();
+            let clicked_in = response.clicked() && !had_focus;
+            if (focus_shortcut && self.settings.select_address_on_shortcut)
+                || (clicked_in && self.settings.select_address_on_click) {
+                Self::select_all_text(ui.ctx(), address_bar_id, &self.address_bar);
+            }
+
+            // Focusing the field counts as starting a new address, even
+            // before the first key press
+            if response.gained_focus() {
+                self.address_edited = true;
+            }
+            if response.changed() {
+                self.address_edited = true;
+                self.address_before_correction = None;
+            }
+
+            // Hand keyboard focus to the content once a page arrives, unless
+            // the user has gone back to the address bar in the meantime
+            if self.focus_content_pending {
+                self.focus_content_pending = false;
+                if response.has_focus() && !self.address_edited {
+                    ui.memory_mut(|memory| memory.surrender_focus(response.id));
+                }
+            }
+
+            let can_navigate = self.is_mixnet_ready() && !self.address_bar.trim().is_empty();
             
             // Right-aligned buttons
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                if ui.button("Go").clicked() && can_navigate {
+                if Self::toolbar_button(ui, true, "Go", "Go to address", "Load the address (Enter)").clicked() && can_navigate {
                     self.handle_navigation();
+                    if self.address_before_correction.is_some() {
+                        ui.memory_mut(|memory| memory.request_focus(address_bar_id));
+                    }
                 }
                 
                 //if self.page_loading {
@@ -370,286 +1892,4173 @@ impl NymMixnetBrowser {
                 //}
             });
 
-            // Enter key handling
+            // Enter key handling. A correction keeps the focus, so a
+            // second Enter confirms it.
             if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && can_navigate {
                 self.handle_navigation();
+                if self.address_before_correction.is_some() {
+                    ui.memory_mut(|memory| memory.request_focus(address_bar_id));
+                }
             }
         });
 
-        if let Some(ref err) = self.error {
-            ui.colored_label(Color32::RED, err);
+        self.show_template_actions(ui);
+
+        if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            if let Some(original) = self.address_before_correction.take() {
+                self.address_bar = original;
+            }
+        }
+        if let Some(ref original) = self.address_before_correction {
+            ui.colored_label(Color32::DARK_GRAY, format!("did you mean {}? (auto-corrected)", self.address_bar.trim()))
+                .on_hover_text(format!("You typed \"{}\". Enter goes to the corrected address, Escape brings back what you typed.", original));
         }
 
-        ScrollArea::vertical().show(ui, |ui| {
-            if self.page_loading {
-                ui.vertical_centered(|ui| {
-                    ui.spinner();
-                    // ui.label("Loading via Mixnet...");
-                    if let Some(start_time) = self.page_load_start_time {
-                        let elapsed = start_time.elapsed();
-                        ui.colored_label(Color32::BLUE, format!("Loading for {:.1} seconds", elapsed.as_secs_f32()));
-                    }
-                });
-            } else if self.current_content.is_empty() {
-                self.show_welcome_page(ui);
-            } else {
-                // Simple approach: render markdown directly
-                CommonMarkViewer::new()
-                    .show(ui, &mut self.md_cache, &self.current_content);
-                
-                // Simple link detection - just detect clicks anywhere in content
-                let response = ui.allocate_rect(ui.max_rect(), egui::Sense::click());
-                
-                if response.clicked() {
-                    // Extract first nym:// link from content as fallback
-                    let links = Self::extract_nym_links(&self.current_content);
-                    if !links.is_empty() {
-                        self.pending_navigation = Some(links[0].clone());
-                    }
+        if let Some(target) = self.redirect_to_confirm.clone() {
+            ui.horizontal(|ui| {
+                ui.colored_label(Color32::from_rgb(200, 150, 0), "The server redirects to another server:");
+                ui.add(egui::Label::new(egui::RichText::new(&target).monospace()).truncate())
+                    .on_hover_text(&target);
+                if ui.small_button("Follow").clicked() {
+                    self.redirect_to_confirm = None;
+                    self.pending_navigation = Some(target);
                 }
-            }
-        });
-    }
+                if ui.small_button("Stay").clicked() {
+                    self.redirect_to_confirm = None;
+                    self.redirects_followed = 0;
+                }
+            });
+        }
 
-    // Extract all nym:// links from content
-    fn extract_nym_links(content: &str) -> Vec<String> {
-        let mut links = Vec::new();
-        let mut search_pos = 0;
-        
-        while let Some(start) = content[search_pos..].find("nym://") {
-            let actual_start = search_pos + start;
-            let remaining = &content[actual_start..];
-            
-            let end = remaining.find(|c: char| c.is_whitespace() || c == ')' || c == ']' || c == '>' || c == '"' || c == '\'')
-                .unwrap_or(remaining.len());
-                
-            let link = &remaining[..end];
-            if !link.is_empty() && !links.contains(&link.to_string()) {
-                links.push(link.to_string());
-            }
-            
-            search_pos = actual_start + end;
-            if search_pos >= content.len() {
-                break;
-            }
+        if let Some(url) = self.external_url.clone() {
+            ui.horizontal(|ui| {
+                ui.colored_label(Color32::DARK_RED, "This is a clearnet address and cannot be loaded over the Mixnet.");
+                if ui.button("Open externally").clicked() {
+                    ui.ctx().open_url(egui::OpenUrl::new_tab(url));
+                    self.external_url = None;
+                }
+            });
         }
-        
-        links
-    }
 
-    // Handle link clicks - SIMPLIFIED AND STABLE VERSION
-    fn handle_link_click(&mut self, href: &str) {
-        if href.starts_with("nym://") {
-            if let Some((server, page)) = Self::parse_nym_url(href) {
-                // Simple logic: if it looks like a Nym address, treat as external
-                if server.contains('.') && server.contains('@') {
-                    // External link
-                    let old_server = self.server_address.clone();
-                    self.server_address = server.clone();
-                    
-                    let path = if page.is_empty() { 
-                        "/".to_string() 
-                    } else { 
-                        format!("/{}", page) 
-                    };
-                    
-                    self.address_bar = if page.is_empty() { String::new() } else { page };
-                    
-                    self.page_loading = true;
-                    self.page_load_start_time = Some(Instant::now());
-                    
-                    if let Err(e) = self.send_request(&path) {
-                        self.server_address = old_server;
-                        self.error = Some(e);
-                        self.page_loading = false;
-                        self.page_load_start_time = None;
-                    } else {
-                        self.add_to_history();
-                    }
-                } else {
-                    // Local link
-                    if !self.server_address.is_empty() {
-                        let path = if page.is_empty() { 
-                            format!("/{}", server) 
-                        } else { 
-                            format!("/{}/{}", server, page) 
-                        };
-                        
-                        self.page_loading = true;
-                        self.page_load_start_time = Some(Instant::now());
-                        
-                        if let Err(e) = self.send_request(&path) {
-                            self.error = Some(e);
-                            self.page_loading = false;
-                            self.page_load_start_time = None;
-                        } else {
-                            self.add_to_history();
+        if self.slow_gateway_warning && !self.slow_gateway_dismissed {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    Color32::from_rgb(200, 150, 0),
+                    "Connection seems slow \u{2014} reconnect with a different gateway?",
+                )
+                .on_hover_text(self.gateway_comparison());
+                if ui.small_button("Reconnect").clicked() {
+                    self.go_offline();
+                    self.go_online();
+                }
+                if ui.small_button("Dismiss").clicked() {
+                    self.slow_gateway_dismissed = true;
+                }
+            });
+        }
+
+        if let Some((original, removed)) = self.stripped_link.clone() {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    Color32::DARK_GRAY,
+                    format!("{} tracking parameter{} removed", removed, if removed == 1 { "" } else { "s" }),
+                )
+                .on_hover_text(&original);
+                if ui.small_button("Send anyway with parameters").clicked() {
+                    self.keep_query_once = true;
+                    self.pending_navigation = Some(original);
+                }
+            });
+        }
+
+        if !self.blocked_images.is_empty() {
+            ui.horizontal(|ui| {
+                let count = self.blocked_images.len();
+                ui.colored_label(
+                    Color32::DARK_GRAY,
+                    format!("{} image{} from other servers not loaded", count, if count == 1 { "" } else { "s" }),
+                );
+                if ui.small_button("Load images").clicked() {
+                    for url in std::mem::take(&mut self.blocked_images) {
+                        if let Some(NymUrl { server, .. }) = NymUrl::parse(&url) {
+                            self.image_servers_allowed.insert(server);
                         }
+                        // Asked for again on the next frame
+                        ui.ctx().forget_image(&url);
                     }
                 }
+            });
+        }
+
+        if let Some(err) = self.error.clone() {
+            if self.offline && err == OFFLINE_MODE {
+                ui.horizontal(|ui| {
+                    ui.colored_label(Color32::RED, &err);
+                    if ui.small_button("Go online and retry").clicked() {
+                        self.error = None;
+                        self.retry_when_online = true;
+                        self.go_online();
+                    }
+                });
+            } else {
+                ui.colored_label(Color32::RED, &err);
             }
-        } else if href.starts_with('/') {
-            let path = &href[1..];
-            self.navigate_to(path);
-        } else {
-            self.navigate_to(href);
         }
-    }
 
-    fn navigate_to(&mut self, path: &str) {
-        self.add_to_history();
-        
-        self.page_loading = true;
-        self.page_load_start_time = Some(Instant::now());
-        let request_path = if path.starts_with('/') { 
-            path.to_string() 
-        } else { 
-            format!("/{}", path) 
+        if let Some(restored_at) = self.scroll_restored_at {
+            if restored_at.elapsed() < SCROLL_NOTICE_DURATION {
+                ui.horizontal(|ui| {
+                    ui.label("Returned to your previous position.");
+                    if ui.small_button("Back to top").clicked() {
+                        self.pending_scroll = Some(0.0);
+                        self.scroll_restored_at = None;
+                    }
+                });
+                ui.ctx().request_repaint_after(SCROLL_NOTICE_DURATION);
+            } else {
+                self.scroll_restored_at = None;
+            }
+        }
+
+        self.show_undo_notices(ui);
+
+        let mut scroll_area = ScrollArea::vertical();
+        if let Some(offset) = self.pending_scroll.take() {
+            scroll_area = scroll_area.vertical_scroll_offset(offset);
+        }
+        let content_started = self.frame_timings.start();
+        let scroll_output = scroll_area.show(ui, |ui| {
+            if self.loading && self.client_address.is_empty() {
+                // Until the first "Connected" there is nothing to navigate with
+                ui.vertical_centered(|ui| {
+                    ui.add_space(80.0);
+                    indicators::busy(ui, self.settings.reduce_motion);
+                    ui.heading("Establishing anonymous connection…");
+                    ui.label("Routing through the Nym Mixnet can take a few seconds.");
+                });
+            } else if self.page_loading {
+                ui.vertical_centered(|ui| {
+                    // ui.label("Loading via Mixnet...");
+                    let elapsed = self.page_load_start_time
+                        .map(|start_time| start_time.elapsed())
+                        .unwrap_or_default();
+                    indicators::loading(ui, self.settings.reduce_motion, elapsed);
+
+                    let Some(estimate) = self.metrics.load_estimate(self.server_address.trim()) else {
+                        return;
+                    };
+                    if elapsed <= estimate.slow {
+                        ui.weak(format!("Usually ~{} s for this server", estimate.typical.as_secs().max(1)));
+                        ui.ctx().request_repaint_after(estimate.slow - elapsed);
+                    } else {
+                        ui.colored_label(
+                            Color32::from_rgb(200, 150, 0),
+                            format!("Taking longer than usual (~{} s for this server)", estimate.typical.as_secs().max(1)),
+                        );
+                        ui.horizontal(|ui| {
+                            if ui.button("Cancel").clicked() {
+                                self.fail_navigation("Request cancelled".to_string());
+                            }
+                            if ui.button("🔄 Retry").on_hover_text("Send the request again").clicked() {
+                                self.reload_current_page(true);
+                            }
+                        });
+                    }
+                });
+            } else if self.page_state == PageState::None {
+                self.show_welcome_page(ui);
+            } else if let PageState::TransportError(error) = &self.page_state {
+                let error = error.clone();
+                self.show_transport_error_page(ui, &error);
+            } else if let PageState::ServerError(status) = self.page_state {
+                self.show_server_error_page(ui, status);
+            } else if self.received_file.is_some() {
+                self.show_received_file_page(ui);
+            } else if markdown::is_blank(&self.current_content) {
+                // A successful reply without a body, e.g. for an action
+                self.show_placeholder_page(ui, "✔ Done (no content)", "The server returned an empty page.");
+            } else if let Some(json_document) = &mut self.json_document {
+                json_document.show(ui, &self.current_content);
+            } else {
+                self.show_markdown_content(ui);
+            }
+        });
+
+        self.frame_timings.record(Phase::Content, content_started);
+        self.scroll_offset = scroll_output.state.offset.y;
+        if self.settings.reading_progress {
+            self.show_reading_progress(ui, &scroll_output);
+        }
+
+        let panels_started = self.frame_timings.start();
+        if self.show_settings {
+            self.show_settings_window(ui.ctx());
+        }
+        if self.pending_template.is_some() {
+            self.show_template_prompt(ui.ctx());
+        }
+        if self.show_page_info {
+            self.show_page_info_window(ui.ctx());
+        }
+        if self.report_draft.is_some() {
+            self.show_report_window(ui.ctx());
+        }
+        if self.reset_dialog.is_some() {
+            self.show_reset_window(ui.ctx());
+        }
+        if self.show_health {
+            self.show_health_window(ui.ctx());
+        }
+        if self.show_sent_reports {
+            self.show_sent_reports_window(ui.ctx());
+        }
+        if self.show_bookmarks {
+            self.show_bookmarks_window(ui.ctx());
+        }
+        if self.show_downloads {
+            self.show_downloads_window(ui.ctx());
+        }
+        if self.show_link_hints {
+            self.show_link_hints_overlay(ui.ctx());
+        }
+        self.frame_timings.record(Phase::Panels, panels_started);
+
+        if self.frame_timings.enabled {
+            self.frame_timings.show(ui.ctx());
+        }
+        self.frame_timings.end_frame();
+    }
+
+    // Ctrl+` cycles through the servers used this session, most recent
+    // first; releasing Ctrl goes to the highlighted one
+    fn handle_server_switcher(&mut self, ctx: &egui::Context) {
+        let servers = self.metrics.recent_servers().to_vec();
+        if servers.len() < 2 {
+            self.server_switcher = None;
+            return;
+        }
+
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Backtick)) {
+            let current = self.server_address.trim();
+            let next = match self.server_switcher {
+                Some(index) => (index + 1) % servers.len(),
+                None => servers.iter().position(|server| **server != *current).unwrap_or(0),
+            };
+            self.server_switcher = Some(next);
+        }
+        let Some(index) = self.server_switcher else {
+            return;
         };
-        
-        if let Err(e) = self.send_request(&request_path) {
-            self.error = Some(e);
-            self.page_loading = false;
-            self.page_load_start_time = None;
+
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.server_switcher = None;
+        } else if !ctx.input(|i| i.modifiers.command) {
+            self.server_switcher = None;
+            self.address_bar = format!("nym://{}/", servers[index]);
+            self.handle_navigation();
         } else {
-            self.address_bar = path.to_string();
+            self.show_server_switcher(ctx, &servers, index);
         }
     }
 
-    // Add current page to history
-    fn add_to_history(&mut self) {
-        if self.current_history_index < self.history.len().saturating_sub(1) {
-            self.history.truncate(self.current_history_index + 1);
+    fn show_server_switcher(&self, ctx: &egui::Context, servers: &[ServerId], highlighted: usize) {
+        egui::Area::new(egui::Id::new("server_switcher"))
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 96.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(egui::RichText::new("Switch server (release Ctrl to go, Esc to cancel)").small());
+                    for (index, server) in servers.iter().enumerate() {
+                        // Title of the last page seen from that server
+                        let title = self.history
+                            .iter()
+                            .rev()
+                            .find(|entry| entry.server == *server && entry.fetched_at.is_some())
+                            .map(|entry| markdown::page_title(&entry.content).unwrap_or_else(|| entry.page.clone()))
+                            .unwrap_or_default();
+                        let short = server.get(..16).unwrap_or(server);
+                        ui.horizontal(|ui| {
+                            identicon::show(ui, server, 14.0);
+                            ui.selectable_label(index == highlighted, format!("{}…  {}", short, title))
+                                .on_hover_text(&**server);
+                        });
+                    }
+                });
+            });
+    }
+
+    // Links reachable with Alt+1..9, in page order
+    fn link_hints(&self) -> &[String] {
+        if self.page_state != PageState::Loaded || self.json_document.is_some() {
+            return &[];
         }
-        
-        let history_entry = HistoryEntry {
-            server: self.server_address.clone(),
-            page: self.address_bar.clone(),
-            content: self.current_content.clone(),
-            timestamp: Instant::now(),
-        };
-        
-        self.history.push(history_entry);
-        self.current_history_index = self.history.len().saturating_sub(1);
+        &self.link_hints
     }
 
-    fn handle_server_message(&mut self, content: String) {
-        if content.starts_with("OK\n") {
-            self.current_content = content[3..].to_string();
-        } else {
-            self.current_content = content;
+    fn handle_link_hint_keys(&mut self, ctx: &egui::Context) {
+        // Digits and F belong to whatever text field is being edited
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        const DIGITS: [egui::Key; MAX_LINK_HINTS] = [
+            egui::Key::Num1, egui::Key::Num2, egui::Key::Num3,
+            egui::Key::Num4, egui::Key::Num5, egui::Key::Num6,
+            egui::Key::Num7, egui::Key::Num8, egui::Key::Num9,
+        ];
+        let chosen = ctx.input_mut(|i| {
+            DIGITS.iter().position(|key| i.consume_key(egui::Modifiers::ALT, *key))
+        });
+        if let Some(link) = chosen.and_then(|index| self.link_hints().get(index)) {
+            self.pending_navigation = Some(link.clone());
+        }
+
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::F)) {
+            self.show_link_hints = !self.show_link_hints && !self.link_hints().is_empty();
+        }
+        if self.show_link_hints && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            self.show_link_hints = false;
         }
-        self.error = None;
-        self.page_loading = false;
-        self.page_load_start_time = None;
     }
 
-    fn go_back(&mut self) {
-        if self.current_history_index > 0 {
-            self.current_history_index -= 1;
-            if let Some(entry) = self.history.get(self.current_history_index) {
-                self.server_address = entry.server.clone();
-                self.address_bar = entry.page.clone();
-                self.current_content = entry.content.clone();
-                self.error = None;
-                self.page_loading = false;
-                self.page_load_start_time = None;
-            }
+    // Lists the hints in a corner of the content; badges next to the links
+    // themselves would need the rendered position of each link
+    fn show_link_hints_overlay(&mut self, ctx: &egui::Context) {
+        let links = self.link_hints().to_vec();
+        if links.is_empty() {
+            self.show_link_hints = false;
+            return;
         }
+        egui::Area::new(egui::Id::new("link_hints"))
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-16.0, 96.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(egui::RichText::new("Links (Alt+number, Esc to close)").small());
+                    for (index, link) in links.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(format!(" {} ", index + 1))
+                                .monospace()
+                                .strong()
+                                .background_color(ui.visuals().selection.bg_fill));
+                            if ui.link(link).clicked() {
+                                self.pending_navigation = Some(link.clone());
+                            }
+                        });
+                    }
+                });
+            });
     }
 
-    fn go_forward(&mut self) {
-        if self.current_history_index < self.history.len().saturating_sub(1) {
-            self.current_history_index += 1;
-            if let Some(entry) = self.history.get(self.current_history_index) {
-                self.server_address = entry.server.clone();
-                self.address_bar = entry.page.clone();
-                self.current_content = entry.content.clone();
-                self.error = None;
-                self.page_loading = false;
-                self.page_load_start_time = None;
-            }
+    fn current_url(&self) -> String {
+        format!("nym://{}/{}", self.server_address.trim(), self.address_bar.trim_start_matches('/'))
+    }
+
+    fn show_page_info_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_page_info;
+        let mut open_settings = false;
+
+        egui::Window::new("Page info")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if self.server_address.trim().is_empty() {
+                    ui.label("No page loaded.");
+                } else {
+                    let url = self.current_url();
+                    let title = markdown::page_title(&self.current_content);
+                    if let Some(title) = &title {
+                        ui.label(format!("Title: {}", title));
+                    }
+                    ui.label(format!("Address: {}", url));
+                    ui.label(format!("Size: {} bytes", self.current_content.len()));
+                    ui.weak(format!("Render caches: {} of {} pages", self.md_caches.len(), RENDER_CACHE_PAGES));
+                    if self.page_words > 0 {
+                        ui.label(format!("Reading time: {}", markdown::reading_time(self.page_words)));
+                    }
+                    if ui.button("Copy as Markdown link")
+                        .on_hover_text("[title](nym://…) for pasting into another page")
+                        .clicked() {
+                        ui.ctx().copy_text(markdown::link_to(title.as_deref().unwrap_or(&url), &url));
+                    }
+                    if !self.settings.report_address.trim().is_empty()
+                        && self.page_state != PageState::None
+                        && ui.button("Report page…")
+                            .on_hover_text("Send a report about this page to your configured collector")
+                            .clicked() {
+                        self.report_draft = Some(ReportDraft::default());
+                        self.report_status = None;
+                        self.report_upload_status = None;
+                    }
+                }
+
+                egui::CollapsingHeader::new("Privacy summary")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        let Some(request) = &self.last_request else {
+                            ui.label("No request sent yet.");
+                            return;
+                        };
+
+                        // Other formats place the address where reply_address() cannot find it
+                        let sends_address = match self.settings.request_formats.get(&*request.server) {
+                            None => protocol::reply_address(&request.message).is_some(),
+                            Some(format) => format.carries_address() && !self.settings.anonymous_requests,
+                        };
+                        if sends_address {
+                            Self::privacy_row(
+                                ui,
+                                Color32::RED,
+                                "Your address",
+                                "Included in the request: the server learns your client address",
+                            );
+                        } else if self.settings.anonymous_requests {
+                            Self::privacy_row(
+                                ui,
+                                Color32::DARK_GREEN,
+                                "Your address",
+                                "Not sent: the server answers over single-use reply blocks (SURBs)",
+                            );
+                        } else {
+                            Self::privacy_row(
+                                ui,
+                                Color32::DARK_GREEN,
+                                "Your address",
+                                "Not included by this server's request format",
+                            );
+                        }
+
+                        let client_header = request.message.lines()
+                            .find_map(|line| line.strip_prefix("Client: "));
+                        let response = match client_header {
+                            Some(client_id) => Self::privacy_row(
+                                ui,
+                                Color32::from_rgb(200, 150, 0),
+                                "Client header",
+                                &format!("Sent \"{}\": identifies your client software", client_id),
+                            ),
+                            None => Self::privacy_row(
+                                ui,
+                                Color32::DARK_GREEN,
+                                "Client header",
+                                "Not sent",
+                            ),
+                        };
+                        if response.on_hover_text("Change in settings").clicked() {
+                            open_settings = true;
+                        }
+
+                        let size = request.message.len();
+                        Self::privacy_row(
+                            ui,
+                            if size < 512 { Color32::DARK_GREEN } else { Color32::from_rgb(200, 150, 0) },
+                            "Payload size",
+                            &format!("{} bytes: request sizes can correlate with what you ask for", size),
+                        );
+
+                        let count = self.metrics.requests_to(&request.server);
+                        Self::privacy_row(
+                            ui,
+                            if count <= 10 { Color32::DARK_GREEN } else { Color32::from_rgb(200, 150, 0) },
+                            "Requests to this server",
+                            &format!("{} this session: many requests make a session easier to profile", count),
+                        );
+                    });
+
+                if let Some(request) = &self.last_request {
+                    egui::CollapsingHeader::new("Raw request").show(ui, |ui| {
+                        ui.add(egui::Label::new(egui::RichText::new(&request.message).monospace()).wrap());
+                    });
+                }
+            });
+
+        self.show_page_info = open;
+        if open_settings {
+            self.show_settings = true;
         }
     }
 
-    fn reload_current_page(&mut self) {
-        if !self.server_address.is_empty() {
-            self.page_loading = true;
-            self.page_load_start_time = Some(Instant::now());
-            
-            let path = if self.address_bar.is_empty() {
-                "/".to_string()
-            } else if self.address_bar.starts_with('/') {
-                self.address_bar.clone()
-            } else {
-                format!("/{}", self.address_bar)
-            };
+    // One row per recent removal, until it is undone, dismissed or expires
+    fn show_undo_notices(&mut self, ui: &mut Ui) {
+        self.undo.expire();
+        let mut undone = None;
+        let mut dismissed = None;
+        for (index, removed) in self.undo.entries().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(removed.describe());
+                if ui.small_button("Undo").clicked() {
+                    undone = Some(index);
+                }
+                if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                    dismissed = Some(index);
+                }
+            });
+        }
 
-            if let Err(e) = self.send_request(&path) {
+        if let Some(removed) = undone.and_then(|index| self.undo.take(index)) {
+            if removed.restore(&mut self.settings, &mut self.bookmarks) {
+                self.save_bookmarks();
+            } else if let Err(e) = self.settings.save(self.storage.as_ref()) {
                 self.error = Some(e);
-                self.page_loading = false;
-                self.page_load_start_time = None;
             }
+        } else if let Some(index) = dismissed {
+            self.undo.dismiss(index);
+        }
+        if let Some(expiry) = self.undo.next_expiry() {
+            ui.ctx().request_repaint_after(expiry);
         }
     }
 
-    fn show_welcome_page(&self, ui: &mut Ui) {
-        ui.vertical_centered(|ui| {
-            ui.heading("NymView for Nym Mixnet");
-            ui.label("Welcome! Enter a nym:// address to begin.");
-            ui.separator();
-            
-            let demo_content = r#"# NymView for Nym Mixnet
-            
-## Features:
-- **Secure** communication via Nym Mixnet
-- **Markdown** support
-- **Private** navigation
-- **History** navigation (◀ ▶ buttons)
-- **Auto-reload** (🔄 button)
-- **30-second timeout** for unresponsive servers
+    // Icon buttons get a spoken name for screen readers, and an outline
+    // while focused since egui only tints focused buttons. Space and Enter
+    // activate a focused button.
+    fn toolbar_button(ui: &mut Ui, enabled: bool, text: &str, name: &str, hint: &str) -> egui::Response {
+        let response = ui.add_enabled(enabled, egui::Button::new(text)).on_hover_text(hint);
+        response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, enabled, name));
+        if response.has_focus() {
+            ui.painter().rect_stroke(
+                response.rect.expand(2.0),
+                4.0,
+                ui.visuals().selection.stroke,
+                egui::StrokeKind::Outside,
+            );
+        }
+        response
+    }
 
-### Example content:
-- `nym://server/` - Homepage
-- `nym://server/about` - About us
-- `nym://server/help` - Help
+    // Reports are only sent from the preview, so the user has seen the
+    // exact payload, their address included
+    fn show_report_window(&mut self, ctx: &egui::Context) {
+        let Some(mut draft) = self.report_draft.take() else {
+            return;
+        };
+        let mut open = true;
+        let collector = self.settings.report_address.trim().to_string();
+        let reads_headers = self.settings.header_servers.contains(&collector);
+        if !reads_headers && !collector.is_empty() {
+            if let Ok(sender) = self.mixnet_sender() {
+                self.probe_header_support(&collector, &sender);
+            }
+        }
 
-*Enter an address to begin*"#;
-            
-            CommonMarkViewer::new()
-                .show(ui, &mut CommonMarkCache::default(), demo_content);
+        egui::Window::new("Report page")
+            .open(&mut open)
+            .collapsible(false)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                ui.label(format!("Page: {}", self.current_url()));
+                ui.label(format!("Collector: {}", collector));
+                ui.separator();
+
+                ui.label("Excerpt (paste the offending part of the page):");
+                let excerpt = ui.add(TextEdit::multiline(&mut draft.excerpt).desired_rows(4));
+                ui.label("Comment (optional):");
+                let comment = ui.add(TextEdit::multiline(&mut draft.comment).desired_rows(2));
+                let mut changed = excerpt.changed() || comment.changed();
+
+                if !reads_headers {
+                    if self.header_probe.as_ref().is_some_and(|(server, _)| *server == collector) {
+                        ui.horizontal(|ui| {
+                            indicators::busy(ui, self.settings.reduce_motion);
+                            ui.label("Checking whether the collector reads request headers\u{2026}");
+                        });
+                    } else {
+                        ui.colored_label(
+                            Color32::from_rgb(200, 150, 0),
+                            "The collector does not read request headers, so its answer could not be told apart from a page's.",
+                        );
+                    }
+                }
+                ui.horizontal(|ui| {
+                    let paste = ui.add_enabled(reads_headers && !self.pasting_image, egui::Button::new("Paste image"))
+                        .on_hover_text("Attach the image on the clipboard, e.g. a screenshot of the page")
+                        .on_disabled_hover_text("The collector does not read request headers");
+                    if paste.clicked() {
+                        self.paste_image(ui.ctx());
+                    }
+                    if self.pasting_image {
+                        indicators::busy(ui, self.settings.reduce_motion);
+                    }
+                    if draft.image.is_some() && ui.button("Remove image").clicked() {
+                        draft.image = None;
+                        changed = true;
+                    }
+                });
+                if let Some(image) = &draft.image {
+                    let [width, height] = image.texture.size();
+                    ui.add(egui::Image::new(&image.texture).max_height(120.0));
+                    ui.label(format!("{}×{}, {} KB as PNG", width, height, image.png.len().div_ceil(1024)));
+                }
+                if changed {
+                    draft.preview = None;
+                    draft.upload_preview = None;
+                }
+
+                if ui.add_enabled(reads_headers, egui::Button::new("Preview report")).clicked() {
+                    let mut report = Report::new(&self.current_url(), &self.current_content, &draft.excerpt, &draft.comment);
+                    if let Some(image) = &draft.image {
+                        report = report.with_image(&image.png);
+                    }
+                    let request_id = self.next_request_id;
+                    match report.payload(self.reply_address(), Some(request_id)) {
+                        Ok(payload) => {
+                            self.next_request_id += 1;
+                            draft.upload_preview = draft.image.as_ref().map(|image| {
+                                let upload_id = self.next_request_id;
+                                self.next_request_id += 1;
+                                let request = upload::upload_request(
+                                    &report.url, self.reply_address(), "image/png", &image.png, Some(upload_id),
+                                );
+                                (upload_id, request.to_string())
+                            });
+                            draft.preview = Some((report, request_id, payload));
+                        }
+                        Err(e) => self.report_status = Some(e),
+                    }
+                }
+
+                if let Some((report, request_id, payload)) = draft.preview.clone() {
+                    ui.label(if self.settings.anonymous_requests {
+                        "This exact message will be sent, without your client address:"
+                    } else {
+                        "This exact message will be sent, including your client address:"
+                    });
+                    let mut shown = payload.as_str();
+                    ui.add(TextEdit::multiline(&mut shown).code_editor().desired_width(f32::INFINITY));
+                    if let Some((_, upload)) = &draft.upload_preview {
+                        ui.label("Followed by the image, its data left out here:");
+                        let (head, body) = upload.split_once("\n\n").unwrap_or((upload, ""));
+                        let mut shown = format!("{}\n\n<{} characters of base64>", head, body.len());
+                        ui.add(TextEdit::multiline(&mut shown).code_editor().interactive(false).desired_width(f32::INFINITY));
+                    }
+                    if ui.button("Send report").clicked() {
+                        match self.send_report(&collector, request_id, report, payload, draft.upload_preview.clone()) {
+                            Ok(()) => {
+                                self.report_status = Some("Report sent, waiting for the collector to confirm.".to_string());
+                                draft = ReportDraft::default();
+                            }
+                            Err(e) => self.report_status = Some(e),
+                        }
+                    }
+                }
+
+                if let Some(status) = &self.report_status {
+                    ui.separator();
+                    ui.label(status);
+                }
+                if let Some(status) = &self.report_upload_status {
+                    ui.label(status);
+                }
+
+                if ui.link(format!("Sent reports ({})", self.sent_reports.reports.len())).clicked() {
+                    self.show_sent_reports = true;
+                }
+            });
+
+        if open {
+            self.report_draft = Some(draft);
+        }
+    }
+
+    fn show_sent_reports_window(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        egui::Window::new("Sent reports")
+            .open(&mut open)
+            .collapsible(false)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                ui.weak(REPORTS_URL);
+                if self.sent_reports.reports.is_empty() {
+                    ui.label("No reports have been sent.");
+                }
+                for sent in self.sent_reports.reports.iter().rev() {
+                    ui.separator();
+                    ui.strong(&sent.report.url);
+                    let sent_at = UNIX_EPOCH + Duration::from_secs(sent.report.timestamp);
+                    ui.weak(format!("Sent to {} on {}", sent.collector, timefmt::absolute(sent_at)));
+                    ui.label(format!("Excerpt: {}", sent.report.excerpt));
+                    if !sent.report.comment.is_empty() {
+                        ui.label(format!("Comment: {}", sent.report.comment));
+                    }
+                    if sent.report.image_sha256.is_some() {
+                        ui.weak("With an image");
+                    }
+                }
+            });
+        self.show_sent_reports = open;
+    }
+
+    // Reading the clipboard and encoding a screenshot as PNG can take a
+    // while, so it runs on the blocking pool; ImagePasted brings the image
+    fn paste_image(&mut self, ctx: &egui::Context) {
+        let Some(sender) = self.message_sender.clone() else {
+            return;
+        };
+        self.pasting_image = true;
+        let ctx = ctx.clone();
+        RUNTIME.spawn_blocking(move || {
+            let result = upload::paste_image(&ctx);
+            let _ = sender.send(BrowserMessage::ImagePasted { result });
+            ctx.request_repaint();
         });
     }
-}
 
-// App Trait Implementation for eframe
-impl App for NymMixnetBrowser {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            self.show(ui);
+    fn send_report(
+        &mut self,
+        collector: &str,
+        request_id: u64,
+        report: Report,
+        payload: String,
+        upload: Option<(u64, String)>,
+    ) -> Result<(), String> {
+        // An untagged answer could not be told apart from a page's
+        if !self.settings.header_servers.contains(collector) {
+            return Err("The collector does not read request headers".to_string());
+        }
+        self.settings.server_filter.check(collector)?;
+        if self.bandwidth_exhausted_since.is_some() {
+            return Err(BANDWIDTH_EXHAUSTED.to_string());
+        }
+        let sender = self.mixnet_sender()?;
+        sender.send(BrowserMessage::SendRequest {
+            request_id,
+            recipient: collector.to_string(),
+            message: payload,
+            reply_surbs: self.reply_surbs(),
+        }).map_err(|e| format!("Send error: {}", e))?;
+        self.report_request_id = Some(request_id);
+        self.report_upload_status = None;
+
+        // The report is out, so it is kept even if the image is not; sending
+        // the draft again would report the page twice
+        self.sent_reports.reports.push(SentReport {
+            collector: collector.to_string(),
+            report,
         });
+        if let Err(e) = self.sent_reports.save(self.storage.as_ref()) {
+            self.error = Some(e);
+        }
+
+        if let Some((upload_id, message)) = upload {
+            let sent = sender.send(BrowserMessage::SendRequest {
+                request_id: upload_id,
+                recipient: collector.to_string(),
+                message,
+                reply_surbs: self.reply_surbs(),
+            });
+            match sent {
+                Ok(()) => self.report_upload_id = Some(upload_id),
+                Err(e) => self.report_upload_status = Some(format!("The image was not sent: {}", e)),
+            }
+        }
+        Ok(())
     }
-}
 
-impl Clone for NymMixnetBrowser {
-    fn clone(&self) -> Self {
-        Self {
-            address_bar: self.address_bar.clone(),
-            current_content: self.current_content.clone(),
-            loading: self.loading,
-            page_loading: self.page_loading,
-            error: self.error.clone(),
-            connection_status: self.connection_status.clone(),
-            server_address: self.server_address.clone(),
-            client_address: self.client_address.clone(),
-            message_receiver: None,
-            message_sender: None,
-            history: self.history.clone(),
-            connection_attempted: self.connection_attempted,
-            md_cache: CommonMarkCache::default(),
-            pending_navigation: None,
-            current_history_index: self.current_history_index,
-            page_load_start_time: None,
+    // Colored privacy summary row; clicking it opens the related setting
+    fn privacy_row(ui: &mut Ui, color: Color32, label: &str, explanation: &str) -> egui::Response {
+        ui.horizontal(|ui| {
+            ui.colored_label(color, "●");
+            ui.strong(label);
+            ui.label(explanation);
+        })
+        .response
+        .interact(egui::Sense::click())
+    }
+
+    // Quick-action buttons for the current server's request templates
+    fn show_template_actions(&mut self, ui: &mut Ui) {
+        let Some(templates) = self.settings.request_templates.get(self.server_address.trim()) else {
+            return;
+        };
+        if templates.is_empty() {
+            return;
+        }
+
+        let mut selected = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Quick actions:");
+            for template in templates {
+                if ui.small_button(&template.name)
+                    .on_hover_text(&template.path)
+                    .clicked() {
+                    selected = Some(template.clone());
+                }
+            }
+        });
+
+        if let Some(template) = selected {
+            if template.variables().is_empty() {
+                self.navigate_to(&template.path);
+            } else {
+                self.pending_template = Some(PendingTemplate::new(template));
+            }
         }
     }
+
+    fn show_template_prompt(&mut self, ctx: &egui::Context) {
+        let Some(pending) = &mut self.pending_template else {
+            return;
+        };
+
+        let mut open = true;
+        let mut send = false;
+        egui::Window::new(pending.template.name.clone())
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                for variable in pending.template.variables() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}:", variable));
+                        let value = pending.values.entry(variable).or_default();
+                        let response = ui.text_edit_singleline(value);
+                        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            send = true;
+                        }
+                    });
+                }
+                ui.label(format!("Request: {}", pending.template.expand(&pending.values)));
+                if ui.button("Send").clicked() {
+                    send = true;
+                }
+            });
+
+        let path = pending.template.expand(&pending.values);
+        if send && self.is_mixnet_ready() {
+            self.pending_template = None;
+            self.navigate_to(&path);
+        } else if !open {
+            self.pending_template = None;
+        }
+    }
+
+    fn show_settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings;
+        let mut changed = false;
+        let mut history_limit_changed = false;
+
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                if ui.checkbox(&mut self.settings.reduce_motion, "Reduce motion")
+                    .on_hover_text("Show static loading text instead of spinners and animations")
+                    .changed() {
+                    changed = true;
+                }
+                ui.checkbox(&mut self.frame_timings.enabled, "Show frame timings")
+                    .on_hover_text("Time spent per frame on messages, content and panels, for bug reports");
+                if ui.button("Reset window layout")
+                    .on_hover_text("Forget the saved window size and position")
+                    .clicked() {
+                    self.reset_window_layout(ctx);
+                    changed = true;
+                }
+
+                ui.separator();
+                ui.label("Select the whole address when focusing the address bar:");
+                if ui.checkbox(&mut self.settings.select_address_on_shortcut, "with Ctrl+L").changed() {
+                    changed = true;
+                }
+                if ui.checkbox(&mut self.settings.select_address_on_click, "with a mouse click").changed() {
+                    changed = true;
+                }
+
+                ui.separator();
+                let anonymous = self.settings.anonymous_requests;
+                if ui.add_enabled(!anonymous, egui::Checkbox::new(&mut self.settings.send_client_id, "Send client identifier"))
+                    .on_hover_text("Lets servers that read request headers adapt to your client, at the cost of revealing it")
+                    .on_disabled_hover_text("Never sent with anonymous requests")
+                    .changed() {
+                    changed = true;
+                }
+                ui.add_enabled_ui(self.settings.send_client_id, |ui| {
+                    ui.horizontal(|ui| {
+                        // Saved once, when editing is done
+                        if ui.text_edit_singleline(&mut self.settings.client_id).lost_focus() {
+                            changed = true;
+                        }
+                        if ui.small_button("Default").clicked() {
+                            self.settings.client_id = settings::default_client_id();
+                            changed = true;
+                        }
+                    });
+                });
+
+                if ui.checkbox(&mut self.settings.accept_compression, "Ask for compressed replies")
+                    .on_hover_text("Servers that support it send pages zstd-compressed, so fewer packets cross the mixnet")
+                    .changed() {
+                    changed = true;
+                }
+                if ui.checkbox(&mut self.settings.cross_server_images, "Load images from other servers")
+                    .on_hover_text("Otherwise they wait for a click; loading them tells their servers which page you visited")
+                    .changed() {
+                    changed = true;
+                }
+
+                ui.separator();
+                ui.label("Scroll position on reload and back/forward:");
+                ui.horizontal(|ui| {
+                    for policy in [ScrollPolicy::AlwaysTop, ScrollPolicy::Restore, ScrollPolicy::RestoreIfRecent] {
+                        let label = match policy {
+                            ScrollPolicy::AlwaysTop => "Always top",
+                            ScrollPolicy::Restore => "Restore",
+                            ScrollPolicy::RestoreIfRecent => "Restore if recent",
+                        };
+                        changed |= ui.radio_value(&mut self.settings.scroll_policy, policy, label).changed();
+                    }
+                });
+                if self.settings.scroll_policy == ScrollPolicy::RestoreIfRecent {
+                    let response = ui.add(
+                        egui::Slider::new(&mut self.settings.scroll_restore_minutes, 1..=120).text("minutes")
+                    );
+                    changed |= response.drag_stopped() || (response.changed() && !response.dragged());
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Suggest reconnecting when requests take over");
+                    let response = ui.add(egui::DragValue::new(&mut self.settings.slow_gateway_threshold_secs)
+                        .range(5..=120)
+                        .suffix(" s"));
+                    changed |= response.drag_stopped() || (response.changed() && !response.dragged());
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Pages kept in history between sessions");
+                    let response = ui.add(egui::DragValue::new(&mut self.settings.history_limit).range(0..=1000))
+                        .on_hover_text("0 keeps no history on disk");
+                    if response.drag_stopped() || (response.changed() && !response.dragged()) {
+                        history_limit_changed = true;
+                    }
+                });
+                if ui.checkbox(&mut self.settings.history_keep_pages, "Keep page contents in saved history")
+                    .on_hover_text("Otherwise only addresses are saved, and earlier pages are fetched again when revisited")
+                    .changed() {
+                    history_limit_changed = true;
+                }
+                if ui.checkbox(&mut self.settings.reading_progress, "Show reading progress").changed() {
+                    changed = true;
+                }
+                ui.add_enabled_ui(self.settings.reading_progress, |ui| {
+                    if ui.checkbox(&mut self.settings.reading_progress_percent, "with percentage").changed() {
+                        changed = true;
+                    }
+                });
+
+                ui.separator();
+                if self.show_appearance_settings(ui) {
+                    changed = true;
+                }
+
+                ui.separator();
+                if self.show_server_filter_settings(ui) {
+                    changed = true;
+                }
+
+                ui.separator();
+                ui.label("Send page reports to (empty disables reporting):");
+                let response = ui.add(TextEdit::singleline(&mut self.settings.report_address)
+                    .hint_text("Collector address")
+                    .desired_width(f32::INFINITY));
+                if response.lost_focus() {
+                    changed = true;
+                }
+
+                ui.separator();
+                ui.label("Remove these query parameters from followed links:");
+                let response = ui.add(TextEdit::singleline(&mut self.stripped_params_input)
+                    .hint_text("utm_source, fbclid, …")
+                    .desired_width(f32::INFINITY));
+                if response.lost_focus() {
+                    self.settings.stripped_query_params = self.stripped_params_input
+                        .split(',')
+                        .map(|name| name.trim().to_string())
+                        .filter(|name| !name.is_empty())
+                        .collect();
+                    self.stripped_params_input = self.settings.stripped_query_params.join(", ");
+                    changed = true;
+                }
+
+                let server = self.server_address.trim().to_string();
+                if !server.is_empty() {
+                    ui.separator();
+                    ui.label("Landing page for the current server:");
+                    let mut landing = self.settings.landing_paths.get(&server).cloned().unwrap_or_default();
+                    let response = ui.add(TextEdit::singleline(&mut landing).hint_text("/"));
+                    if response.changed() {
+                        if landing.trim().is_empty() {
+                            self.settings.landing_paths.remove(&server);
+                        } else {
+                            self.settings.landing_paths.insert(server.clone(), landing);
+                        }
+                    }
+                    // Saved once, when editing is done
+                    if response.lost_focus() {
+                        changed = true;
+                    }
+
+                    ui.label("Request templates for the current server:");
+                    let templates = self.settings.request_templates.entry(server.clone()).or_default();
+                    let mut removed = None;
+                    for (index, template) in templates.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{}: {}", template.name, template.path));
+                            if ui.small_button("✖").on_hover_text("Remove template").clicked() {
+                                removed = Some(index);
+                            }
+                        });
+                    }
+                    if let Some(index) = removed {
+                        let template = templates.remove(index);
+                        self.undo.push(Removed::Template { server: server.clone(), index, template });
+                        changed = true;
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.add(TextEdit::singleline(&mut self.new_template.name)
+                            .hint_text("Name")
+                            .desired_width(80.0));
+                        ui.add(TextEdit::singleline(&mut self.new_template.path)
+                            .hint_text("search?q={query}")
+                            .desired_width(160.0));
+                        let valid = !self.new_template.name.trim().is_empty()
+                            && !self.new_template.path.trim().is_empty();
+                        if ui.add_enabled(valid, egui::Button::new("Add")).clicked() {
+                            templates.push(RequestTemplate {
+                                name: self.new_template.name.trim().to_string(),
+                                path: self.new_template.path.trim().to_string(),
+                            });
+                            self.new_template = RequestTemplate { name: String::new(), path: String::new() };
+                            changed = true;
+                        }
+                    });
+                    if templates.is_empty() {
+                        self.settings.request_templates.remove(&server);
+                    }
+
+                    if self.show_request_format_settings(ui, &server) {
+                        changed = true;
+                    }
+                }
+
+                ui.separator();
+                if ui.checkbox(&mut self.settings.anonymous_requests, "Anonymous requests")
+                    .on_hover_text("Send requests without your address; servers reply over SURBs. \
+                                    Servers that predate this cannot answer.")
+                    .changed() {
+                    changed = true;
+                }
+                if ui.checkbox(&mut self.settings.persistent_identity, "Keep the same Nym address across restarts")
+                    .on_hover_text(format!(
+                        "Takes effect on the next connection. Keys are stored unencrypted in {}",
+                        config::get_config_dir(CLIENT_IDENTITY_DIR).display(),
+                    ))
+                    .changed() {
+                    changed = true;
+                }
+
+                ui.separator();
+                if self.show_replay_settings(ui) {
+                    changed = true;
+                }
+
+                ui.separator();
+                self.show_encryption_settings(ui);
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!self.exporting_diagnostics, egui::Button::new("Export diagnostics…"))
+                        .on_hover_text("Save version, settings and counters for a bug report; server addresses are left out")
+                        .clicked() {
+                        self.export_diagnostics(ui.ctx());
+                    }
+                    if self.exporting_diagnostics {
+                        indicators::busy(ui, self.settings.reduce_motion);
+                    }
+                    if let Some(path) = &self.diagnostics_path {
+                        if ui.small_button("Show file").on_hover_text(path.display().to_string()).clicked() {
+                            if let Err(e) = Self::open_with_system(path.parent().unwrap_or(path)) {
+                                self.error = Some(format!("Could not open folder: {}", e));
+                            }
+                        }
+                    }
+                });
+                if let Some(path) = &self.diagnostics_path {
+                    ui.label(format!("Diagnostics saved to {}", path.display()));
+                }
+
+                ui.separator();
+                if ui.button("Reset NymView…")
+                    .on_hover_text("Choose which stored data to delete and start over")
+                    .clicked() {
+                    self.reset_dialog = Some(ResetDialog::new(self.storage.as_ref()));
+                }
+            });
+
+        self.show_settings = open;
+        if history_limit_changed {
+            self.save_history();
+            changed = true;
+        }
+        if changed {
+            self.apply_motion_preference(ctx);
+            if let Err(e) = self.settings.save(self.storage.as_ref()) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    // Returns whether the server's request format changed
+    fn show_request_format_settings(&mut self, ui: &mut Ui, server: &str) -> bool {
+        let mut changed = false;
+        let current = self.settings.request_formats.get(server).cloned().unwrap_or_default();
+        ui.horizontal(|ui| {
+            ui.label("Request format:");
+            egui::ComboBox::from_id_salt("request_format")
+                .selected_text(current.label())
+                .show_ui(ui, |ui| {
+                    for preset in RequestFormat::PRESETS {
+                        let label = preset.label();
+                        if ui.selectable_label(current == preset, label).clicked() && current != preset {
+                            self.set_request_format(server, preset);
+                            changed = true;
+                        }
+                    }
+                    let custom = matches!(current, RequestFormat::Custom(_));
+                    if ui.selectable_label(custom, "Custom template").clicked() && !custom {
+                        // Starts from the default format so the result is sendable right away
+                        self.request_format_input = "GET {path} FROM {addr}".to_string();
+                        self.set_request_format(server, RequestFormat::Custom(self.request_format_input.clone()));
+                        changed = true;
+                    }
+                });
+        });
+
+        if let RequestFormat::Custom(template) = &current {
+            if self.request_format_server != server {
+                self.request_format_server = server.to_string();
+                self.request_format_input = template.clone();
+            }
+            let response = ui.add(TextEdit::singleline(&mut self.request_format_input)
+                .hint_text("FETCH {path}")
+                .font(egui::TextStyle::Monospace)
+                .desired_width(f32::INFINITY));
+            match request_format::validate_template(&self.request_format_input) {
+                Ok(()) if response.lost_focus() && self.request_format_input != *template => {
+                    self.set_request_format(server, RequestFormat::Custom(self.request_format_input.clone()));
+                    changed = true;
+                }
+                Ok(()) => {}
+                Err(e) => {
+                    ui.colored_label(Color32::RED, e);
+                }
+            }
+        }
+
+        let mut reads_headers = self.settings.header_servers.contains(server);
+        if ui.checkbox(&mut reads_headers, "Server reads request headers")
+            .on_hover_text("Request IDs, compression and the client identifier are only sent to servers that read them. NymView servers are detected on the first visit.")
+            .changed() {
+            if reads_headers {
+                self.settings.header_servers.insert(server.to_string());
+            } else {
+                self.settings.header_servers.remove(server);
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    fn set_request_format(&mut self, server: &str, format: RequestFormat) {
+        if format == RequestFormat::NymView {
+            self.settings.request_formats.remove(server);
+        } else {
+            self.settings.request_formats.insert(server.to_string(), format);
+        }
+    }
+
+    // Recording and replay take effect on the next connection
+    fn show_replay_settings(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        let file = replay::default_file();
+        ui.label("Record and replay:");
+        if ui.checkbox(&mut self.settings.record_session, "Record replies")
+            .on_hover_text(format!(
+                "Takes effect on the next connection. Pages are written unencrypted to {}",
+                file.display(),
+            ))
+            .changed() {
+            changed = true;
+        }
+        if self.settings.record_session {
+            ui.colored_label(
+                Color32::from_rgb(200, 150, 0),
+                "Recorded pages are kept unencrypted, also across restarts, until removed with Reset NymView\u{2026}",
+            );
+        }
+        if ui.checkbox(&mut self.settings.replay_session, "Replay recorded pages instead of using the mixnet")
+            .on_hover_text("Takes effect on the next connection; for demos without a network")
+            .changed() {
+            changed = true;
+        }
+        ui.add_enabled_ui(self.settings.replay_session, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Replay delay:");
+                if ui.add(egui::DragValue::new(&mut self.settings.replay_latency_ms)
+                    .range(0..=30_000)
+                    .suffix(" ms"))
+                    .on_hover_text("How long each recorded reply is held back")
+                    .changed() {
+                    changed = true;
+                }
+            });
+        });
+        if let Some(mode) = &self.session_override {
+            ui.weak(match mode {
+                SessionMode::Replay(file) => format!("Replaying {} as asked on the command line", file.display()),
+                SessionMode::Record(file) => format!("Recording to {} as asked on the command line", file.display()),
+                SessionMode::Live => String::new(),
+            });
+        }
+        changed
+    }
+
+    fn show_encryption_settings(&mut self, ui: &mut Ui) {
+        ui.label("Local data encryption:");
+        if self.storage.is_encrypted() {
+            ui.label("Settings, bookmarks, history and reports are encrypted; NymView asks for the passphrase at startup.");
+            if ui.button("Stop encrypting").clicked() {
+                let plain: Arc<dyn Storage> = Arc::new(FileStorage::default());
+                // Everything is rewritten in plain text before the key is
+                // dropped; a document left encrypted would be lost with it
+                let result = self.persist_to(self.storage.as_ref(), plain.as_ref())
+                    .and_then(|_| EncryptedStorage::disable(plain.as_ref()));
+                match result {
+                    Ok(()) => self.storage = plain,
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            return;
+        }
+        if self.storage.path(WELCOME_FILE).is_none() {
+            ui.label("Nothing is stored on disk in this session.");
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut self.encryption_passphrase)
+                .password(true)
+                .hint_text("Passphrase")
+                .desired_width(120.0));
+            ui.add(TextEdit::singleline(&mut self.encryption_confirm)
+                .password(true)
+                .hint_text("Repeat")
+                .desired_width(120.0));
+            let matching = !self.encryption_passphrase.is_empty()
+                && self.encryption_passphrase == self.encryption_confirm;
+            if ui.add_enabled(matching, egui::Button::new("Encrypt"))
+                .on_hover_text("Encrypt settings, bookmarks, history and reports on disk; the passphrase cannot be recovered")
+                .clicked() {
+                match EncryptedStorage::enable(self.storage.clone(), &self.encryption_passphrase) {
+                    Ok(encrypted) => {
+                        let encrypted: Arc<dyn Storage> = Arc::new(encrypted);
+                        match self.persist_to(self.storage.as_ref(), encrypted.as_ref()) {
+                            Ok(()) => self.storage = encrypted,
+                            Err(e) => {
+                                // Documents encrypted so far are written back in
+                                // plain text before the key goes, or they would
+                                // be lost with it
+                                let rollback = self.persist_to(encrypted.as_ref(), self.storage.as_ref())
+                                    .and_then(|_| EncryptedStorage::disable(self.storage.as_ref()));
+                                self.error = Some(match rollback {
+                                    Ok(()) => e,
+                                    Err(rollback) => format!("{}; undoing it failed: {}", e, rollback),
+                                });
+                            }
+                        }
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+                self.encryption_passphrase.clear();
+                self.encryption_confirm.clear();
+            }
+        });
+    }
+
+    fn show_reset_window(&mut self, ctx: &egui::Context) {
+        let Some(mut dialog) = self.reset_dialog.take() else {
+            return;
+        };
+        let mut open = true;
+        let mut reset = false;
+
+        egui::Window::new("Reset NymView")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if let Some(summary) = &dialog.summary {
+                    ui.label(summary);
+                    return;
+                }
+                if dialog.artifacts.is_empty() {
+                    ui.label("NymView has nothing stored.");
+                    return;
+                }
+
+                ui.label("Select what to delete:");
+                let key_selected = dialog.is_selected(Category::EncryptionKey);
+                for (artifact, checked) in &mut dialog.artifacts {
+                    let forced = key_selected && artifact.category.is_encrypted();
+                    let mut shown = *checked || forced;
+                    let files = if artifact.files == 1 { String::new() } else { format!("{} files, ", artifact.files) };
+                    let label = format!("{} ({}{})", artifact.category.label(), files, reset::format_size(artifact.bytes));
+                    let response = ui.add_enabled(!forced, egui::Checkbox::new(&mut shown, label));
+                    if forced {
+                        response.on_disabled_hover_text("Unreadable without the encryption key");
+                    } else {
+                        *checked = shown;
+                    }
+                }
+
+                if dialog.needs_confirmation() {
+                    ui.separator();
+                    if dialog.is_selected(Category::EncryptionKey) {
+                        ui.colored_label(Color32::DARK_RED, "Removing the encryption key deletes all encrypted data.");
+                    }
+                    if dialog.is_selected(Category::ClientIdentity) {
+                        ui.colored_label(Color32::DARK_RED, "Your Nym address changes on the next connection.");
+                    }
+                    ui.label("Type RESET to confirm.");
+                    ui.add(TextEdit::singleline(&mut dialog.confirmation).desired_width(120.0));
+                }
+
+                ui.separator();
+                let ready = !dialog.selected().is_empty() && dialog.is_confirmed();
+                if ui.add_enabled(ready, egui::Button::new("Delete selected")).clicked() {
+                    reset = true;
+                }
+            });
+
+        if reset {
+            self.wait_for_history_write();
+            let summary = reset::wipe(self.storage.as_ref(), &dialog.selected());
+            self.apply_reset(ctx, &summary);
+            dialog.summary = Some(summary.describe());
+        }
+        if open {
+            self.reset_dialog = Some(dialog);
+        }
+    }
+
+    // Brings the in-memory state of the removed categories back to what a
+    // first run would have. The connection is left as it is.
+    fn apply_reset(&mut self, ctx: &egui::Context, summary: &ResetSummary) {
+        for category in &summary.removed {
+            match category {
+                Category::Settings => {
+                    let offline = self.settings.offline_mode;
+                    self.settings = Settings { offline_mode: offline, ..Settings::default() };
+                    self.stripped_params_input = self.settings.stripped_query_params.join(", ");
+                    self.window_geometry = None;
+                    self.apply_motion_preference(ctx);
+                    self.apply_content_font(ctx);
+                    self.load_theme();
+                }
+                Category::WelcomePage => self.welcome_content = DEFAULT_WELCOME_PAGE.to_string(),
+                Category::GatewayStats => {
+                    self.gateway_stats = GatewayStats::default();
+                    if !self.client_address.is_empty() {
+                        self.gateway_stats.start_session(&self.client_address);
+                    }
+                }
+                Category::Reports => self.sent_reports = SentReports::default(),
+                Category::Bookmarks => self.bookmarks = Bookmarks::default(),
+                Category::History => {
+                    self.history.clear();
+                    self.current_history_index = 0;
+                    self.history_dirty = false;
+                }
+                Category::Diagnostics => self.diagnostics_path = None,
+                // A recording in progress stops too, rather than start the
+                // file again with its next reply
+                Category::Recordings => {
+                    if self.settings.record_session {
+                        self.settings.record_session = false;
+                        if let Err(e) = self.settings.save(self.storage.as_ref()) {
+                            eprintln!("Could not save settings: {}", e);
+                        }
+                    }
+                    let recording = matches!(&self.session_mode, SessionMode::Record(file) if *file == replay::default_file());
+                    if recording && !self.offline {
+                        self.reconnect();
+                    }
+                }
+                // The connected client keeps its keys in memory until it reconnects
+                Category::ClientIdentity => {}
+                Category::EncryptionKey => {
+                    if self.storage.is_encrypted() {
+                        self.storage = Arc::new(FileStorage::default());
+                    }
+                }
+            }
+        }
+    }
+
+    // Writes everything kept between runs to `storage`: every document
+    // `from` holds is read back and written again, so it ends up
+    // encoded the way `storage` writes. Stops at the first failure, before
+    // the caller gets to drop the old key.
+    fn persist_to(&self, from: &dyn Storage, storage: &dyn Storage) -> Result<(), String> {
+        self.wait_for_history_write();
+        for category in Category::ALL.into_iter().filter(|category| category.is_encrypted()) {
+            for name in category.documents() {
+                if let Some(content) = from.read(name) {
+                    storage.write(name, &content)?;
+                }
+            }
+        }
+        // What is in memory may be newer than the last write, e.g. after a
+        // failed save
+        self.settings.save(storage)?;
+        self.bookmarks.save(storage)?;
+        SavedHistory::new(&self.history, self.settings.history_limit, self.settings.history_keep_pages).save(storage)?;
+        self.gateway_stats.save(storage)
+    }
+
+    fn show_bookmarks_window(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut action = None;
+
+        egui::Window::new("Bookmarks")
+            .open(&mut open)
+            .collapsible(false)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                if self.bookmarks.bookmarks.is_empty() {
+                    ui.label("No bookmarks yet. Use ☆ in the toolbar to add the current page.");
+                    return;
+                }
+                let mut folders = vec![String::new()];
+                folders.extend(self.bookmarks.folders());
+                for folder in folders {
+                    let indices: Vec<usize> = self.bookmarks.bookmarks
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, bookmark)| bookmark.folder == folder)
+                        .map(|(index, _)| index)
+                        .collect();
+                    if folder.is_empty() {
+                        for index in indices {
+                            self.show_bookmark_row(ui, index, &mut action);
+                        }
+                    } else {
+                        egui::CollapsingHeader::new(format!("📁 {}", folder))
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                for index in indices {
+                                    self.show_bookmark_row(ui, index, &mut action);
+                                }
+                            });
+                    }
+                }
+            });
+
+        match action {
+            Some(BookmarkAction::Open(url)) => {
+                // Stored exactly as it was visited
+                self.keep_query_once = true;
+                self.pending_navigation = Some(url);
+            }
+            Some(BookmarkAction::Delete(index)) => self.remove_bookmark(index),
+            Some(BookmarkAction::Save(index, title, folder)) => {
+                if let Some(bookmark) = self.bookmarks.bookmarks.get_mut(index) {
+                    bookmark.title = title.trim().to_string();
+                    bookmark.folder = folder.trim().to_string();
+                }
+                self.bookmark_editing = None;
+                self.save_bookmarks();
+            }
+            None => {}
+        }
+        if !open {
+            self.bookmark_editing = None;
+        }
+        self.show_bookmarks = open;
+    }
+
+    fn show_bookmark_row(&mut self, ui: &mut Ui, index: usize, action: &mut Option<BookmarkAction>) {
+        let bookmark = &self.bookmarks.bookmarks[index];
+        if let Some((editing, title, folder)) = &mut self.bookmark_editing {
+            if *editing == index {
+                let mut cancel = false;
+                ui.horizontal(|ui| {
+                    ui.add(TextEdit::singleline(title).hint_text("Title").desired_width(160.0));
+                    ui.add(TextEdit::singleline(folder).hint_text("Folder").desired_width(100.0));
+                    if ui.small_button("Save").clicked() && !title.trim().is_empty() {
+                        *action = Some(BookmarkAction::Save(index, title.clone(), folder.clone()));
+                    }
+                    cancel = ui.small_button("Cancel").clicked();
+                });
+                if cancel {
+                    self.bookmark_editing = None;
+                }
+                return;
+            }
+        }
+        ui.horizontal(|ui| {
+            if ui.link(&bookmark.title).on_hover_text(&bookmark.url).clicked() {
+                *action = Some(BookmarkAction::Open(bookmark.url.clone()));
+            }
+            if ui.small_button("✏").on_hover_text("Rename or move to a folder").clicked() {
+                self.bookmark_editing = Some((index, bookmark.title.clone(), bookmark.folder.clone()));
+            }
+            if ui.small_button("✖").on_hover_text("Delete bookmark").clicked() {
+                *action = Some(BookmarkAction::Delete(index));
+            }
+        });
+    }
+
+    fn remove_bookmark(&mut self, index: usize) {
+        if index >= self.bookmarks.bookmarks.len() {
+            return;
+        }
+        let bookmark = self.bookmarks.bookmarks.remove(index);
+        self.undo.push(Removed::Bookmark { index, bookmark });
+        self.bookmark_editing = None;
+        self.save_bookmarks();
+    }
+
+    fn save_bookmarks(&mut self) {
+        if let Err(e) = self.bookmarks.save(self.storage.as_ref()) {
+            self.error = Some(e);
+        }
+    }
+
+    // Runs the startup self-check off the UI thread; a check stuck on a
+    // slow disk is reported as timed out instead of hanging the report
+    fn start_health_check(&mut self) {
+        if self.health_checking {
+            return;
+        }
+        let Some(sender) = self.message_sender.clone() else {
+            return;
+        };
+        self.health_checking = true;
+        self.health = None;
+        let storage = self.storage.clone();
+        RUNTIME.spawn(async move {
+            let check = tokio::task::spawn_blocking(move || health::run(storage.as_ref()));
+            let report = match tokio::time::timeout(health::TIME_LIMIT, check).await {
+                Ok(Ok(report)) => report,
+                _ => HealthReport::timed_out(),
+            };
+            let _ = sender.send(BrowserMessage::HealthChecked { report });
+        });
+    }
+
+    fn show_health_window(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut fix = None;
+        let mut check_again = false;
+
+        egui::Window::new("Self-check")
+            .open(&mut open)
+            .collapsible(false)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                let Some(report) = &self.health else {
+                    indicators::busy(ui, self.settings.reduce_motion);
+                    return;
+                };
+                for check in &report.checks {
+                    ui.horizontal(|ui| {
+                        match check.status {
+                            HealthStatus::Ok => ui.colored_label(Color32::DARK_GREEN, "✔"),
+                            HealthStatus::Degraded => ui.colored_label(Color32::from_rgb(200, 150, 0), "⚠"),
+                        };
+                        ui.strong(check.name);
+                    });
+                    ui.label(&check.explanation);
+                    if let Some(check_fix) = &check.fix {
+                        if ui.button(check_fix.label()).clicked() {
+                            fix = Some(check_fix.clone());
+                        }
+                    }
+                    ui.add_space(4.0);
+                }
+                ui.separator();
+                if ui.button("Check again").clicked() {
+                    check_again = true;
+                }
+            });
+
+        if let Some(fix) = fix {
+            if let Err(e) = fix.apply(self.storage.as_ref(), &mut self.settings) {
+                self.error = Some(e);
+            }
+            // The address only changes on a new connection
+            if fix == Fix::UseEphemeralIdentity && !self.offline {
+                self.reconnect();
+            }
+            check_again = true;
+        }
+        if check_again {
+            self.start_health_check();
+        }
+        self.show_health = open;
+    }
+
+    fn export_diagnostics(&mut self, ctx: &egui::Context) {
+        let report = match diagnostics::build_report(&self.settings, &self.metrics, &self.connection_status) {
+            Ok(report) => report,
+            Err(e) => {
+                self.error = Some(e);
+                return;
+            }
+        };
+        let Some(sender) = self.message_sender.clone() else {
+            return;
+        };
+        self.exporting_diagnostics = true;
+        let ctx = ctx.clone();
+        RUNTIME.spawn_blocking(move || {
+            let result = diagnostics::write_report(&report);
+            let _ = sender.send(BrowserMessage::DiagnosticsExported { result });
+            ctx.request_repaint();
+        });
+    }
+
+    // Remembers the offset of the page being left and decides where the
+    // new content starts according to the scroll policy
+    fn update_scroll_for_new_content(&mut self) {
+        self.scroll_memory.remember(&self.scroll_page, self.scroll_offset);
+        self.scroll_page = self.current_url();
+
+        let restored = if std::mem::take(&mut self.restore_scroll) {
+            let recent = Duration::from_secs(self.settings.scroll_restore_minutes * 60);
+            self.scroll_memory.offset_for(&self.scroll_page, self.settings.scroll_policy, recent)
+        } else {
+            None
+        };
+        self.pending_scroll = Some(restored.unwrap_or(0.0));
+        self.scroll_offset = restored.unwrap_or(0.0);
+        self.scroll_restored_at = restored
+            .filter(|offset| *offset > FAR_SCROLL_OFFSET)
+            .map(|_| Instant::now());
+    }
+
+    fn show_reading_progress<R>(&self, ui: &Ui, scroll_output: &egui::scroll_area::ScrollAreaOutput<R>) {
+        let viewport = scroll_output.inner_rect;
+        let scrollable = scroll_output.content_size.y - viewport.height();
+        if scrollable <= 0.0 || self.current_content.is_empty() {
+            return;
+        }
+
+        let progress = (scroll_output.state.offset.y / scrollable).clamp(0.0, 1.0);
+        let bar = egui::Rect::from_min_size(
+            viewport.left_top(),
+            egui::vec2(viewport.width() * progress, 3.0),
+        );
+        let painter = ui.painter_at(viewport);
+        painter.rect_filled(bar, 0.0, ui.visuals().selection.bg_fill);
+        if self.settings.reading_progress_percent {
+            painter.text(
+                viewport.right_top() + egui::vec2(-16.0, 6.0),
+                egui::Align2::RIGHT_TOP,
+                format!("{:.0}%", progress * 100.0),
+                egui::FontId::proportional(11.0),
+                ui.visuals().weak_text_color(),
+            );
+        }
+    }
+
+    // Stand-in for pages without renderable content; keeps the URL visible
+    // so it is clear navigation did happen
+    fn show_placeholder_page(&mut self, ui: &mut Ui, heading: &str, message: &str) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(40.0);
+            ui.heading(heading);
+            ui.label(message);
+            ui.weak(self.current_url());
+            if ui.button("🔄 Reload").clicked() {
+                self.reload_current_page(false);
+            }
+        });
+    }
+
+    // Offered in place of a page for content NymView cannot display
+    fn show_received_file_page(&mut self, ui: &mut Ui) {
+        let Some(file) = &self.received_file else {
+            return;
+        };
+        let file_name = downloads::file_name_for(&file.url, &file.data.content_type);
+        let details = format!("{}, {}", file.data.content_type, reset::format_size(file.data.bytes.len() as u64));
+        let mut save = false;
+        ui.vertical_centered(|ui| {
+            ui.add_space(40.0);
+            ui.heading(format!("📄 {}", file_name));
+            ui.label(details);
+            ui.weak("This is not a page NymView can display, but it can be saved.");
+            save = ui.button("💾 Save to Downloads")
+                .on_hover_text(downloads::downloads_dir().display().to_string())
+                .clicked();
+        });
+        if save {
+            self.start_download(ui.ctx());
+        }
+    }
+
+    // Saving runs on the runtime's blocking pool, so navigation carries on
+    // meanwhile; the outcome comes back as a BrowserMessage
+    fn start_download(&mut self, ctx: &egui::Context) {
+        let Some(sender) = self.message_sender.clone() else {
+            return;
+        };
+        let Some(ReceivedFile { url, data }) = self.received_file.take() else {
+            return;
+        };
+        let id = self.next_download_id;
+        self.next_download_id += 1;
+        let file_name = downloads::file_name_for(&url, &data.content_type);
+        self.downloads.push(Download {
+            id,
+            url,
+            file_name: file_name.clone(),
+            content_type: data.content_type,
+            size: data.bytes.len() as u64,
+            state: DownloadState::Saving,
+        });
+        self.show_downloads = true;
+
+        let dir = downloads::downloads_dir();
+        let ctx = ctx.clone();
+        RUNTIME.spawn_blocking(move || {
+            let result = downloads::save(&dir, &file_name, &data.bytes);
+            let _ = sender.send(BrowserMessage::DownloadFinished { id, result });
+            ctx.request_repaint();
+        });
+    }
+
+    fn show_downloads_window(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut clear = false;
+        let mut open_folder = None;
+
+        egui::Window::new("Downloads")
+            .open(&mut open)
+            .collapsible(false)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                if self.downloads.is_empty() {
+                    ui.label("Nothing downloaded yet. Files a server sends instead of a page can be saved from where the page would be.");
+                    return;
+                }
+                for download in self.downloads.iter().rev() {
+                    ui.horizontal(|ui| {
+                        ui.strong(&download.file_name);
+                        ui.weak(format!("{}, {}", download.content_type, reset::format_size(download.size)));
+                    })
+                    .response
+                    .on_hover_text(&download.url);
+                    match &download.state {
+                        DownloadState::Saving => {
+                            ui.horizontal(|ui| {
+                                indicators::busy(ui, self.settings.reduce_motion);
+                                ui.label("Saving…");
+                            });
+                        }
+                        DownloadState::Done(path) => {
+                            ui.horizontal(|ui| {
+                                ui.colored_label(Color32::DARK_GREEN, "✔ Saved");
+                                if ui.small_button("📂 Open folder").on_hover_text(path.display().to_string()).clicked() {
+                                    open_folder = Some(path.clone());
+                                }
+                            });
+                        }
+                        DownloadState::Failed(e) => {
+                            ui.colored_label(Color32::DARK_RED, format!("✖ {}", e));
+                        }
+                    }
+                    ui.separator();
+                }
+                if self.downloads.iter().any(Download::is_finished) && ui.button("Clear finished").clicked() {
+                    clear = true;
+                }
+            });
+
+        if let Some(path) = open_folder {
+            if let Err(e) = Self::open_with_system(path.parent().unwrap_or(&path)) {
+                self.error = Some(format!("Could not open folder: {}", e));
+            }
+        }
+        if clear {
+            self.downloads.retain(|download| !download.is_finished());
+        }
+        self.show_downloads = open;
+    }
+
+    // Generated page for an error status. The server's message is shown as
+    // plain text, never rendered as markdown.
+    fn show_server_error_page(&mut self, ui: &mut Ui, status: ErrorStatus) {
+        let (heading, explanation) = match status {
+            ErrorStatus::NotFound => ("Page not found", "The server has no page at this address."),
+            ErrorStatus::TooLarge => ("Too large to send", "The server will not send this over the Mixnet."),
+            ErrorStatus::Redirect => (
+                "Too many redirects",
+                "The server kept sending NymView elsewhere. The last redirect pointed to:",
+            ),
+            ErrorStatus::ServerError => (
+                "The server ran into an error",
+                "The request arrived, but the server could not answer it.",
+            ),
+        };
+        let message = self.current_content.trim().to_string();
+        ui.vertical_centered(|ui| {
+            ui.add_space(40.0);
+            ui.heading(heading);
+            ui.label(explanation);
+            if !message.is_empty() {
+                ui.label(egui::RichText::new(message).monospace());
+            }
+            ui.weak(format!("{} answered {}", self.current_url(), status.code()));
+            if ui.button("🔄 Retry").clicked() {
+                self.reload_current_page(true);
+            }
+        });
+    }
+
+    // Generated page for requests the Mixnet did not get an answer for
+    fn show_transport_error_page(&mut self, ui: &mut Ui, error: &str) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(40.0);
+            ui.heading("The page could not be fetched");
+            ui.label(error);
+            ui.weak(self.current_url());
+            ui.weak("No answer came back through the Mixnet; the server may never have seen the request.");
+            if ui.button("🔄 Retry").clicked() {
+                let page = self.address_bar.clone();
+                self.navigate_to(&page);
+            }
+        });
+    }
+
+    fn show_source_badge(&mut self, ui: &mut Ui) {
+        let fetched = self.page_fetched_at
+            .map(|fetched_at| format!("fetched {}", timefmt::relative(fetched_at)))
+            .unwrap_or_else(|| "never fetched".to_string());
+        let (text, color, explanation) = match self.page_source {
+            PageSource::Live => (
+                "live",
+                Color32::DARK_GREEN,
+                "Fetched from the server over the Mixnet",
+            ),
+            PageSource::History => (
+                "history snapshot",
+                Color32::from_rgb(200, 150, 0),
+                "Shown from history without asking the server again",
+            ),
+        };
+
+        let badge = ui.add(egui::Label::new(egui::RichText::new(text).small().color(color))
+            .sense(egui::Sense::click()))
+            .on_hover_text(format!(
+                "{}, {}{}.\nClick to fetch the live version.",
+                explanation,
+                fetched,
+                self.page_fetched_at
+                    .map(|fetched_at| format!(" ({})", timefmt::details(fetched_at)))
+                    .unwrap_or_default(),
+            ));
+        if badge.clicked() {
+            self.reload_current_page(true);
+        }
+        ui.weak(fetched);
+        ui.ctx().request_repaint_after(Duration::from_secs(30));
+    }
+
+    fn log_render_failure(content: &str, reason: &str) {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        content.hash(&mut hasher);
+        eprintln!(
+            "Render fallback ({}): content hash {:016x}, {} bytes",
+            reason,
+            hasher.finish(),
+            content.len(),
+        );
+    }
+
+    fn show_markdown_content(&mut self, ui: &mut Ui) {
+        if self.render_fallback {
+            ui.colored_label(
+                Color32::from_rgb(200, 150, 0),
+                "This page could not be rendered as Markdown \u{2014} showing raw text",
+            );
+            ui.add(
+                TextEdit::multiline(&mut self.current_content.as_str())
+                    .code_editor()
+                    .desired_width(f32::INFINITY)
+            );
+            return;
+        }
+
+        let available_width = ui.available_width();
+        let width = self.settings.content_max_width
+            .map_or(available_width, |max_width| max_width.min(available_width));
+        let heading_scale = self.settings.heading_scale;
+        let paragraph_spacing = self.settings.paragraph_spacing;
+        let content_family = self.content_font_loaded
+            .then(|| egui::FontFamily::Name(CONTENT_FONT.into()));
+        let theme = self.theme.clone();
+
+        // Centered column of at most `width`
+        ui.horizontal_top(|ui| {
+            ui.add_space(((available_width - width) / 2.0).max(0.0));
+            ui.vertical(|ui| {
+                ui.set_max_width(width);
+                let style = ui.style_mut();
+                style.spacing.item_spacing.y *= paragraph_spacing;
+                for (text_style, font_id) in style.text_styles.iter_mut() {
+                    if *text_style == egui::TextStyle::Heading {
+                        font_id.size *= heading_scale;
+                    }
+                    if let Some(family) = &content_family {
+                        if *text_style != egui::TextStyle::Monospace {
+                            font_id.family = family.clone();
+                        }
+                    }
+                }
+                theme.apply(style);
+
+                // Hooked links are not opened by the viewer; a click only
+                // marks the hook, and is picked up by SegmentView
+                let cache = self.md_caches.get(self.display_key);
+                let mut links = self.page_links.clone();
+                for transclusion in self.transclusions.values() {
+                    if let Transclusion::Loaded(_, nested) = transclusion {
+                        links.extend(nested.iter().filter(|link| !self.page_links.contains(link)).cloned());
+                    }
+                }
+                for link in &links {
+                    cache.add_link_hook(link.as_str());
+                }
+                let server = self.server_address.trim();
+                let mut view = SegmentView {
+                    cache,
+                    server,
+                    transclusions: &self.transclusions,
+                    links: &links,
+                    ancestors: vec![format!("nym://{}{}", server, protocol::normalize_path(&self.address_bar))],
+                    fetch: Vec::new(),
+                    clicked: None,
+                    reduce_motion: self.settings.reduce_motion,
+                };
+                let shown = ui.scope(|ui| view.show(ui, &self.page_segments));
+                let SegmentView { fetch, clicked, .. } = view;
+                if let Some(link) = clicked {
+                    self.pending_navigation = Some(link);
+                }
+                self.start_transclusions(fetch, ui.ctx());
+                // Non-empty input that draws nothing would look like a broken page
+                if shown.response.rect.height() < 1.0 && !self.display_content.trim().is_empty() {
+                    Self::log_render_failure(&self.current_content, "nothing was rendered");
+                    self.render_fallback = true;
+                    ui.ctx().request_repaint();
+                }
+            });
+        });
+    }
+
+    // Fetches transcluded pages in the background; they arrive as
+    // BrowserMessage::Transcluded and fill in their expanders
+    fn start_transclusions(&mut self, urls: Vec<String>, ctx: &egui::Context) {
+        let Some(sender) = self.message_sender.clone() else {
+            return;
+        };
+        for url in urls {
+            let Some(nym_url) = NymUrl::parse(&url) else {
+                continue;
+            };
+            self.transclusions.insert(url.clone(), Transclusion::Loading);
+            let page = self.navigate(&nym_url);
+            let sender = sender.clone();
+            let ctx = ctx.clone();
+            RUNTIME.spawn(async move {
+                let result = page.await.map_err(|e| e.to_string());
+                let _ = sender.send(BrowserMessage::Transcluded { url, result });
+                ctx.request_repaint();
+            });
+        }
+    }
+
+    // Returns true when a persisted setting was modified
+    fn show_appearance_settings(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        // Save sliders once dragging ends, the value itself applies live
+        let slider_changed = |response: egui::Response| {
+            response.drag_stopped() || (response.changed() && !response.dragged())
+        };
+
+        ui.label("Content appearance:");
+        let mut limit_width = self.settings.content_max_width.is_some();
+        if ui.checkbox(&mut limit_width, "Limit content width").changed() {
+            self.settings.content_max_width = limit_width.then_some(800.0);
+            changed = true;
+        }
+        if let Some(max_width) = &mut self.settings.content_max_width {
+            changed |= slider_changed(ui.add(egui::Slider::new(max_width, 400.0..=1600.0).text("Max width")));
+        }
+        changed |= slider_changed(ui.add(
+            egui::Slider::new(&mut self.settings.heading_scale, 0.75..=2.0).text("Heading size")
+        ));
+        changed |= slider_changed(ui.add(
+            egui::Slider::new(&mut self.settings.paragraph_spacing, 0.5..=3.0).text("Paragraph spacing")
+        ));
+
+        if ui.checkbox(&mut self.settings.trim_trailing_whitespace, "Trim trailing whitespace")
+            .on_hover_text("Removes stray hard line breaks; code blocks are kept as sent. Applies to newly loaded pages")
+            .changed() {
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut self.content_font_input)
+                .hint_text("Path to a .ttf/.otf font")
+                .desired_width(200.0));
+            let path = self.content_font_input.trim().to_string();
+            if ui.add_enabled(!path.is_empty(), egui::Button::new("Use font")).clicked() {
+                self.settings.content_font = Some(path);
+                self.apply_content_font(ui.ctx());
+                changed = true;
+            }
+            if self.settings.content_font.is_some() && ui.button("Default font").clicked() {
+                self.settings.content_font = None;
+                self.content_font_input.clear();
+                self.apply_content_font(ui.ctx());
+                changed = true;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Theme:");
+            for (name, _) in theme::BUNDLED {
+                if ui.selectable_label(self.settings.content_theme == name, name).clicked() {
+                    self.settings.content_theme = name.to_string();
+                    self.load_theme();
+                    changed = true;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut self.theme_input)
+                .hint_text("Path to a .toml theme")
+                .desired_width(200.0));
+            let path = self.theme_input.trim().to_string();
+            if ui.add_enabled(!path.is_empty(), egui::Button::new("Use theme"))
+                .on_hover_text("Reloaded automatically when the file changes")
+                .clicked() {
+                self.settings.content_theme = path;
+                self.theme_modified = None;
+                self.load_theme();
+                changed = true;
+            }
+        });
+        if let Some(error) = &self.theme_error {
+            ui.colored_label(Color32::RED, format!("Theme not applied:\n{}", error));
+        }
+
+        changed
+    }
+
+    // Selects the configured theme. A file that fails to load or validate
+    // leaves the previous theme in place.
+    fn load_theme(&mut self) {
+        let selected = self.settings.content_theme.trim().to_string();
+        if let Some(theme) = Theme::bundled(&selected) {
+            self.theme = theme;
+            self.theme_error = None;
+            self.theme_modified = None;
+            return;
+        }
+
+        self.theme_modified = fs::metadata(&selected).and_then(|metadata| metadata.modified()).ok();
+        let loaded = fs::read_to_string(&selected)
+            .map_err(|e| format!("Could not read {}: {}", selected, e))
+            .and_then(|source| Theme::parse(&source));
+        match loaded {
+            Ok(theme) => {
+                self.theme = theme;
+                self.theme_error = None;
+            }
+            Err(e) => self.theme_error = Some(e),
+        }
+    }
+
+    // Live reload for theme files, checked about once a second
+    fn poll_theme_file(&mut self, ctx: &egui::Context) {
+        if Theme::bundled(self.settings.content_theme.trim()).is_some() {
+            return;
+        }
+        if self.theme_checked_at.is_some_and(|checked_at| checked_at.elapsed() < Duration::from_secs(1)) {
+            return;
+        }
+        self.theme_checked_at = Some(Instant::now());
+        let modified = fs::metadata(self.settings.content_theme.trim())
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified != self.theme_modified {
+            self.load_theme();
+        }
+        ctx.request_repaint_after(Duration::from_secs(1));
+    }
+
+    // Registers the user's content font, falling back to egui's fonts
+    fn apply_content_font(&mut self, ctx: &egui::Context) {
+        let mut fonts = egui::FontDefinitions::default();
+        self.content_font_loaded = false;
+
+        if let Some(path) = self.settings.content_font.clone() {
+            self.content_font_input = path.clone();
+            match fs::read(&path) {
+                // egui panics on unparsable font data, so validate it first
+                Ok(bytes) if ab_glyph::FontVec::try_from_vec(bytes.clone()).is_ok() => {
+                    fonts.font_data.insert(
+                        CONTENT_FONT.to_string(),
+                        Arc::new(egui::FontData::from_owned(bytes)),
+                    );
+                    let mut family = vec![CONTENT_FONT.to_string()];
+                    family.extend(fonts.families.get(&egui::FontFamily::Proportional).cloned().unwrap_or_default());
+                    fonts.families.insert(egui::FontFamily::Name(CONTENT_FONT.into()), family);
+                    self.content_font_loaded = true;
+                }
+                Ok(_) => self.error = Some(format!("{} is not a valid TTF/OTF font", path)),
+                Err(e) => self.error = Some(format!("Could not read font {}: {}", path, e)),
+            }
+        }
+
+        ctx.set_fonts(fonts);
+    }
+
+    // Returns true when the filter was modified
+    fn show_server_filter_settings(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        let filter = &mut self.settings.server_filter;
+
+        ui.label("Server access:");
+        ui.horizontal(|ui| {
+            changed |= ui.radio_value(&mut filter.mode, FilterMode::Off, "Allow all").changed();
+            changed |= ui.radio_value(&mut filter.mode, FilterMode::Deny, "Block listed").changed();
+            changed |= ui.radio_value(&mut filter.mode, FilterMode::AllowOnly, "Allow listed only").changed();
+        });
+
+        let current_server = self.server_address.trim().to_string();
+        let mode = filter.mode;
+        let Some(list) = filter.active_list_mut() else {
+            return changed;
+        };
+
+        let mut removed = None;
+        for (index, server) in list.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(server).on_hover_text(server);
+                if ui.small_button("✖").on_hover_text("Remove from list").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed {
+            let server = list.remove(index);
+            self.undo.push(Removed::FilterEntry { mode, index, server });
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut self.new_filter_entry)
+                .hint_text("Server address")
+                .desired_width(200.0));
+            let entry = self.new_filter_entry.trim().to_string();
+            if ui.add_enabled(!entry.is_empty(), egui::Button::new("Add")).clicked() {
+                if !list.contains(&entry) {
+                    list.push(entry);
+                    changed = true;
+                }
+                self.new_filter_entry.clear();
+            }
+            if !current_server.is_empty()
+                && !list.contains(&current_server)
+                && ui.button("Add current server").clicked() {
+                list.push(current_server);
+                changed = true;
+            }
+        });
+
+        changed
+    }
+
+    // Remember the window placement and persist it when the window closes
+    fn track_window_geometry(&mut self, ctx: &egui::Context) {
+        let (outer_rect, inner_rect, maximized, monitor_size, close_requested) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (
+                viewport.outer_rect,
+                viewport.inner_rect,
+                viewport.maximized.unwrap_or(false),
+                viewport.monitor_size,
+                viewport.close_requested(),
+            )
+        });
+
+        if !self.window_placement_checked {
+            if let (Some(outer_rect), Some(inner_rect)) = (outer_rect, inner_rect) {
+                self.window_placement_checked = true;
+                for command in Self::fit_window_to_monitor(outer_rect, inner_rect, monitor_size) {
+                    ctx.send_viewport_cmd(command);
+                }
+            }
+        }
+
+        // Until the reset has reached the window, the viewport still reports
+        // the old placement
+        if let Some(reset_at) = self.window_reset_at {
+            let applied = inner_rect.is_some_and(|rect| {
+                (rect.size() - egui::Vec2::from(settings::DEFAULT_WINDOW_SIZE)).length() < 1.0
+            });
+            if applied || reset_at.elapsed() > WINDOW_RESET_WAIT {
+                self.window_reset_at = None;
+            }
+        }
+
+        if let (Some(outer_rect), Some(inner_rect), None) = (outer_rect, inner_rect, self.window_reset_at) {
+            if maximized {
+                // Keep the restored size so un-maximizing next session still works
+                if let Some(geometry) = &mut self.window_geometry {
+                    geometry.maximized = true;
+                }
+            } else {
+                self.window_geometry = Some(WindowGeometry {
+                    x: outer_rect.min.x,
+                    y: outer_rect.min.y,
+                    width: inner_rect.width(),
+                    height: inner_rect.height(),
+                    maximized: false,
+                });
+            }
+        }
+
+        if close_requested && self.window_geometry.is_some() {
+            self.settings.window = self.window_geometry;
+            if let Err(e) = self.settings.save(self.storage.as_ref()) {
+                eprintln!("{}", e);
+            }
+        }
+        if close_requested {
+            self.flush_gateway_stats(true);
+        }
+        if close_requested && self.history_dirty {
+            self.wait_for_history_write();
+            self.history_dirty = false;
+            let saved = SavedHistory::new(&self.history, self.settings.history_limit, self.settings.history_keep_pages);
+            if let Err(e) = saved.save(self.storage.as_ref()) {
+                eprintln!("{}", e);
+            }
+        }
+    }
+
+    // Commands fitting a restored window to its monitor, e.g. one saved on
+    // a larger display. The size is clamped with the decorations taken off,
+    // as InnerSize sets the inner size. egui reports the size of the
+    // monitor the window is on but not where that monitor is, so a window
+    // on any monitor stays where it was saved. Only one on no monitor at
+    // all, saved on a display since unplugged, is moved to the origin,
+    // which is on the primary monitor.
+    fn fit_window_to_monitor(outer_rect: egui::Rect, inner_rect: egui::Rect, monitor_size: Option<egui::Vec2>) -> Vec<egui::ViewportCommand> {
+        let Some(monitor_size) = monitor_size else {
+            return vec![egui::ViewportCommand::OuterPosition(egui::Pos2::ZERO)];
+        };
+        let outer_size = outer_rect.size().min(monitor_size);
+        if outer_size == outer_rect.size() {
+            return Vec::new();
+        }
+        let decorations = (outer_rect.size() - inner_rect.size()).max(egui::Vec2::ZERO);
+        vec![egui::ViewportCommand::InnerSize((outer_size - decorations).max(egui::Vec2::ZERO))]
+    }
+
+    fn reset_window_layout(&mut self, ctx: &egui::Context) {
+        self.settings.window = None;
+        self.window_geometry = None;
+        self.window_reset_at = Some(Instant::now());
+        ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(settings::DEFAULT_WINDOW_SIZE.into()));
+        if let Some(command) = egui::ViewportCommand::center_on_screen(ctx) {
+            ctx.send_viewport_cmd(command);
+        }
+    }
+
+    fn apply_motion_preference(&self, ctx: &egui::Context) {
+        let animation_time = if self.settings.reduce_motion {
+            0.0
+        } else {
+            egui::Style::default().animation_time
+        };
+        ctx.style_mut(|style| style.animation_time = animation_time);
+    }
+
+    // Handle link clicks - SIMPLIFIED AND STABLE VERSION
+    fn handle_link_click(&mut self, href: &str) {
+        self.show_link_hints = false;
+        let (stripped, removed) = if std::mem::take(&mut self.keep_query_once) {
+            (href.to_string(), 0)
+        } else {
+            address::strip_query_params(href, &self.settings.stripped_query_params)
+        };
+        // Set once the navigation has started, which clears the previous notice
+        if self.open_link(&stripped) && removed > 0 {
+            self.stripped_link = Some((href.to_string(), removed));
+        }
+    }
+
+    // Returns whether a navigation started
+    fn open_link(&mut self, href: &str) -> bool {
+        if href.starts_with("nym://") {
+            let Some(NymUrl { server, page }) = NymUrl::parse(href) else {
+                return false;
+            };
+            // Simple logic: if it looks like a Nym address, treat as external
+            if server.contains('.') && server.contains('@') {
+                // External link
+                let old_server = self.server_address.clone();
+                self.server_address = server.clone();
+                
+                let path = self.request_path(&page);
+                self.address_bar = path.trim_start_matches('/').to_string();
+                if !self.begin_navigation(&path) {
+                    return false;
+                }
+
+                if let Err(e) = self.send_request(&path) {
+                    self.server_address = old_server;
+                    self.error = Some(e);
+                    self.page_loading = false;
+                    self.page_load_start_time = None;
+                } else {
+                    self.add_to_history();
+                }
+                true
+            } else {
+                // Local link
+                if self.server_address.is_empty() {
+                    return false;
+                }
+                let path = protocol::normalize_path(&format!("{}/{}", server, page));
+                if !self.begin_navigation(&path) {
+                    return false;
+                }
+
+                if let Err(e) = self.send_request(&path) {
+                    self.error = Some(e);
+                    self.page_loading = false;
+                    self.page_load_start_time = None;
+                } else {
+                    self.add_to_history();
+                }
+                true
+            }
+        } else if let Some(path) = href.strip_prefix('/') {
+            self.navigate_to(path)
+        } else {
+            self.navigate_to(href)
+        }
+    }
+
+    // The history entry is for the page being opened, so the address bar
+    // is updated before it is added. Returns whether a navigation started.
+    fn navigate_to(&mut self, path: &str) -> bool {
+        let request_path = self.request_path(path);
+        self.address_bar = request_path.trim_start_matches('/').to_string();
+        if !self.begin_navigation(&request_path) {
+            return false;
+        }
+        
+        if let Err(e) = self.send_request(&request_path) {
+            self.error = Some(e);
+            self.page_loading = false;
+            self.page_load_start_time = None;
+        } else {
+            self.add_to_history();
+        }
+        true
+    }
+
+    // Add current page to history
+    fn add_to_history(&mut self) {
+        // Re-requesting the page we are on (double "Go", reload) is not a new entry
+        if let Some(entry) = self.history.get(self.current_history_index) {
+            if *entry.server == *self.server_address && entry.page == self.address_bar {
+                return;
+            }
+        }
+
+        if self.current_history_index < self.history.len().saturating_sub(1) {
+            self.history.truncate(self.current_history_index + 1);
+        }
+        
+        let history_entry = HistoryEntry {
+            server: self.server_ids.intern(&self.server_address),
+            page: self.address_bar.clone(),
+            content: self.current_content.clone(),
+            fetched_at: None,
+            server_error: false,
+            error_code: String::new(),
+            content_dropped: false,
+        };
+        
+        self.history.push(history_entry);
+        self.current_history_index = self.history.len().saturating_sub(1);
+    }
+
+    fn record_gateway_latency(&mut self, latency: Duration) {
+        self.gateway_stats.record(latency);
+        self.gateway_stats_changed.get_or_insert_with(Instant::now);
+
+        // Only worth suggesting when another gateway has done much better
+        let threshold = Duration::from_secs(self.settings.slow_gateway_threshold_secs);
+        self.slow_gateway_warning = self.metrics.recent_median_latency()
+            .is_some_and(|median| median > threshold)
+            && self.gateway_stats.had_session_faster_than(threshold / 2);
+    }
+
+    fn gateway_comparison(&self) -> String {
+        let mut lines = vec!["Average round trip by session:".to_string()];
+        for session in self.gateway_stats.sessions.iter().rev().filter(|session| session.samples > 0).take(5) {
+            lines.push(format!(
+                "{} \u{2014} {:.1} s over {} requests",
+                session.gateway,
+                session.average_ms as f32 / 1000.0,
+                session.samples,
+            ));
+        }
+        lines.join("\n")
+    }
+
+    // Text replies to page requests come prepared by the mixnet task;
+    // untagged ones are prepared here
+    fn handle_server_message(
+        &mut self,
+        request_id: Option<u64>,
+        content_type: &str,
+        data: Vec<u8>,
+        prepared: Option<Box<PreparedReply>>,
+    ) {
+        if self.is_stale_reply(request_id) {
+            eprintln!("Discarding a reply to request {:?}; waiting for {:?}", request_id, self.pending_request_id);
+            return;
+        }
+        // deliver() cannot tell whom an untagged reply is for, so anyone
+        // else waiting for the pending request gets it from here
+        if let (None, Some(pending)) = (request_id, self.pending_request_id) {
+            let waiters = IN_FLIGHT.lock().unwrap().complete(pending).unwrap_or_default();
+            for waiter in waiters {
+                if let Waiter::Awaited(waiter) = waiter {
+                    let _ = waiter.send(BrowserMessage::ReceivedMessage {
+                        request_id: Some(pending),
+                        content_type: content_type.to_string(),
+                        data: data.clone(),
+                        prepared: None,
+                    });
+                }
+            }
+        }
+        let latency = self.page_load_start_time.map(|start_time| start_time.elapsed());
+        self.metrics.record_reply(data.len(), latency);
+        if let Some(latency) = latency {
+            self.record_gateway_latency(latency);
+            if let Some(request) = &self.last_request {
+                self.metrics.record_server_latency(&request.server, latency);
+            }
+        }
+
+        let fetched_at = SystemTime::now();
+        if protocol::is_text_type(content_type) {
+            let PreparedReply { status, page } = match prepared {
+                Some(prepared) => *prepared,
+                None => PreparedReply::new(&data, self.settings.trim_trailing_whitespace, self.server_address.trim()),
+            };
+            match status {
+                ReplyStatus::Ok => self.show_prepared_page(PageSource::Live, page, Some(fetched_at)),
+                ReplyStatus::Error(ErrorStatus::Redirect) if self.redirects_followed < MAX_REDIRECTS => {
+                    self.follow_redirect(page.content);
+                    return;
+                }
+                ReplyStatus::Error(status) => {
+                    self.show_prepared_page(PageSource::Live, page, Some(fetched_at));
+                    self.page_state = PageState::ServerError(status);
+                }
+                ReplyStatus::Unframed => {
+                    self.show_prepared_page(PageSource::Live, page, Some(fetched_at));
+                    if let Some(document) = JsonDocument::detect(content_type, &self.current_content) {
+                        self.json_document = Some(document);
+                        self.page_words = 0;
+                    }
+                }
+            }
+        } else {
+            let notice = Self::binary_notice(content_type, data.len());
+            self.set_page_content(PageSource::Live, notice, Some(fetched_at));
+            self.received_file = Some(ReceivedFile {
+                url: self.current_url(),
+                data: Data::new(content_type, data),
+            });
+        }
+        self.error = None;
+        self.page_loading = false;
+        self.page_load_start_time = None;
+        self.pending_request_id = None;
+        self.redirects_followed = 0;
+        self.focus_content_pending = true;
+
+        // The history entry was created when the request went out; give it
+        // the page that actually arrived
+        if let Some(entry) = self.history.get_mut(self.current_history_index) {
+            if *entry.server == *self.server_address && entry.page == self.address_bar {
+                entry.content = self.current_content.clone();
+                entry.content_dropped = false;
+                entry.fetched_at = Some(fetched_at);
+                (entry.server_error, entry.error_code) = match self.page_state {
+                    PageState::ServerError(status) => (true, status.code().to_string()),
+                    _ => (false, String::new()),
+                };
+                self.save_history();
+            }
+        }
+    }
+
+    // Shown in place of content that arrived intact but is not a page
+    fn binary_notice(content_type: &str, size: usize) -> String {
+        format!(
+            "# Not a page\n\nThe server sent `{}` content ({}), which NymView cannot display. Reload to save it again.",
+            content_type,
+            reset::format_size(size as u64),
+        )
+    }
+
+    // A reply tagged with another request's ID answers a navigation that was
+    // superseded, and with nothing pending the page has already arrived.
+    // Untagged replies come from servers that predate request IDs and are
+    // taken to answer whatever is pending.
+    fn is_stale_reply(&self, request_id: Option<u64>) -> bool {
+        match (self.pending_request_id, request_id) {
+            (None, _) => true,
+            (Some(pending), Some(request_id)) => pending != request_id,
+            (Some(_), None) => false,
+        }
+    }
+
+    // Marks history.json for writing. Writes run on the blocking pool, one
+    // at a time; changes made meanwhile are written together afterwards.
+    fn save_history(&mut self) {
+        self.history_dirty = true;
+    }
+
+    fn flush_history(&mut self, ctx: &egui::Context) {
+        if !self.history_dirty || self.history_saving.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        self.history_dirty = false;
+        let saved = SavedHistory::new(&self.history, self.settings.history_limit, self.settings.history_keep_pages);
+        let storage = self.storage.clone();
+        let saving = self.history_saving.clone();
+        let ctx = ctx.clone();
+        RUNTIME.spawn_blocking(move || {
+            if let Err(e) = saved.save(storage.as_ref()) {
+                eprintln!("{}", e);
+            }
+            saving.store(false, Ordering::Release);
+            // Picks up changes that waited for this write
+            ctx.request_repaint();
+        });
+    }
+
+    // Written at most every GATEWAY_STATS_SAVE_DELAY rather than after
+    // every reply, off the UI thread
+    fn flush_gateway_stats(&mut self, close_requested: bool) {
+        let due = self.gateway_stats_changed
+            .is_some_and(|changed| close_requested || changed.elapsed() > GATEWAY_STATS_SAVE_DELAY);
+        if !due {
+            return;
+        }
+        self.gateway_stats_changed = None;
+        if close_requested {
+            if let Err(e) = self.gateway_stats.save(self.storage.as_ref()) {
+                eprintln!("{}", e);
+            }
+            return;
+        }
+        let stats = self.gateway_stats.clone();
+        let storage = self.storage.clone();
+        RUNTIME.spawn_blocking(move || {
+            if let Err(e) = stats.save(storage.as_ref()) {
+                eprintln!("{}", e);
+            }
+        });
+    }
+
+    // For the few places that must not race a background write: switching
+    // storage, resetting and quitting
+    fn wait_for_history_write(&self) {
+        let started = Instant::now();
+        while self.history_saving.load(Ordering::Acquire) && started.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    // Every change of the displayed page goes through here, so the source
+    // badge always matches what is shown
+    fn set_page_content(&mut self, source: PageSource, content: String, fetched_at: Option<SystemTime>) {
+        let page = PreparedPage::new(content, self.server_address.trim());
+        self.show_prepared_page(source, page, fetched_at);
+    }
+
+    fn show_prepared_page(&mut self, source: PageSource, page: PreparedPage, fetched_at: Option<SystemTime>) {
+        self.page_source = source;
+        self.received_file = None;
+        self.page_fetched_at = fetched_at;
+        self.show_link_hints = false;
+        self.json_document = None;
+        if page.fallback {
+            Self::log_render_failure(&page.content, "markdown preparation panicked");
+        }
+        self.render_fallback = page.fallback;
+        self.display_content = page.display_content;
+        self.display_key = page.display_key;
+        self.page_links = page.links;
+        self.link_hints = self.page_links.iter().take(MAX_LINK_HINTS).cloned().collect();
+        self.page_segments = page.segments;
+        self.transclusions.clear();
+        self.page_words = page.words;
+        self.current_content = page.content;
+        self.page_state = PageState::Loaded;
+        self.update_scroll_for_new_content();
+    }
+
+    // Transport failures are reported as errors, never as page content
+    fn handle_request_failed(&mut self, request_id: u64, error: String) {
+        self.metrics.record_send_failure();
+        if let Some((url, _)) = self.image_requests.remove(&request_id) {
+            self.image_loader.finish(&url, Err(error));
+            return;
+        }
+        if self.report_upload_id == Some(request_id) {
+            self.report_upload_id = None;
+            self.report_upload_status = Some(format!("The image was not sent: {}", error));
+            return;
+        }
+        if self.pending_request_id != Some(request_id) {
+            return;
+        }
+        self.fail_navigation(error);
+    }
+
+    // A REDIRECT is followed like a link to its target, tracking parameters
+    // stripped as usual. One leading to another server waits for the user
+    // to confirm it. The history entry created for the request is dropped,
+    // as only the target was served.
+    fn follow_redirect(&mut self, target: String) {
+        eprintln!("Redirected to {}", target);
+        self.redirects_followed += 1;
+        self.pending_request_id = None;
+        self.page_loading = false;
+        self.page_load_start_time = None;
+        self.drop_unanswered_history_entry();
+        // A redirect back to the same page must not be taken for a double click
+        self.last_navigation = None;
+        let other_server = NymUrl::parse(&target).is_some_and(|url| {
+            url.server.contains('.') && url.server.contains('@') && url.server != self.server_address.trim()
+        });
+        if other_server {
+            self.redirect_to_confirm = Some(target);
+        } else {
+            self.pending_navigation = Some(target);
+        }
+    }
+
+    // Shows the generated error page. The history entry created for the
+    // request is dropped again since nothing was served for it.
+    fn fail_navigation(&mut self, error: String) {
+        // Timed out or cancelled: the same page is asked for anew next time,
+        // unless someone else still waits for this request
+        if let Some(request_id) = self.pending_request_id.take() {
+            IN_FLIGHT.lock().unwrap().detach(request_id, |waiter| matches!(waiter, Waiter::Gui { .. }));
+        }
+        self.page_state = PageState::TransportError(error);
+        self.page_loading = false;
+        self.page_load_start_time = None;
+        self.redirects_followed = 0;
+        self.drop_unanswered_history_entry();
+    }
+
+    fn drop_unanswered_history_entry(&mut self) {
+        let unanswered = self.history.get(self.current_history_index).is_some_and(|entry| {
+            entry.fetched_at.is_none() && *entry.server == *self.server_address && entry.page == self.address_bar
+        });
+        if unanswered {
+            self.history.remove(self.current_history_index);
+            self.current_history_index = self.current_history_index.saturating_sub(1);
+        }
+    }
+
+    fn go_back(&mut self) {
+        if self.current_history_index > 0 {
+            self.current_history_index -= 1;
+            self.show_history_entry();
+        }
+    }
+
+    fn go_forward(&mut self) {
+        if self.current_history_index < self.history.len().saturating_sub(1) {
+            self.current_history_index += 1;
+            self.show_history_entry();
+        }
+    }
+
+    fn show_history_entry(&mut self) {
+        if self.history.get(self.current_history_index).is_none() {
+            return;
+        }
+        // Otherwise the reply to the page being left would replace this one
+        self.abandon_pending_request();
+        let entry = &self.history[self.current_history_index];
+        self.server_address = entry.server.to_string();
+        self.address_bar = entry.page.clone();
+        self.stripped_link = None;
+        if entry.content_dropped {
+            self.reload_current_page(false);
+            if let Some(error) = self.error.take() {
+                self.page_state = PageState::TransportError(error);
+            }
+            return;
+        }
+        let content = entry.content.clone();
+        let fetched_at = entry.fetched_at;
+        let error_status = entry.server_error
+            .then(|| ErrorStatus::from_code(&entry.error_code).unwrap_or(ErrorStatus::ServerError));
+        self.restore_scroll = true;
+        self.set_page_content(PageSource::History, content, fetched_at);
+        if let Some(status) = error_status {
+            self.page_state = PageState::ServerError(status);
+        }
+        self.error = None;
+        self.page_loading = false;
+        self.page_load_start_time = None;
+    }
+
+    fn reload_current_page(&mut self, hard_reload: bool) {
+        if !self.server_address.is_empty() {
+            self.image_loader.retry_failed();
+            self.page_loading = true;
+            self.page_load_start_time = Some(Instant::now());
+            
+            let path = self.request_path(&self.address_bar);
+
+            if let Err(e) = self.send_request_with(&path, !hard_reload) {
+                self.error = Some(e);
+                self.page_loading = false;
+                self.page_load_start_time = None;
+            } else {
+                self.restore_scroll = true;
+            }
+        } else if self.page_state == PageState::None {
+            // Nothing loaded yet: pick up edits to the welcome page
+            self.welcome_content = Self::load_welcome_page(self.storage.as_ref());
+        }
+    }
+
+    // User supplied welcome page, falling back to the built-in one
+    fn load_welcome_page(storage: &dyn Storage) -> String {
+        storage.read(WELCOME_FILE)
+            .unwrap_or_else(|| DEFAULT_WELCOME_PAGE.to_string())
+    }
+
+    fn edit_welcome_page(&mut self) {
+        let Some(path) = self.storage.path(WELCOME_FILE) else {
+            self.error = Some("The welcome page can only be edited when settings are stored on disk".to_string());
+            return;
+        };
+        if self.storage.read(WELCOME_FILE).is_none() {
+            if let Err(e) = self.storage.write(WELCOME_FILE, DEFAULT_WELCOME_PAGE) {
+                self.error = Some(e);
+                return;
+            }
+        }
+
+        if let Err(e) = Self::open_with_system(&path) {
+            self.error = Some(format!("Could not open {}: {}", path.display(), e));
+        }
+    }
+
+    fn open_with_system(path: &Path) -> std::io::Result<()> {
+        #[cfg(target_os = "windows")]
+        let mut command = {
+            let mut command = Command::new("cmd");
+            command.args(["/C", "start", ""]);
+            command
+        };
+        #[cfg(target_os = "macos")]
+        let mut command = Command::new("open");
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        let mut command = Command::new("xdg-open");
+
+        command.arg(path).spawn().map(|_| ())
+    }
+
+    fn show_welcome_page(&mut self, ui: &mut Ui) {
+        ui.vertical_centered(|ui| {
+            ui.heading("NymView for Nym Mixnet");
+            ui.label("Welcome! Enter a nym:// address to begin.");
+            if ui.small_button("Edit welcome page")
+                .on_hover_text("Open welcome.md in your editor, then press 🔄 to reload")
+                .clicked() {
+                self.edit_welcome_page();
+            }
+            ui.separator();
+            
+            CommonMarkViewer::new()
+                .show(ui, &mut self.welcome_md_cache, &self.welcome_content);
+        });
+    }
+}
+
+// Draws a page cut at its transclusions, each one an expander that is
+// fetched when first opened
+struct SegmentView<'a> {
+    cache: &'a mut CommonMarkCache,
+    server: &'a str,
+    transclusions: &'a HashMap<String, Transclusion>,
+    // Hooked in the cache, checked after each piece is drawn
+    links: &'a [String],
+    // The page and the transclusions around what is being drawn
+    ancestors: Vec<String>,
+    // Opened expanders with nothing fetched yet, and retries
+    fetch: Vec<String>,
+    clicked: Option<String>,
+    reduce_motion: bool,
+}
+
+impl SegmentView<'_> {
+    fn show(&mut self, ui: &mut Ui, segments: &[markdown::Segment]) {
+        for segment in segments {
+            match segment {
+                // Image URLs are absolute after markdown::resolve_image_urls
+                markdown::Segment::Markdown(text) => self.show_markdown(ui, text),
+                markdown::Segment::Transclusion { label, target } => self.show_transclusion(ui, label, target),
+            }
+        }
+    }
+
+    fn show_markdown(&mut self, ui: &mut Ui, text: &str) {
+        CommonMarkViewer::new()
+            .explicit_image_uri_scheme(true)
+            .show(ui, self.cache, text);
+        // Hooks are reset each time the viewer runs, so look right away
+        if let Some(link) = self.links.iter().find(|link| self.cache.get_link_hook(link) == Some(true)) {
+            self.clicked = Some(link.clone());
+        }
+    }
+
+    fn show_transclusion(&mut self, ui: &mut Ui, label: &str, target: &str) {
+        let url = format!("nym://{}{}", self.server, protocol::normalize_path(target));
+        if self.ancestors.contains(&url) {
+            ui.weak(format!("\u{21bb} {} is already shown above", label));
+            return;
+        }
+        if self.ancestors.len() > MAX_TRANSCLUSION_DEPTH {
+            self.show_markdown(ui, &markdown::link_to(label, target));
+            return;
+        }
+        let transclusions = self.transclusions;
+        let expander = egui::CollapsingHeader::new(label)
+            .id_salt(("transclusion", &url))
+            .show(ui, |ui| match transclusions.get(&url) {
+                None | Some(Transclusion::Loading) => {
+                    ui.horizontal(|ui| {
+                        indicators::busy(ui, self.reduce_motion);
+                        ui.label("Loading\u{2026}");
+                    });
+                }
+                Some(Transclusion::Failed(e)) => {
+                    ui.colored_label(Color32::RED, e);
+                    if ui.button("Retry").clicked() {
+                        self.fetch.push(url.clone());
+                    }
+                }
+                Some(Transclusion::Loaded(segments, _)) => {
+                    self.ancestors.push(url.clone());
+                    self.show(ui, segments);
+                    self.ancestors.pop();
+                }
+            });
+        if expander.body_returned.is_some() && !transclusions.contains_key(&url) {
+            self.fetch.push(url);
+        }
+    }
+}
+
+// App Trait Implementation for eframe
+impl App for NymMixnetBrowser {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.track_window_geometry(ctx);
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.show(ui);
+        });
+    }
+}
+
+impl Clone for NymMixnetBrowser {
+    fn clone(&self) -> Self {
+        Self {
+            address_bar: self.address_bar.clone(),
+            current_content: self.current_content.clone(),
+            loading: self.loading,
+            page_loading: self.page_loading,
+            error: self.error.clone(),
+            connection_status: self.connection_status.clone(),
+            server_address: self.server_address.clone(),
+            client_address: self.client_address.clone(),
+            message_receiver: None,
+            message_sender: None,
+            history: self.history.clone(),
+            connection_attempted: self.connection_attempted,
+            md_caches: RenderCaches::new(RENDER_CACHE_PAGES),
+            welcome_md_cache: CommonMarkCache::default(),
+            pending_navigation: None,
+            current_history_index: self.current_history_index,
+            page_load_start_time: None,
+            welcome_content: self.welcome_content.clone(),
+            next_request_id: self.next_request_id,
+            pending_request_id: None,
+            settings: self.settings.clone(),
+            show_settings: false,
+            json_document: self.json_document.clone(),
+            address_edited: false,
+            focus_content_pending: false,
+            metrics: self.metrics.clone(),
+            offline: self.offline,
+            address_before_correction: None,
+            external_url: None,
+            pending_template: None,
+            new_template: self.new_template.clone(),
+            window_geometry: self.window_geometry,
+            window_placement_checked: self.window_placement_checked,
+            new_filter_entry: String::new(),
+            last_request: self.last_request.clone(),
+            show_page_info: false,
+            display_content: self.display_content.clone(),
+            content_font_loaded: self.content_font_loaded,
+            content_font_input: self.content_font_input.clone(),
+            render_fallback: self.render_fallback,
+            bandwidth_exhausted_since: self.bandwidth_exhausted_since,
+            scroll_memory: self.scroll_memory.clone(),
+            scroll_offset: self.scroll_offset,
+            scroll_page: self.scroll_page.clone(),
+            restore_scroll: false,
+            pending_scroll: None,
+            scroll_restored_at: None,
+            diagnostics_path: self.diagnostics_path.clone(),
+            exporting_diagnostics: false,
+            page_state: self.page_state.clone(),
+            gateway_stats: self.gateway_stats.clone(),
+            slow_gateway_warning: self.slow_gateway_warning,
+            slow_gateway_dismissed: self.slow_gateway_dismissed,
+            storage: self.storage.clone(),
+            encryption_passphrase: String::new(),
+            encryption_confirm: String::new(),
+            last_navigation: None,
+            page_source: self.page_source,
+            page_fetched_at: self.page_fetched_at,
+            show_link_hints: false,
+            server_ids: self.server_ids.clone(),
+            page_words: self.page_words,
+            stripped_link: None,
+            keep_query_once: false,
+            stripped_params_input: self.stripped_params_input.clone(),
+            server_switcher: None,
+            report_draft: None,
+            sent_reports: self.sent_reports.clone(),
+            report_request_id: None,
+            report_status: None,
+            report_upload_id: None,
+            report_upload_status: None,
+            pasting_image: false,
+            theme: self.theme.clone(),
+            theme_error: self.theme_error.clone(),
+            theme_modified: self.theme_modified,
+            theme_checked_at: None,
+            theme_input: self.theme_input.clone(),
+            resume_check: None,
+            last_frame: None,
+            offline_blocked_url: self.offline_blocked_url.clone(),
+            retry_when_online: false,
+            frame_timings: FrameTimings::default(),
+            reset_dialog: None,
+            request_format_input: String::new(),
+            request_format_server: String::new(),
+            undo: self.undo.clone(),
+            health: self.health.clone(),
+            health_checking: false,
+            show_health: false,
+            show_sent_reports: false,
+            bookmarks: self.bookmarks.clone(),
+            bookmark_editing: None,
+            show_bookmarks: false,
+            image_loader: self.image_loader.clone(),
+            image_requests: self.image_requests.clone(),
+            received_file: None,
+            downloads: self.downloads.clone(),
+            next_download_id: self.next_download_id,
+            show_downloads: false,
+            page_links: self.page_links.clone(),
+            link_hints: self.link_hints.clone(),
+            page_segments: self.page_segments.clone(),
+            transclusions: self.transclusions.clone(),
+            redirects_followed: 0,
+            history_dirty: false,
+            history_saving: self.history_saving.clone(),
+            header_probe: self.header_probe.clone(),
+            probed_servers: self.probed_servers.clone(),
+            blocked_images: self.blocked_images.clone(),
+            image_servers_allowed: self.image_servers_allowed.clone(),
+            redirect_to_confirm: self.redirect_to_confirm.clone(),
+            window_reset_at: self.window_reset_at,
+            display_key: self.display_key,
+            gateway_stats_changed: self.gateway_stats_changed,
+            session_override: self.session_override.clone(),
+            session_mode: self.session_mode.clone(),
+            #[cfg(any(test, feature = "memory-transport"))]
+            memory_responses: self.memory_responses.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn own_requests_are_recognised_by_their_reply_address() {
+        let own = "client.addr@gateway";
+        assert!(NymMixnetBrowser::is_own_request(b"GET / FROM client.addr@gateway", own));
+        assert!(NymMixnetBrowser::is_own_request(b"GET /page FROM client.addr@gateway\nRequest-Id: 4", own));
+        assert!(!NymMixnetBrowser::is_own_request(b"GET / FROM other.addr@gateway", own));
+        assert!(!NymMixnetBrowser::is_own_request(b"OK\n# GET / FROM client.addr@gateway", own));
+        assert!(!NymMixnetBrowser::is_own_request(b"GET /", own));
+        assert!(!NymMixnetBrowser::is_own_request(&[0xff, 0xfe, b'\n'], own));
+    }
+
+    #[test]
+    fn restored_windows_are_fitted_to_the_monitor() {
+        use egui::{pos2, vec2, Rect, ViewportCommand};
+
+        let monitor = Some(vec2(1920.0, 1080.0));
+        // 2 px borders and a 30 px title bar
+        let window = |x: f32, y: f32, width: f32, height: f32, monitor: Option<egui::Vec2>| {
+            let outer = Rect::from_min_size(pos2(x, y), vec2(width + 4.0, height + 32.0));
+            let inner = Rect::from_min_size(pos2(x + 2.0, y + 30.0), vec2(width, height));
+            NymMixnetBrowser::fit_window_to_monitor(outer, inner, monitor)
+        };
+
+        assert_eq!(window(100.0, 50.0, 1024.0, 768.0, monitor), vec![]);
+        // On a second monitor to the right or left of the primary one
+        assert_eq!(window(2500.0, 100.0, 1024.0, 768.0, monitor), vec![]);
+        assert_eq!(window(-1100.0, 100.0, 1024.0, 768.0, monitor), vec![]);
+        // Saved on a larger display: the outer size now fills the monitor,
+        // wherever that is
+        let fitted = ViewportCommand::InnerSize(vec2(1916.0, 1048.0));
+        assert_eq!(window(0.0, 0.0, 2556.0, 1408.0, monitor), vec![fitted.clone()]);
+        assert_eq!(window(3000.0, 0.0, 2556.0, 1408.0, monitor), vec![fitted]);
+        // Saved on a display that is gone
+        assert_eq!(
+            window(5000.0, 100.0, 1024.0, 768.0, None),
+            vec![ViewportCommand::OuterPosition(pos2(0.0, 0.0))],
+        );
+    }
+}
+
+// Drive the whole browser through egui frames, without a mixnet
+#[cfg(test)]
+mod ui_tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use std::sync::MutexGuard;
+
+    const SERVER: &str = "server.addr@gateway";
+    const CLIENT: &str = "memory-client";
+
+    // The mixnet task is reached through a global sender, so browsers
+    // under test take turns
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    // Drives a browser connected to an in-memory server through headless
+    // egui frames. Nothing touches the network or the disk.
+    struct Harness {
+        ctx: egui::Context,
+        browser: NymMixnetBrowser,
+        responses: MemoryResponses,
+        _serial: MutexGuard<'static, ()>,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+            let responses = MemoryResponses::new();
+            let mut browser = NymMixnetBrowser::with_storage(Arc::new(MemoryStorage::default()));
+            browser.use_memory_transport(responses.clone());
+            let mut harness = Self {
+                ctx: egui::Context::default(),
+                browser,
+                responses,
+                _serial: serial,
+            };
+            harness.run_until("the memory transport connects", |browser| !browser.client_address.is_empty());
+            harness
+        }
+
+        fn frame(&mut self, events: Vec<egui::Event>) -> egui::FullOutput {
+            let input = egui::RawInput {
+                screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, settings::DEFAULT_WINDOW_SIZE.into())),
+                events,
+                ..Default::default()
+            };
+            let browser = &mut self.browser;
+            self.ctx.run(input, |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| browser.show(ui));
+            })
+        }
+
+        // Replies arrive from the mixnet task's thread, so this keeps
+        // drawing frames until they have been applied
+        fn run_until(&mut self, what: &str, done: impl Fn(&NymMixnetBrowser) -> bool) {
+            let started = Instant::now();
+            while !done(&self.browser) {
+                assert!(started.elapsed() < Duration::from_secs(5), "timed out waiting until {}", what);
+                self.frame(Vec::new());
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        }
+
+        // Gives requests time to reach the server
+        fn settle(&mut self) {
+            for _ in 0..10 {
+                self.frame(Vec::new());
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        }
+
+        fn rendered_text(&mut self) -> String {
+            let output = self.frame(Vec::new());
+            let mut text = String::new();
+            for clipped in &output.shapes {
+                collect_text(&clipped.shape, &mut text);
+            }
+            text
+        }
+
+        // Types `address` into the address bar and presses Enter
+        fn enter_address(&mut self, address: &str) {
+            self.browser.address_bar.clear();
+            self.ctx.memory_mut(|memory| memory.request_focus(egui::Id::new("address_bar")));
+            self.frame(Vec::new());
+            self.frame(vec![egui::Event::Text(address.to_string())]);
+            self.frame(vec![key_press(egui::Key::Enter)]);
+        }
+
+        fn load(&mut self, address: &str) {
+            self.enter_address(address);
+            self.run_until("the page arrives", |browser| !browser.page_loading);
+        }
+
+        // Clicks the middle of the text `label` as drawn in the last frame
+        fn click(&mut self, label: &str) {
+            let output = self.frame(Vec::new());
+            let pos = output.shapes
+                .iter()
+                .find_map(|clipped| text_position(&clipped.shape, label))
+                .unwrap_or_else(|| panic!("no {:?} on screen", label));
+            let button = |pressed| egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Primary,
+                pressed,
+                modifiers: egui::Modifiers::NONE,
+            };
+            self.frame(vec![egui::Event::PointerMoved(pos)]);
+            self.frame(vec![button(true)]);
+            self.frame(vec![button(false)]);
+        }
+
+        // Request lines of the page requests the server received
+        fn page_requests(&self) -> Vec<String> {
+            self.responses
+                .received()
+                .iter()
+                .filter(|request| request.starts_with("GET "))
+                .map(|request| request.lines().next().unwrap_or("").to_string())
+                .collect()
+        }
+    }
+
+    fn key_press(key: egui::Key) -> egui::Event {
+        egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::NONE,
+        }
+    }
+
+    fn collect_text(shape: &egui::Shape, text: &mut String) {
+        match shape {
+            egui::Shape::Text(shape) => {
+                text.push_str(shape.galley.text());
+                text.push('\n');
+            }
+            egui::Shape::Vec(shapes) => {
+                for shape in shapes {
+                    collect_text(shape, text);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn text_position(shape: &egui::Shape, label: &str) -> Option<egui::Pos2> {
+        match shape {
+            egui::Shape::Text(shape) if shape.galley.text() == label => {
+                Some(shape.pos + shape.galley.rect.center().to_vec2())
+            }
+            egui::Shape::Vec(shapes) => shapes.iter().find_map(|shape| text_position(shape, label)),
+            _ => None,
+        }
+    }
+
+    fn url(page: &str) -> NymUrl {
+        NymUrl { server: SERVER.to_string(), page: page.to_string() }
+    }
+
+    #[test]
+    fn entering_an_address_sends_its_request_line() {
+        let mut harness = Harness::new();
+        harness.responses.insert("/about", "About this server");
+        harness.enter_address(&format!("nym://{}/about", SERVER));
+
+        assert!(harness.browser.page_loading);
+        harness.run_until("the page arrives", |browser| !browser.page_loading);
+        assert_eq!(harness.page_requests(), vec![format!("GET /about FROM {}", CLIENT)]);
+    }
+
+    #[test]
+    fn an_answered_request_renders_the_page_and_grows_history() {
+        let mut harness = Harness::new();
+        harness.responses.insert("/about", "# About\n\nHello from memory");
+        let history_before = harness.browser.history.len();
+
+        harness.load(&format!("nym://{}/about", SERVER));
+
+        assert_eq!(harness.browser.page_state, PageState::Loaded);
+        assert_eq!(harness.browser.current_content, "# About\n\nHello from memory");
+        assert_eq!(harness.browser.history.len(), history_before + 1);
+        assert!(harness.rendered_text().contains("Hello from memory"));
+    }
+
+    #[test]
+    fn a_request_without_an_answer_times_out_to_an_error_page() {
+        let mut harness = Harness::new();
+        harness.responses.silence("/slow");
+        harness.enter_address(&format!("nym://{}/slow", SERVER));
+        assert!(harness.browser.page_loading);
+        assert!(harness.browser.pending_request_id.is_some());
+
+        // As if the request had been waiting all along
+        harness.browser.page_load_start_time = Instant::now().checked_sub(PAGE_LOAD_TIMEOUT + Duration::from_secs(1));
+        harness.frame(Vec::new());
+
+        assert!(!harness.browser.page_loading);
+        assert_eq!(harness.browser.pending_request_id, None);
+        assert!(matches!(harness.browser.page_state, PageState::TransportError(_)));
+        assert!(harness.rendered_text().contains("The page could not be fetched"));
+        // Nothing was served, so there is nothing to go back to
+        assert!(harness.browser.history.iter().all(|entry| entry.page != "slow"));
+    }
+
+    #[test]
+    fn a_failed_send_sets_the_error_not_the_content() {
+        let mut harness = Harness::new();
+        let content_before = harness.browser.current_content.clone();
+        harness.responses.fail_sends(vec![SendFailure::NotConnected("gateway went away".to_string())]);
+
+        harness.load(&format!("nym://{}/about", SERVER));
+
+        match &harness.browser.page_state {
+            PageState::TransportError(error) => assert!(error.contains("gateway went away"), "{}", error),
+            state => panic!("expected a transport error, got {:?}", state),
+        }
+        assert_eq!(harness.browser.current_content, content_before);
+        assert_eq!(harness.browser.pending_request_id, None);
+        assert!(harness.page_requests().is_empty());
+    }
+
+    #[test]
+    fn navigation_waits_for_the_sender_not_just_the_status() {
+        let mut harness = Harness::new();
+        assert!(harness.browser.is_mixnet_ready());
+
+        // "Connected" without a sender to the mixnet task
+        if let Some(sender) = GUI_TO_MIXNET_SENDER.get() {
+            sender.lock().unwrap().take();
+        }
+        harness.browser.loading = false;
+        assert!(!harness.browser.is_mixnet_ready());
+
+        // A sender without a finished connection
+        harness.browser.go_online();
+        harness.browser.loading = true;
+        assert!(NymMixnetBrowser::get_gui_sender().is_some());
+        assert!(!harness.browser.is_mixnet_ready());
+    }
+
+    #[test]
+    fn a_bare_server_address_opens_its_landing_path() {
+        let mut harness = Harness::new();
+        harness.responses.insert("/home", "Home page");
+        harness.browser.settings.landing_paths.insert(SERVER.to_string(), "home/".to_string());
+
+        harness.load(&format!("nym://{}/", SERVER));
+        // Not taken for a double press
+        std::thread::sleep(NAVIGATION_DEBOUNCE);
+        harness.load(&format!("nym://{}//home/", SERVER));
+
+        assert_eq!(harness.browser.current_content, "Home page");
+        assert_eq!(harness.page_requests(), vec![format!("GET /home FROM {}", CLIENT); 2]);
+        // Both addresses are the same page
+        assert!(harness.browser.history.iter().all(|entry| entry.page == "home"));
+    }
+
+    #[test]
+    fn our_own_address_is_refused_as_a_server() {
+        let mut harness = Harness::new();
+        harness.enter_address(&format!("nym://{}/", CLIENT));
+        harness.settle();
+
+        assert!(harness.browser.error.as_deref().is_some_and(|error| error.contains("your own client address")));
+        assert!(!harness.browser.page_loading);
+        assert!(harness.responses.received().is_empty());
+    }
+
+    #[test]
+    fn a_blocked_server_is_never_sent_to() {
+        let mut harness = Harness::new();
+        harness.browser.settings.server_filter.mode = FilterMode::Deny;
+        harness.browser.settings.server_filter.denied.push(SERVER.to_string());
+        harness.enter_address(&format!("nym://{}/home", SERVER));
+        harness.settle();
+
+        assert!(harness.browser.error.as_deref().is_some_and(|error| error.contains("deny list")));
+        assert!(harness.responses.received().is_empty());
+
+        harness.browser.settings.server_filter.mode = FilterMode::AllowOnly;
+        std::thread::sleep(NAVIGATION_DEBOUNCE);
+        harness.enter_address(&format!("nym://{}/home", SERVER));
+        harness.settle();
+
+        assert!(harness.browser.error.as_deref().is_some_and(|error| error.contains("allow list")));
+        assert!(harness.responses.received().is_empty());
+    }
+
+    #[test]
+    fn an_empty_ok_reply_is_done_not_the_welcome_page() {
+        let mut harness = Harness::new();
+        harness.responses.insert("/action", "");
+        harness.load(&format!("nym://{}/action", SERVER));
+        harness.settle();
+
+        assert_eq!(harness.browser.page_state, PageState::Loaded);
+        assert_eq!(harness.browser.error, None);
+        let text = harness.rendered_text();
+        assert!(text.contains("Done (no content)"), "{}", text);
+        assert!(!text.contains("Welcome!"), "{}", text);
+    }
+
+    #[test]
+    fn blank_pages_are_placeholders_recorded_in_history() {
+        let mut harness = Harness::new();
+        let bodies = [("/empty", ""), ("/whitespace", " \n\t\r\n  "), ("/bom", "\u{feff}"), ("/bom-newline", "\u{feff}\r\n")];
+        for (index, (path, body)) in bodies.into_iter().enumerate() {
+            harness.responses.insert(path, body);
+            std::thread::sleep(NAVIGATION_DEBOUNCE);
+            harness.load(&format!("nym://{}{}", SERVER, path));
+            harness.settle();
+
+            assert_eq!(harness.browser.page_state, PageState::Loaded, "{}", path);
+            assert_eq!(harness.browser.history.len(), index + 1, "{}", path);
+            let text = harness.rendered_text();
+            assert!(text.contains("The server returned an empty page."), "{}: {}", path, text);
+            assert!(text.contains(&format!("nym://{}{}", SERVER, path)), "{}: {}", path, text);
+            assert!(text.contains("Reload"), "{}: {}", path, text);
+        }
+    }
+
+    #[test]
+    fn only_a_repeat_of_the_last_navigation_is_debounced() {
+        let mut harness = Harness::new();
+        assert!(harness.browser.begin_navigation("/a"));
+        assert!(!harness.browser.begin_navigation("/a"));
+        assert!(harness.browser.begin_navigation("/b"));
+        assert!(harness.browser.begin_navigation("/a"));
+        assert!(!harness.browser.begin_navigation("/a"));
+        std::thread::sleep(NAVIGATION_DEBOUNCE);
+        assert!(harness.browser.begin_navigation("/a"));
+    }
+
+    #[test]
+    fn a_new_navigation_supersedes_the_pending_one() {
+        let mut harness = Harness::new();
+        harness.responses.silence("/slow");
+        harness.responses.insert("/fast", "# Fast page");
+        harness.enter_address(&format!("nym://{}/slow", SERVER));
+        harness.run_until("the slow request is sent", |browser| browser.pending_request_id.is_some());
+        let slow_id = harness.browser.pending_request_id.unwrap();
+
+        harness.load(&format!("nym://{}/fast", SERVER));
+        assert_eq!(harness.browser.current_content, "# Fast page");
+
+        // The superseded request answering or failing late changes nothing
+        harness.browser.handle_server_message(Some(slow_id), protocol::MARKDOWN_TYPE, b"OK\n# Slow page".to_vec(), None);
+        harness.browser.handle_request_failed(slow_id, "Timed out".to_string());
+        harness.settle();
+
+        assert_eq!(harness.browser.current_content, "# Fast page");
+        assert_eq!(harness.browser.page_state, PageState::Loaded);
+        assert_eq!(harness.browser.error, None);
+        assert!(!harness.rendered_text().contains("Slow page"));
+    }
+
+    #[test]
+    fn the_page_source_follows_where_the_page_came_from() {
+        let mut harness = Harness::new();
+        harness.responses.insert("/a", "# Page A");
+        harness.responses.insert("/b", "# Page B");
+        harness.load(&format!("nym://{}/a", SERVER));
+        std::thread::sleep(NAVIGATION_DEBOUNCE);
+        harness.load(&format!("nym://{}/b", SERVER));
+        harness.settle();
+        assert_eq!(harness.browser.page_source, PageSource::Live);
+        assert!(harness.rendered_text().contains("live"));
+
+        harness.browser.go_back();
+        harness.settle();
+        assert_eq!(harness.browser.current_content, "# Page A");
+        assert_eq!(harness.browser.page_source, PageSource::History);
+        assert!(harness.rendered_text().contains("history snapshot"));
+
+        harness.browser.go_forward();
+        harness.settle();
+        assert_eq!(harness.browser.current_content, "# Page B");
+        assert_eq!(harness.browser.page_source, PageSource::History);
+
+        harness.browser.reload_current_page(false);
+        harness.run_until("the reload arrives", |browser| !browser.page_loading);
+        harness.settle();
+        assert_eq!(harness.browser.page_source, PageSource::Live);
+        assert!(!harness.rendered_text().contains("history snapshot"));
+
+        // Error replies are live too
+        std::thread::sleep(NAVIGATION_DEBOUNCE);
+        harness.load(&format!("nym://{}/missing", SERVER));
+        assert!(matches!(harness.browser.page_state, PageState::ServerError(_)));
+        assert_eq!(harness.browser.page_source, PageSource::Live);
+    }
+
+    #[test]
+    fn a_page_arriving_mid_typing_keeps_the_address_bar() {
+        let mut harness = Harness::new();
+        harness.responses.silence("/slow");
+        harness.responses.insert("/next", "# Next page");
+        harness.enter_address(&format!("nym://{}/slow", SERVER));
+        harness.run_until("the slow request is sent", |browser| browser.pending_request_id.is_some());
+        let slow_id = harness.browser.pending_request_id.unwrap();
+
+        let address_bar = egui::Id::new("address_bar");
+        harness.browser.address_bar.clear();
+        harness.ctx.memory_mut(|memory| memory.request_focus(address_bar));
+        harness.frame(Vec::new());
+        let typed = format!("nym://{}", SERVER);
+        harness.frame(vec![egui::Event::Text(typed.clone())]);
+
+        harness.browser.handle_server_message(Some(slow_id), protocol::MARKDOWN_TYPE, b"OK\n# Slow page".to_vec(), None);
+        harness.settle();
+        assert_eq!(harness.browser.current_content, "# Slow page");
+        assert!(harness.ctx.memory(|memory| memory.has_focus(address_bar)));
+        assert_eq!(harness.browser.address_bar, typed);
+
+        harness.frame(vec![egui::Event::Text("/next".to_string())]);
+        std::thread::sleep(NAVIGATION_DEBOUNCE);
+        harness.frame(vec![key_press(egui::Key::Enter)]);
+        harness.run_until("the next page arrives", |browser| !browser.page_loading);
+        assert_eq!(harness.page_requests().last().unwrap(), &format!("GET /next FROM {}", CLIENT));
+        assert_eq!(harness.browser.current_content, "# Next page");
+    }
+
+    #[test]
+    fn render_caches_stay_bounded_over_many_pages() {
+        let mut harness = Harness::new();
+        for page in 0..100 {
+            let path = format!("/page{}", page);
+            harness.responses.insert(&path, &format!("# Page {}\n\nText of page {}.", page, page));
+            harness.load(&format!("nym://{}{}", SERVER, path));
+            harness.frame(Vec::new());
+            assert!(harness.browser.md_caches.len() <= RENDER_CACHE_PAGES);
+        }
+        assert_eq!(harness.browser.md_caches.len(), RENDER_CACHE_PAGES);
+    }
+
+    #[test]
+    fn waking_from_sleep_restarts_the_request_timer() {
+        let mut harness = Harness::new();
+        harness.responses.silence("/slow");
+        harness.enter_address(&format!("nym://{}/slow", SERVER));
+        assert!(harness.browser.page_loading);
+
+        // The wall clock ran an hour ahead of the monotonic one
+        harness.browser.page_load_start_time = Instant::now().checked_sub(PAGE_LOAD_TIMEOUT + Duration::from_secs(1));
+        harness.browser.last_frame = Some((Instant::now(), SystemTime::now() - Duration::from_secs(3600)));
+        harness.frame(Vec::new());
+
+        assert!(harness.browser.page_loading);
+        assert_eq!(harness.browser.error, None);
+        assert!(harness.browser.page_load_start_time.unwrap().elapsed() < Duration::from_secs(1));
+
+        // The client sends itself a message to see that it still gets through
+        assert!(harness.browser.resume_check.is_some());
+        harness.run_until("the check comes back", |browser| {
+            browser.resume_check.as_ref().is_some_and(|(check, _)| *check == ResumeCheck::Healthy)
+        });
+        assert!(harness.rendered_text().contains("the connection is fine"));
+        assert!(harness.page_requests().iter().all(|request| request.contains("/slow")));
+    }
+
+    #[test]
+    fn a_connection_lost_in_sleep_is_reestablished() {
+        let mut harness = Harness::new();
+        harness.responses.silence("/slow");
+        harness.enter_address(&format!("nym://{}/slow", SERVER));
+        harness.settle();
+        harness.responses.fail_sends(vec![SendFailure::NotConnected("gateway went away".to_string())]);
+
+        harness.browser.last_frame = Some((Instant::now(), SystemTime::now() - Duration::from_secs(3600)));
+        harness.frame(Vec::new());
+        harness.run_until("the check fails", |browser| {
+            matches!(&browser.resume_check, Some((ResumeCheck::Reconnected(error), _)) if error.contains("gateway went away"))
+        });
+        harness.run_until("the new client connects", |browser| !browser.client_address.is_empty());
+
+        assert_eq!(harness.browser.connection_status, "Connected");
+        assert!(harness.rendered_text().contains("reconnected"));
+    }
+
+    #[test]
+    fn a_late_frame_is_not_a_suspend() {
+        let mut harness = Harness::new();
+        harness.responses.silence("/slow");
+        harness.enter_address(&format!("nym://{}/slow", SERVER));
+
+        // Both clocks moved on together
+        let started = Instant::now() - Duration::from_secs(20);
+        harness.browser.page_load_start_time = Some(started);
+        harness.browser.last_frame = Some((Instant::now() - Duration::from_secs(20), SystemTime::now() - Duration::from_secs(20)));
+        harness.frame(Vec::new());
+        assert_eq!(harness.browser.page_load_start_time, Some(started));
+
+        harness.browser.page_load_start_time = Instant::now().checked_sub(PAGE_LOAD_TIMEOUT + Duration::from_secs(1));
+        harness.frame(Vec::new());
+        assert!(!harness.browser.page_loading);
+        assert!(matches!(harness.browser.page_state, PageState::TransportError(_)));
+    }
+
+    #[test]
+    fn link_hints_are_the_followable_links() {
+        let mut harness = Harness::new();
+        harness.responses.insert(
+            "/home",
+            "# Home\n\n[About](/about) and [Other](nym://other.addr@gateway/x)\n\n```\nnym://in.code@block/\n```\n\nplain nym://in.text@page/",
+        );
+        harness.responses.insert("/about", "# About");
+        harness.load(&format!("nym://{}/home", SERVER));
+        assert_eq!(harness.browser.link_hints(), ["/about".to_string(), "nym://other.addr@gateway/x".to_string()]);
+
+        harness.frame(vec![egui::Event::Key {
+            key: egui::Key::Num1,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::ALT,
+        }]);
+        harness.run_until("the linked page arrives", |browser| !browser.page_loading);
+        assert_eq!(harness.browser.current_content, "# About");
+    }
+
+    #[test]
+    fn back_and_forward_move_through_history() {
+        let mut harness = Harness::new();
+        harness.responses.insert("/one", "Page one");
+        harness.responses.insert("/two", "Page two");
+        harness.load(&format!("nym://{}/one", SERVER));
+        harness.load(&format!("nym://{}/two", SERVER));
+        let requests = harness.page_requests().len();
+
+        harness.browser.go_back();
+        harness.frame(Vec::new());
+        assert_eq!(harness.browser.current_content, "Page one");
+        assert_eq!(harness.browser.address_bar, "one");
+
+        harness.browser.go_forward();
+        harness.frame(Vec::new());
+        assert_eq!(harness.browser.current_content, "Page two");
+        // Both pages came from history, not the server
+        assert_eq!(harness.page_requests().len(), requests);
+    }
+
+    #[test]
+    fn going_back_during_a_load_ignores_its_reply() {
+        let mut harness = Harness::new();
+        harness.responses.insert("/a", "# Page A");
+        harness.responses.silence("/slow");
+        harness.load(&format!("nym://{}/a", SERVER));
+        harness.enter_address(&format!("nym://{}/slow", SERVER));
+        harness.run_until("the slow request is sent", |browser| browser.pending_request_id.is_some());
+        let slow_id = harness.browser.pending_request_id.unwrap();
+
+        harness.browser.go_back();
+        assert_eq!(harness.browser.pending_request_id, None);
+        let entry_before = harness.browser.history[harness.browser.current_history_index].clone();
+
+        harness.browser.handle_server_message(Some(slow_id), protocol::MARKDOWN_TYPE, b"OK\n# Slow page".to_vec(), None);
+        harness.browser.handle_request_failed(slow_id, "Timed out".to_string());
+        harness.settle();
+
+        assert_eq!(harness.browser.address_bar, "a");
+        assert_eq!(harness.browser.current_content, "# Page A");
+        assert_eq!(harness.browser.page_state, PageState::Loaded);
+        let entry = &harness.browser.history[harness.browser.current_history_index];
+        assert_eq!(entry.page, "a");
+        assert_eq!(entry.content, entry_before.content);
+        assert_eq!(entry.fetched_at, entry_before.fetched_at);
+        assert!(!harness.rendered_text().contains("Slow page"));
+    }
+
+    #[test]
+    fn back_returns_to_a_server_error_page_with_its_ribbon() {
+        let mut harness = Harness::new();
+        harness.responses.insert("/a", "# Page A");
+        harness.load(&format!("nym://{}/a", SERVER));
+        harness.load(&format!("nym://{}/missing", SERVER));
+        assert_eq!(harness.browser.page_state, PageState::ServerError(ErrorStatus::NotFound));
+        harness.load(&format!("nym://{}/a", SERVER));
+
+        harness.browser.go_back();
+        harness.settle();
+
+        assert_eq!(harness.browser.address_bar, "missing");
+        assert_eq!(harness.browser.page_state, PageState::ServerError(ErrorStatus::NotFound));
+        assert_eq!(harness.page_requests().len(), 3);
+        let text = harness.rendered_text();
+        assert!(text.contains("Page not found"), "{}", text);
+        assert!(text.contains(&format!("nym://{}/missing answered NOT_FOUND", SERVER)), "{}", text);
+    }
+
+    #[test]
+    fn back_skips_a_transport_error_page() {
+        let mut harness = Harness::new();
+        harness.responses.insert("/a", "# Page A");
+        harness.responses.insert("/b", "# Page B");
+        harness.load(&format!("nym://{}/a", SERVER));
+        harness.responses.fail_sends(vec![SendFailure::NotConnected("gateway went away".to_string())]);
+        harness.load(&format!("nym://{}/broken", SERVER));
+        assert!(matches!(harness.browser.page_state, PageState::TransportError(_)));
+        harness.load(&format!("nym://{}/b", SERVER));
+
+        harness.browser.go_back();
+        harness.settle();
+
+        assert_eq!(harness.browser.address_bar, "a");
+        assert_eq!(harness.browser.current_content, "# Page A");
+        assert_eq!(harness.browser.page_state, PageState::Loaded);
+        assert!(harness.browser.history.iter().all(|entry| entry.page != "broken"));
+        assert!(!harness.rendered_text().contains("gateway went away"));
+    }
+
+    #[test]
+    fn a_page_whose_preparation_panics_is_shown_as_raw_text() {
+        let mut harness = Harness::new();
+        harness.responses.insert("/a", "# Page A");
+        harness.load(&format!("nym://{}/a", SERVER));
+
+        let page = PreparedPage::with_rewrite("# Title\n\nBody text".to_string(), SERVER, |_, _| panic!("pathological input"));
+        assert!(page.fallback);
+        harness.browser.show_prepared_page(PageSource::Live, page, None);
+
+        assert!(harness.browser.render_fallback);
+        let text = harness.rendered_text();
+        assert!(text.contains("This page could not be rendered as Markdown \u{2014} showing raw text"), "{}", text);
+        assert!(text.contains("# Title\n\nBody text"), "{}", text);
+    }
+
+    #[test]
+    fn a_page_that_draws_nothing_is_shown_as_raw_text() {
+        // Only link reference definitions: valid Markdown with nothing to draw
+        const NOTHING_TO_DRAW: &str = "[home]: /home\n[about]: /about \"About\"\n";
+        let mut harness = Harness::new();
+        harness.responses.insert("/blank", NOTHING_TO_DRAW);
+        harness.load(&format!("nym://{}/blank", SERVER));
+        harness.frame(Vec::new());
+
+        assert!(harness.browser.render_fallback);
+        let text = harness.rendered_text();
+        assert!(text.contains("This page could not be rendered as Markdown \u{2014} showing raw text"), "{}", text);
+        assert!(text.contains("[home]: /home"), "{}", text);
+    }
+
+    #[test]
+    fn a_double_enter_in_the_address_bar_sends_one_request() {
+        let mut harness = Harness::new();
+        // Unanswered, so the second Enter finds the first request pending
+        harness.responses.silence("/home");
+        harness.enter_address(&format!("nym://{}/home", SERVER));
+        assert_eq!(harness.browser.server_address, SERVER);
+        harness.ctx.memory_mut(|memory| memory.request_focus(egui::Id::new("address_bar")));
+        harness.frame(Vec::new());
+        harness.frame(vec![key_press(egui::Key::Enter)]);
+        harness.settle();
+
+        assert_eq!(harness.page_requests(), vec![format!("GET /home FROM {}", CLIENT)]);
+    }
+
+    #[test]
+    fn a_corrected_address_is_shown_before_it_is_loaded() {
+        let mut harness = Harness::new();
+        harness.responses.insert("/about", "About this server");
+        let typed = format!("nym:/{}/about", SERVER);
+        let corrected = format!("nym://{}/about", SERVER);
+        harness.enter_address(&typed);
+        harness.settle();
+
+        assert_eq!(harness.browser.address_bar, corrected);
+        assert!(harness.rendered_text().contains(&format!("did you mean {}? (auto-corrected)", corrected)));
+        assert!(!harness.browser.page_loading);
+        assert!(harness.page_requests().is_empty());
+
+        harness.frame(vec![key_press(egui::Key::Escape)]);
+        assert_eq!(harness.browser.address_bar, typed);
+        assert_eq!(harness.browser.address_before_correction, None);
+        assert!(!harness.rendered_text().contains("auto-corrected"));
+
+        // Corrected again, then confirmed with a second Enter
+        harness.enter_address(&typed);
+        harness.frame(vec![key_press(egui::Key::Enter)]);
+        harness.run_until("the page arrives", |browser| !browser.page_loading);
+        assert_eq!(harness.browser.current_content, "About this server");
+        assert_eq!(harness.page_requests(), vec![format!("GET /about FROM {}", CLIENT)]);
+    }
+
+    #[test]
+    fn sent_reports_are_listed_at_their_address() {
+        let mut harness = Harness::new();
+        harness.browser.sent_reports.reports.push(SentReport {
+            collector: "collector.addr@gateway".to_string(),
+            report: Report {
+                url: format!("nym://{}/spam", SERVER),
+                content_sha256: String::new(),
+                excerpt: "buy now".to_string(),
+                comment: "advertising".to_string(),
+                timestamp: 1_700_000_000,
+                image_sha256: None,
+            },
+        });
+
+        harness.enter_address(REPORTS_URL);
+        harness.settle();
+
+        assert!(harness.browser.show_sent_reports);
+        let text = harness.rendered_text();
+        assert!(text.contains(&format!("nym://{}/spam", SERVER)), "{}", text);
+        assert!(text.contains("Comment: advertising"));
+        assert!(harness.responses.received().is_empty());
+    }
+
+    #[test]
+    fn reports_wait_until_the_collector_is_known_to_read_headers() {
+        let collector = "collector.addr@gateway";
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.browser.settings.report_address = collector.to_string();
+        harness.load(&format!("nym://{}/", SERVER));
+
+        harness.browser.report_draft = Some(ReportDraft::default());
+        harness.frame(vec![]);
+        assert!(harness.browser.send_report(collector, 99, Report::new("nym://x/", "", "", ""), String::new(), None).is_err());
+        harness.run_until("the collector answers the probe", |browser| browser.settings.header_servers.contains(collector));
+
+        harness.click("Preview report");
+        harness.settle();
+        // The button is below the preview, out of the test screen
+        let (report, request_id, payload) = harness.browser.report_draft.as_ref().unwrap().preview.clone().unwrap();
+        harness.browser.send_report(collector, request_id, report, payload, None).unwrap();
+        harness.run_until("the collector answers", |browser| browser.report_request_id.is_none());
+
+        let report = harness.responses.received().into_iter().find(|request| request.starts_with("REPORT ")).unwrap();
+        assert!(report.contains("\nRequest-Id: "), "{}", report);
+        assert_eq!(harness.browser.sent_reports.reports.len(), 1);
+        assert!(harness.browser.report_status.as_deref().is_some_and(|status| status.starts_with("The collector answered")));
+    }
+
+    #[test]
+    fn a_large_page_arrives_ready_to_show() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        let paragraph = "Some *text* with a [link](/elsewhere) and ![an image](picture.png).\n\n";
+        let page = paragraph.repeat(1_000_000 / paragraph.len());
+        // Delivered by hand below, as the mixnet task would
+        harness.responses.hold("/big");
+        harness.enter_address(&format!("nym://{}/big", SERVER));
+        harness.run_until("the request is sent", |browser| browser.pending_request_id.is_some());
+
+        let reply = BrowserMessage::ReceivedMessage {
+            request_id: harness.browser.pending_request_id,
+            content_type: protocol::MARKDOWN_TYPE.to_string(),
+            data: format!("OK\n{}", page).into_bytes(),
+            prepared: None,
+        };
+        let BrowserMessage::ReceivedMessage { request_id, content_type, data, prepared } =
+            NymMixnetBrowser::prepare_for_gui(reply, SERVER, true)
+        else {
+            unreachable!();
+        };
+        assert!(prepared.is_some());
+
+        let started = Instant::now();
+        harness.browser.handle_server_message(request_id, &content_type, data, prepared);
+        let elapsed = started.elapsed();
+
+        assert!(elapsed < Duration::from_millis(10), "showing the page took {:?}", elapsed);
+        assert_eq!(harness.browser.page_state, PageState::Loaded);
+        assert!(harness.browser.current_content.trim_end() == page.trim_end());
+        assert!(harness.browser.display_content.contains(&format!("nym://{}/picture.png", SERVER)));
+        assert_eq!(harness.browser.page_links, vec!["/elsewhere".to_string()]);
+    }
+
+    fn requests_for(harness: &Harness, path: &str) -> usize {
+        let line = format!("GET {} FROM {}", path, CLIENT);
+        harness.page_requests().iter().filter(|request| **request == line).count()
+    }
+
+    #[test]
+    fn identical_requests_in_flight_share_one_reply() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.responses.insert("/a", "# Page A");
+        harness.responses.insert("/slow", "# Slow page");
+        harness.responses.hold("/slow");
+        harness.enter_address(&format!("nym://{}/slow", SERVER));
+        harness.run_until("the slow request is sent", |browser| browser.pending_request_id.is_some());
+        let slow_id = harness.browser.pending_request_id.unwrap();
+
+        // A transclusion of the same page, then leaving and coming back
+        let fetch = RUNTIME.spawn(harness.browser.navigate(&url("slow")));
+        harness.load(&format!("nym://{}/a", SERVER));
+        harness.enter_address(&format!("nym://{}/slow", SERVER));
+        assert_eq!(harness.browser.pending_request_id, Some(slow_id));
+
+        harness.responses.release("/slow");
+        harness.run_until("the slow page arrives", |browser| !browser.page_loading);
+
+        assert_eq!(harness.browser.current_content, "# Slow page");
+        assert_eq!(RUNTIME.block_on(fetch).unwrap(), Ok("# Slow page".to_string()));
+        assert_eq!(requests_for(&harness, "/slow"), 1);
+        assert_eq!(harness.browser.metrics.coalesced, 1);
+        assert_eq!(IN_FLIGHT.lock().unwrap().find(&RequestKey::get(SERVER, "/slow")), None);
+    }
+
+    #[test]
+    fn a_timed_out_request_is_sent_again() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.responses.silence("/slow");
+        harness.enter_address(&format!("nym://{}/slow", SERVER));
+        harness.run_until("the slow request is sent", |browser| browser.pending_request_id.is_some());
+
+        harness.browser.page_load_start_time = Instant::now().checked_sub(PAGE_LOAD_TIMEOUT + Duration::from_secs(1));
+        harness.frame(Vec::new());
+        assert!(matches!(harness.browser.page_state, PageState::TransportError(_)));
+
+        std::thread::sleep(NAVIGATION_DEBOUNCE);
+        harness.enter_address(&format!("nym://{}/slow", SERVER));
+        harness.settle();
+
+        assert_eq!(requests_for(&harness, "/slow"), 2);
+        assert_eq!(harness.browser.metrics.coalesced, 0);
+    }
+
+    #[test]
+    fn the_reply_still_reaches_others_when_the_page_view_gives_up() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.responses.insert("/slow", "# Slow page");
+        harness.responses.hold("/slow");
+        harness.enter_address(&format!("nym://{}/slow", SERVER));
+        harness.run_until("the slow request is sent", |browser| browser.pending_request_id.is_some());
+        let fetch = RUNTIME.spawn(harness.browser.navigate(&url("slow")));
+
+        // The page view times out first
+        harness.browser.page_load_start_time = Instant::now().checked_sub(PAGE_LOAD_TIMEOUT + Duration::from_secs(1));
+        harness.frame(Vec::new());
+        assert!(matches!(harness.browser.page_state, PageState::TransportError(_)));
+
+        harness.responses.release("/slow");
+        assert_eq!(RUNTIME.block_on(fetch).unwrap(), Ok("# Slow page".to_string()));
+        harness.settle();
+
+        assert_eq!(requests_for(&harness, "/slow"), 1);
+        assert!(matches!(harness.browser.page_state, PageState::TransportError(_)));
+        assert_ne!(harness.browser.current_content, "# Slow page");
+    }
+
+    #[test]
+    fn offline_mode_sends_nothing() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.responses.insert("/home", "# Home");
+        harness.load(&format!("nym://{}/home", SERVER));
+        let sent = harness.responses.received().len();
+        assert!(sent > 0);
+
+        // The mixnet task is left running, so only offline mode stands
+        // between each of these and the transport
+        harness.browser.offline = true;
+        std::thread::sleep(NAVIGATION_DEBOUNCE);
+        harness.enter_address(&format!("nym://{}/other", SERVER));
+        harness.settle();
+        assert_eq!(harness.browser.error.as_deref(), Some(OFFLINE_MODE));
+        harness.browser.reload_current_page(true);
+        harness.browser.handle_link_click(&format!("nym://{}/linked", SERVER));
+        harness.settle();
+        let fetched = RUNTIME.block_on(harness.browser.navigate(&url("home")));
+        assert_eq!(fetched, Err(FetchError::NotSent(OFFLINE_MODE.to_string())));
+
+        assert_eq!(harness.responses.received().len(), sent);
+        assert!(!harness.browser.page_loading);
+    }
+
+    #[test]
+    fn transient_send_failures_are_retried() {
+        let mut harness = Harness::new();
+        // Known to read headers, so no probe shares the failures
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.responses.insert("/home", "# Home");
+        let busy = || SendFailure::Transient("queue full".to_string());
+        harness.responses.fail_sends(vec![busy(), busy()]);
+        harness.load(&format!("nym://{}/home", SERVER));
+
+        assert_eq!(harness.browser.page_state, PageState::Loaded);
+        assert_eq!(harness.browser.current_content, "# Home");
+        assert_eq!(harness.browser.metrics.send_failures, 0);
+    }
+
+    #[test]
+    fn retries_give_up_after_the_last_attempt() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.responses.insert("/home", "# Home");
+        let busy = || SendFailure::Transient("queue full".to_string());
+        harness.responses.fail_sends((0..=SEND_RETRIES).map(|_| busy()).collect());
+        harness.load(&format!("nym://{}/home", SERVER));
+
+        assert!(matches!(&harness.browser.page_state, PageState::TransportError(error) if error.contains("queue full")));
+        assert!(harness.page_requests().is_empty());
+    }
+
+    #[test]
+    fn other_send_failures_are_not_retried() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.responses.insert("/home", "# Home");
+        harness.responses.fail_sends(vec![SendFailure::InvalidRecipient("malformed recipient".to_string())]);
+        harness.load(&format!("nym://{}/home", SERVER));
+
+        assert!(matches!(&harness.browser.page_state, PageState::TransportError(error) if error.starts_with("Invalid address")));
+        assert!(harness.page_requests().is_empty());
+    }
+
+    #[test]
+    fn every_button_has_a_spoken_name() {
+        use egui::accesskit::Role;
+
+        let mut harness = Harness::new();
+        harness.responses.insert("/home", "# Home\n\n[About](/about)");
+        harness.load(&format!("nym://{}/home", SERVER));
+        harness.ctx.enable_accesskit();
+        harness.frame(Vec::new());
+        let update = harness.frame(Vec::new()).platform_output.accesskit_update.expect("an accessibility tree");
+
+        let buttons: Vec<Option<String>> = update.nodes
+            .iter()
+            .filter(|(_, node)| node.role() == Role::Button)
+            .map(|(_, node)| node.label().map(str::to_string))
+            .collect();
+        assert!(buttons.len() >= 5, "{:?}", buttons);
+        for name in &buttons {
+            // Icon glyphs alone are read out as symbol names, if at all
+            assert!(
+                name.as_deref().is_some_and(|name| name.chars().any(char::is_alphabetic)),
+                "unnamed button {:?} among {:?}",
+                name,
+                buttons,
+            );
+        }
+    }
+
+    #[test]
+    fn navigate_resolves_with_the_page() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.responses.insert("/about", "# About\n\nHello");
+
+        let page = RUNTIME.block_on(harness.browser.navigate(&url("about")));
+        assert_eq!(page, Ok("# About\n\nHello".to_string()));
+        // The page was handed back, not shown
+        assert_ne!(harness.browser.current_content, "# About\n\nHello");
+    }
+
+    #[test]
+    fn concurrent_navigations_each_get_their_own_page() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.responses.insert("/one", "first");
+        harness.responses.insert("/two", "second");
+
+        let (two, one) = (url("/two"), url("/one"));
+        let browser = &harness.browser;
+        let (page_two, page_one) = RUNTIME.block_on(async {
+            tokio::join!(browser.navigate(&two), browser.navigate(&one))
+        });
+        assert_eq!(page_two, Ok("second".to_string()));
+        assert_eq!(page_one, Ok("first".to_string()));
+    }
+
+    #[test]
+    fn navigate_reports_server_errors() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+
+        let error = RUNTIME.block_on(harness.browser.navigate(&url("missing")));
+        assert!(matches!(error, Err(FetchError::Status(ErrorStatus::NotFound, _))), "{:?}", error);
+    }
+
+    #[test]
+    fn navigate_needs_servers_that_echo_request_ids() {
+        let harness = Harness::new();
+
+        let error = RUNTIME.block_on(harness.browser.navigate(&url("/")));
+        assert_eq!(error, Err(FetchError::NoRequestIds(SERVER.to_string())));
+    }
+
+    #[test]
+    fn transclusions_expand_in_place() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.responses.insert("/docs", "# Docs\n\n[Setup](/setup \"transclude\")\n\nAfter");
+        harness.responses.insert("/setup", "Run the client");
+        harness.load(&format!("nym://{}/docs", SERVER));
+        let sent = harness.page_requests().len();
+
+        // Collapsed expanders fetch nothing
+        harness.settle();
+        assert_eq!(harness.page_requests().len(), sent);
+        assert!(!harness.rendered_text().contains("Run the client"));
+
+        harness.click("Setup");
+        harness.run_until("the transclusion arrives", |browser| loaded(browser) == 1);
+        let text = harness.rendered_text();
+        assert!(text.contains("Run the client"), "{}", text);
+        assert!(text.contains("After"), "{}", text);
+        assert_eq!(harness.browser.current_content, "# Docs\n\n[Setup](/setup \"transclude\")\n\nAfter");
+
+        // Collapsing keeps the page, opening it again fetches nothing
+        harness.click("Setup");
+        harness.click("Setup");
+        harness.settle();
+        assert!(harness.rendered_text().contains("Run the client"));
+        assert_eq!(harness.page_requests().len(), sent + 1);
+    }
+
+    #[test]
+    fn a_loading_transclusion_holds_still_with_reduced_motion() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.browser.settings.reduce_motion = true;
+        harness.responses.insert("/docs", "# Docs\n\n[Setup](/setup \"transclude\")");
+        harness.responses.hold("/setup");
+        harness.load(&format!("nym://{}/docs", SERVER));
+
+        harness.click("Setup");
+        harness.settle();
+        assert!(matches!(harness.browser.transclusions.values().next(), Some(Transclusion::Loading)));
+        assert!(harness.rendered_text().contains("Loading\u{2026}"));
+        // A spinner would ask for the next frame right away
+        let output = harness.frame(Vec::new());
+        assert!(output.viewport_output[&egui::ViewportId::ROOT].repaint_delay >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn transclusion_errors_stay_in_their_expander() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.responses.insert("/docs", "# Docs\n\n!include(/missing)");
+        harness.load(&format!("nym://{}/docs", SERVER));
+
+        harness.click("/missing");
+        harness.run_until("the transclusion fails", |browser| {
+            matches!(browser.transclusions.values().next(), Some(Transclusion::Failed(..)))
+        });
+        let text = harness.rendered_text();
+        assert!(text.contains("NOT_FOUND"), "{}", text);
+        assert!(text.contains("# Docs"), "{}", text);
+        assert_eq!(harness.browser.page_state, PageState::Loaded);
+        assert!(harness.browser.error.is_none());
+    }
+
+    fn loaded(browser: &NymMixnetBrowser) -> usize {
+        browser.transclusions.values().filter(|transclusion| matches!(transclusion, Transclusion::Loaded(..))).count()
+    }
+
+    #[test]
+    fn transclusions_stop_at_cycles_and_the_depth_limit() {
+        let mut harness = Harness::new();
+        harness.browser.settings.header_servers.insert(SERVER.to_string());
+        harness.responses.insert("/a", "# A\n\n[B](/b \"transclude\")");
+        harness.responses.insert("/b", "In B\n\n[C](/c \"transclude\")");
+        harness.responses.insert("/c", "In C\n\n[Back to A](/a \"transclude\")\n\n[D](/d \"transclude\")");
+        harness.responses.insert("/d", "In D");
+        harness.load(&format!("nym://{}/a", SERVER));
+
+        harness.click("B");
+        harness.run_until("B arrives", |browser| loaded(browser) == 1);
+        harness.click("C");
+        harness.run_until("C arrives", |browser| loaded(browser) == 2);
+        let text = harness.rendered_text();
+        assert!(text.contains("In C"), "{}", text);
+        assert!(text.contains("Back to A is already shown above"), "{}", text);
+        // A third level is only a link
+        assert!(text.contains("[D](/d)"), "{}", text);
+        harness.settle();
+        assert!(!harness.page_requests().iter().any(|request| request.contains("/d")));
+    }
 }
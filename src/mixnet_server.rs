@@ -1,12 +1,16 @@
 use nym_sdk::mixnet;
 use nym_sdk::mixnet::MixnetMessageSender;
+use nymview_protocol::{self as protocol, compression, Data, ErrorStatus, Request, Response};
 use std::collections::HashMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use tokio::sync::RwLock;
 use std::sync::Arc;
 use crate::config;
 
+// Files are sent as one mixnet message; larger ones are refused
+const MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
 pub struct NymMixnetServer {
     nym_client: mixnet::MixnetClient,
     sites_dir: PathBuf,
@@ -16,7 +20,7 @@ pub struct NymMixnetServer {
 
 impl NymMixnetServer {
     pub async fn new(sites_directory: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let config_dir = config::ensure_config_dir()?;
+        let config_dir = config::ensure_config_dir("mixnet_server")?;
         
         println!("Persistence directory: {:?}", config_dir);
         
@@ -83,6 +87,14 @@ This server hosts pages via the **Nym Mixnet**.
 - Place `.md` files in the sites directory  
 - Example: `about.md` → accessible at `/about`
 
+## Markdown Extras
+
+- [x] Task lists render as checkboxes
+- [ ] Definition lists are supported too
+
+Mixnet
+: A network that hides who talks to whom
+
 ## External Links
 
 - [Nym News](nym://5CDQAbHuc...K8LtT7jKGjVJCuNym/news)
@@ -104,7 +116,13 @@ This server hosts pages via the **Nym Mixnet**.
                     if let Ok(text_message) = String::from_utf8(received.message.clone()) {
                         let (response, reply_to) = self.handle_request(&text_message).await;
                         
-                        if let Some(target) = reply_to {
+                        // Anonymous requests carry no FROM; they are answered
+                        // over the reply SURBs that came with them
+                        if let (None, Some(sender_tag)) = (&reply_to, received.sender_tag) {
+                            if let Err(e) = self.nym_client.send_reply(sender_tag, response).await {
+                                eprintln!("Error sending anonymous response: {}", e);
+                            }
+                        } else if let Some(target) = reply_to {
                             match target.parse::<nym_sdk::mixnet::Recipient>() {
                                 Ok(recipient) => {
                                     if let Err(e) = self.nym_client.send_plain_message(recipient, response).await {
@@ -126,67 +144,147 @@ This server hosts pages via the **Nym Mixnet**.
         }
     }
     
-    async fn handle_request(&self, request: &str) -> (String, Option<String>) {
-        // Search for " FROM " from the back (in case path contains spaces)
-        if let Some(from_pos) = request.rfind(" FROM ") {
-            let actual_request = &request[..from_pos];
-            let client_address = request[from_pos + 6..].trim().to_string();
-            
-            let response = self.process_command(actual_request).await;
-            (response, Some(client_address))
-        } else {
-            // Old requests or errors
-            let response = "ERROR: Request must be 'GET /path FROM your_address'".to_string();
-            (response, None)
-        }
+    // The reply address is None for anonymous requests
+    async fn handle_request(&self, message: &str) -> (Vec<u8>, Option<String>) {
+        let response = match Request::parse(message) {
+            Ok(request) => {
+                let mut response = self.process_command(&request).await;
+                if request.accepts_encoding(protocol::ZSTD) {
+                    if let Some(compressed) = compression::compress(&response) {
+                        response = compressed;
+                    }
+                }
+                match request.request_id() {
+                    Some(request_id) => protocol::tag_reply(request_id, &response),
+                    None => response,
+                }
+            }
+            Err(e) => Response::Error(ErrorStatus::ServerError, e).to_string().into_bytes(),
+        };
+        (response, protocol::reply_address(message).map(str::to_string))
     }
     
-    async fn process_command(&self, request: &str) -> String {
-        let parts: Vec<&str> = request.splitn(2, ' ').collect();
-        if parts.len() != 2 {
-            return "ERROR: Invalid request format".to_string();
-        }
-        
-        let command = parts[0];
-        let path = parts[1].trim();
-        
-        match command {
-            "GET" => self.serve_page(path).await,
+    async fn process_command(&self, request: &Request) -> Vec<u8> {
+        let response = match request.method.as_str() {
+            "GET" => return self.serve_page(&request.path).await,
             "LIST" => self.list_pages().await,
-            "PING" => "PONG".to_string(),
+            "PING" => return protocol::PONG_WITH_HEADERS.as_bytes().to_vec(),
             "RELOAD" => self.reload_cache().await,
-            _ => format!("ERROR: Unknown command: {}", command),
-        }
+            command => Response::Error(ErrorStatus::ServerError, format!("Unknown command: {}", command)),
+        };
+        response.to_string().into_bytes()
     }
     
-    async fn serve_page(&self, path: &str) -> String {
+    async fn serve_page(&self, path: &str) -> Vec<u8> {
         let clean_path = if path == "/" { "index" } else { path.trim_start_matches('/') };
         
-        let cache = self.cache.read().await;
-        match cache.get(clean_path) {
-            Some(content) => {
-                format!("OK\n{}", content)
-            }
+        // Not held while a file is read
+        let cached = self.cache.read().await.get(clean_path).cloned();
+        let response = match cached {
+            Some(content) => Response::Ok(content),
             None => {
-                format!("ERROR: Page '{}' not found", clean_path)
+                let sites_dir = self.sites_dir.clone();
+                let file_path = clean_path.to_string();
+                let file = tokio::task::spawn_blocking(move || Self::serve_file(&sites_dir, &file_path))
+                    .await
+                    .unwrap_or_else(|e| Err(Response::Error(ErrorStatus::ServerError, format!("Error reading '{}': {}", clean_path, e))));
+                match file {
+                    Ok(Some(data)) => return data.encode(),
+                    Ok(None) => Response::Error(ErrorStatus::NotFound, format!("Page '{}' not found", clean_path)),
+                    Err(e) => e,
+                }
             }
+        };
+        response.to_string().into_bytes()
+    }
+    
+    // Content type of a file that may be served: one with a known
+    // extension other than a page's, on a plain relative path without
+    // hidden components, so ".env" or ".git/config" are never sent
+    fn servable_type(clean_path: &str) -> Option<&'static str> {
+        let content_type = protocol::content_type_for(clean_path);
+        if content_type == protocol::MARKDOWN_TYPE
+            || content_type == protocol::DEFAULT_TYPE
+            || clean_path.contains('\\') {
+            return None;
         }
+        let plain = Path::new(clean_path).components().all(|component| match component {
+            Component::Normal(name) => !name.to_string_lossy().starts_with('.'),
+            _ => false,
+        });
+        plain.then_some(content_type)
     }
     
-    async fn list_pages(&self) -> String {
+    // Files other than pages are sent as they are on disk, and only if
+    // their path still leads into the sites directory once symlinks are
+    // resolved. Blocking.
+    fn serve_file(sites_dir: &Path, clean_path: &str) -> Result<Option<Data>, Response> {
+        let Some(content_type) = Self::servable_type(clean_path) else {
+            return Ok(None);
+        };
+        
+        let inside = fs::canonicalize(sites_dir.join(clean_path))
+            .and_then(|path| Ok((fs::canonicalize(sites_dir)?, path)));
+        let path = match inside {
+            Ok((sites_dir, path)) if path.starts_with(&sites_dir) => path,
+            _ => return Ok(None),
+        };
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => {
+                if metadata.len() > MAX_FILE_SIZE {
+                    return Err(Response::Error(ErrorStatus::TooLarge, format!("File '{}' is too large to serve", clean_path)));
+                }
+                fs::read(&path)
+                    .map(|bytes| Some(Data::new(content_type, bytes)))
+                    .map_err(|e| Response::Error(ErrorStatus::ServerError, format!("Error reading '{}': {}", clean_path, e)))
+            }
+            _ => Ok(None),
+        }
+    }
+    
+    async fn list_pages(&self) -> Response {
         let cache = self.cache.read().await;
         let pages: Vec<&str> = cache.keys().map(|k| k.as_str()).collect();
-        format!("OK\n{}", pages.join(","))
+        Response::Ok(pages.join(","))
     }
     
-    async fn reload_cache(&self) -> String {
+    async fn reload_cache(&self) -> Response {
         match Self::load_sites_into_cache(&self.sites_dir).await {
             Ok(new_cache) => {
                 let mut cache = self.cache.write().await;
                 *cache = new_cache;
-                "OK\nCache reloaded".to_string()
+                Response::Ok("Cache reloaded".to_string())
             }
-            Err(e) => format!("ERROR: Error reloading: {}", e),
+            Err(e) => Response::Error(ErrorStatus::ServerError, format!("Error reloading: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_known_non_hidden_files_are_servable() {
+        let cases = [
+            ("images/logo.png", Some("image/png")),
+            ("docs/manual.PDF", Some("application/pdf")),
+            ("data.json", Some("application/json")),
+            ("index.md", None),
+            (".env", None),
+            (".git/config", None),
+            (".htpasswd", None),
+            ("images/.hidden.png", None),
+            (".well-known/logo.png", None),
+            ("notes", None),
+            ("backup.sql", None),
+            ("../secret.png", None),
+            ("/etc/logo.png", None),
+            ("images\\logo.png", None),
+            ("./logo.png", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(NymMixnetServer::servable_type(path), expected, "path {:?}", path);
         }
     }
 }
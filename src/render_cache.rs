@@ -0,0 +1,79 @@
+use egui_commonmark::CommonMarkCache;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+
+// Render caches for the most recently shown pages. CommonMarkCache holds
+// on to images and layout state for everything it has rendered, so one
+// shared cache grows with every page visited; this keeps one per page
+// and drops the least recently shown once over capacity.
+pub struct RenderCaches {
+    // Keyed by a hash of the rendered content; most recently used last
+    entries: VecDeque<(u64, CommonMarkCache)>,
+    capacity: usize,
+}
+
+impl RenderCaches {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    // Computed once per page, not once per frame
+    pub fn key(content: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn get(&mut self, key: u64) -> &mut CommonMarkCache {
+        let entry = match self.entries.iter().position(|(k, _)| *k == key) {
+            Some(index) => self.entries.remove(index).unwrap(),
+            None => (key, CommonMarkCache::default()),
+        };
+        self.entries.push_back(entry);
+        if self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+        &mut self.entries.back_mut().unwrap().1
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(caches: &RenderCaches) -> Vec<u64> {
+        caches.entries.iter().map(|(key, _)| *key).collect()
+    }
+
+    #[test]
+    fn the_least_recently_shown_page_is_dropped() {
+        let mut caches = RenderCaches::new(3);
+        for key in [1, 2, 3] {
+            caches.get(key);
+        }
+        caches.get(1);
+        caches.get(4);
+        assert_eq!(keys(&caches), vec![3, 1, 4]);
+        caches.get(4);
+        assert_eq!(keys(&caches), vec![3, 1, 4]);
+    }
+
+    #[test]
+    fn stays_bounded_however_many_pages_are_shown() {
+        let mut caches = RenderCaches::new(8);
+        for page in 0..100 {
+            caches.get(RenderCaches::key(&format!("# Page {}", page)));
+            assert!(caches.len() <= 8);
+        }
+        assert_eq!(caches.len(), 8);
+        assert_eq!(RenderCaches::new(0).capacity, 1);
+    }
+}
@@ -0,0 +1,344 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use nymview_protocol::{self as protocol, compression, ErrorStatus, Request, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::time::Instant;
+use crate::config;
+use crate::transport::{BoxFuture, SendFailure, Transport};
+
+// Address the browser shows while replaying
+pub const REPLAY_ADDRESS: &str = "replay";
+
+// Requests still waiting for a reply are forgotten beyond this many
+const MAX_OUTSTANDING: usize = 256;
+
+// Where the settings record to and replay from
+pub fn default_file() -> PathBuf {
+    config::get_config_dir("replay").join("session.jsonl")
+}
+
+// How the browser reaches servers: over the mixnet, over the mixnet while
+// writing every exchange to a file, or from such a file alone
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SessionMode {
+    #[default]
+    Live,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+// A request and the reply it got; a recording has one per line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exchange {
+    pub server: String,
+    pub request: String,
+    // Text replies stay readable, so recordings can also be written by
+    // hand as test fixtures. Anything else is kept base64-encoded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_base64: Option<String>,
+}
+
+impl Exchange {
+    pub fn new(server: &str, request: &str, reply: &[u8]) -> Self {
+        let (reply, reply_base64) = match std::str::from_utf8(reply) {
+            Ok(text) => (Some(text.to_string()), None),
+            Err(_) => (None, Some(STANDARD.encode(reply))),
+        };
+        Self {
+            server: server.to_string(),
+            request: request.to_string(),
+            reply,
+            reply_base64,
+        }
+    }
+
+    pub fn reply_bytes(&self) -> Result<Vec<u8>, String> {
+        match (&self.reply, &self.reply_base64) {
+            (Some(text), _) => Ok(text.clone().into_bytes()),
+            (None, Some(encoded)) => STANDARD
+                .decode(encoded)
+                .map_err(|e| format!("Invalid reply to {}: {}", self.request, e)),
+            (None, None) => Ok(Vec::new()),
+        }
+    }
+}
+
+// What a request is recorded under: the path of a GET, method and path of
+// anything else. Messages in other servers' formats are taken whole.
+pub fn request_key(message: &str) -> String {
+    match Request::parse(message) {
+        Ok(request) if request.method.chars().all(|c| c.is_ascii_uppercase()) => {
+            if request.method == "GET" {
+                request.path
+            } else {
+                format!("{} {}", request.method, request.path)
+            }
+        }
+        _ => message.trim().to_string(),
+    }
+}
+
+// A reply is appended as soon as it arrives, so a session cut short keeps
+// what it got. Written inline; replies come far slower than disks take them.
+fn append(file: &Path, exchange: &Exchange) -> Result<(), String> {
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    }
+    let mut line = serde_json::to_string(exchange).map_err(|e| format!("Could not serialize reply: {}", e))?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Could not write {}: {}", file.display(), e))
+}
+
+// Passes everything through to another transport, writing each reply to
+// `file` along with the request it answers
+pub struct RecordingTransport {
+    inner: Box<dyn Transport>,
+    file: PathBuf,
+    // ID, server and key of each request sent and not yet answered
+    outstanding: VecDeque<(Option<u64>, String, String)>,
+}
+
+impl RecordingTransport {
+    pub fn new(inner: Box<dyn Transport>, file: PathBuf) -> Self {
+        Self {
+            inner,
+            file,
+            outstanding: VecDeque::new(),
+        }
+    }
+
+    // Tagged replies go with the request of the same ID; untagged ones
+    // with the oldest request sent without an ID
+    fn record(&mut self, message: &[u8]) {
+        let (request_id, reply) = protocol::split_reply_id(message);
+        let Some(index) = self.outstanding.iter().position(|(id, _, _)| *id == request_id) else {
+            return;
+        };
+        let (_, server, key) = self.outstanding.remove(index).unwrap();
+        // Recorded uncompressed, so replays work whatever the request asked for
+        let reply = match compression::decompress(reply) {
+            Ok(reply) => reply,
+            Err(e) => {
+                eprintln!("Not recording reply to {}: {}", key, e);
+                return;
+            }
+        };
+        if let Err(e) = append(&self.file, &Exchange::new(&server, &key, &reply)) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn address(&self) -> String {
+        self.inner.address()
+    }
+
+    fn next_messages(&mut self) -> BoxFuture<'_, Option<Vec<Vec<u8>>>> {
+        Box::pin(async move {
+            let messages = self.inner.next_messages().await?;
+            for message in &messages {
+                self.record(message);
+            }
+            Some(messages)
+        })
+    }
+
+    fn send(&mut self, recipient: String, message: String, reply_surbs: u32) -> BoxFuture<'_, Result<(), SendFailure>> {
+        Box::pin(async move {
+            let request_id = Request::parse(&message).ok().and_then(|request| request.request_id());
+            let key = request_key(&message);
+            self.inner.send(recipient.clone(), message, reply_surbs).await?;
+            if self.outstanding.len() == MAX_OUTSTANDING {
+                self.outstanding.pop_front();
+            }
+            self.outstanding.push_back((request_id, recipient, key));
+            Ok(())
+        })
+    }
+
+    fn disconnect(self: Box<Self>) -> BoxFuture<'static, ()> {
+        self.inner.disconnect()
+    }
+}
+
+// Answers requests from a recording without touching the network. Each
+// reply is held back by `latency`, so pages load about as they would.
+pub struct ReplayTransport {
+    // The last reply recorded for each server and request
+    replies: HashMap<(String, String), Vec<u8>>,
+    latency: Duration,
+    // Replies not yet delivered, in the order they fall due
+    due: VecDeque<(Instant, Vec<u8>)>,
+}
+
+impl ReplayTransport {
+    pub fn new(exchanges: Vec<Exchange>, latency: Duration) -> Result<Self, String> {
+        let mut replies = HashMap::new();
+        for exchange in exchanges {
+            let reply = exchange.reply_bytes()?;
+            replies.insert((exchange.server, exchange.request), reply);
+        }
+        Ok(Self {
+            replies,
+            latency,
+            due: VecDeque::new(),
+        })
+    }
+
+    pub fn load(file: &Path, latency: Duration) -> Result<Self, String> {
+        let content = fs::read_to_string(file)
+            .map_err(|e| format!("Could not read {}: {}", file.display(), e))?;
+        let mut exchanges = Vec::new();
+        for (number, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let exchange = serde_json::from_str(line)
+                .map_err(|e| format!("{} line {}: {}", file.display(), number + 1, e))?;
+            exchanges.push(exchange);
+        }
+        Self::new(exchanges, latency)
+    }
+
+    fn reply_to(&self, recipient: &str, message: &str) -> Vec<u8> {
+        let key = request_key(message);
+        if let Some(reply) = self.replies.get(&(recipient.to_string(), key.clone())) {
+            return reply.clone();
+        }
+        // Servers probed in the recorded session answered already
+        if key.starts_with("PING ") {
+            return protocol::PONG.as_bytes().to_vec();
+        }
+        Response::Error(ErrorStatus::NotFound, format!("{} is not in the recording", key))
+            .to_string()
+            .into_bytes()
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn address(&self) -> String {
+        REPLAY_ADDRESS.to_string()
+    }
+
+    fn next_messages(&mut self) -> BoxFuture<'_, Option<Vec<Vec<u8>>>> {
+        Box::pin(async move {
+            // Replies are queued by send() on the same task, so with none
+            // queued this just waits for the next request
+            let Some(first_due) = self.due.front().map(|(due, _)| *due) else {
+                return std::future::pending().await;
+            };
+            tokio::time::sleep_until(first_due).await;
+            let now = Instant::now();
+            let mut messages = Vec::new();
+            while self.due.front().is_some_and(|(due, _)| *due <= now) {
+                messages.extend(self.due.pop_front().map(|(_, reply)| reply));
+            }
+            Some(messages)
+        })
+    }
+
+    fn send(&mut self, recipient: String, message: String, _reply_surbs: u32) -> BoxFuture<'_, Result<(), SendFailure>> {
+        Box::pin(async move {
+            let reply = self.reply_to(&recipient, &message);
+            let reply = match Request::parse(&message).ok().and_then(|request| request.request_id()) {
+                Some(request_id) => protocol::tag_reply(request_id, &reply),
+                None => reply,
+            };
+            self.due.push_back((Instant::now() + self.latency, reply));
+            Ok(())
+        })
+    }
+
+    fn disconnect(self: Box<Self>) -> BoxFuture<'static, ()> {
+        Box::pin(async {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVER: &str = "server.addr@gateway";
+
+    fn recording() -> Vec<Exchange> {
+        vec![
+            Exchange::new(SERVER, "/", b"OK\n# Home"),
+            Exchange::new(SERVER, "/logo.png", &protocol::Data::new("image/png", vec![0x89, 0xff, 0x00]).encode()),
+            Exchange::new(SERVER, "PING /", protocol::PONG_WITH_HEADERS.as_bytes()),
+        ]
+    }
+
+    #[test]
+    fn requests_are_keyed_by_path_and_method() {
+        assert_eq!(request_key("GET /about FROM client.addr@gw\nRequest-Id: 3"), "/about");
+        assert_eq!(request_key("PING / FROM client.addr@gw"), "PING /");
+        assert_eq!(request_key("/bare/path\n"), "/bare/path");
+        assert_eq!(request_key(r#"{"path": "/"}"#), r#"{"path": "/"}"#);
+    }
+
+    #[test]
+    fn binary_replies_survive_a_round_trip_through_a_line() {
+        let reply = vec![0u8, 159, 146, 150, b'\n'];
+        let exchange = Exchange::new(SERVER, "/file", &reply);
+        assert!(exchange.reply.is_none());
+        let line = serde_json::to_string(&exchange).unwrap();
+        let parsed: Exchange = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.reply_bytes().unwrap(), reply);
+    }
+
+    #[tokio::test]
+    async fn replay_answers_from_the_recording_after_the_latency() {
+        let mut transport = ReplayTransport::new(recording(), Duration::from_millis(20)).unwrap();
+        let started = Instant::now();
+        transport.send(SERVER.to_string(), "GET / FROM replay\nRequest-Id: 9".to_string(), 0).await.unwrap();
+        transport.send(SERVER.to_string(), "GET /missing FROM replay".to_string(), 0).await.unwrap();
+        let messages = transport.next_messages().await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0], b"ID 9\nOK\n# Home");
+        assert_eq!(
+            Response::parse(std::str::from_utf8(&messages[1]).unwrap()),
+            Some(Response::Error(ErrorStatus::NotFound, "/missing is not in the recording".to_string())),
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_matches_the_server_too() {
+        let mut transport = ReplayTransport::new(recording(), Duration::ZERO).unwrap();
+        transport.send("other.addr@gateway".to_string(), "GET / FROM replay".to_string(), 0).await.unwrap();
+        transport.send("other.addr@gateway".to_string(), "PING / FROM replay".to_string(), 0).await.unwrap();
+        let messages = transport.next_messages().await.unwrap();
+        assert!(messages[0].starts_with(b"NOT_FOUND: "));
+        assert_eq!(messages[1], protocol::PONG.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn recorded_sessions_replay_the_same_replies() {
+        let file = std::env::temp_dir().join(format!("nymview-replay-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&file);
+        let source = ReplayTransport::new(recording(), Duration::ZERO).unwrap();
+        let mut recorder = RecordingTransport::new(Box::new(source), file.clone());
+        recorder.send(SERVER.to_string(), "GET /logo.png FROM replay".to_string(), 0).await.unwrap();
+        recorder.send(SERVER.to_string(), "GET / FROM replay\nRequest-Id: 4".to_string(), 0).await.unwrap();
+        let live = recorder.next_messages().await.unwrap();
+
+        let mut replayer = ReplayTransport::load(&file, Duration::ZERO).unwrap();
+        let _ = fs::remove_file(&file);
+        replayer.send(SERVER.to_string(), "GET /logo.png FROM replay".to_string(), 0).await.unwrap();
+        replayer.send(SERVER.to_string(), "GET / FROM replay\nRequest-Id: 4".to_string(), 0).await.unwrap();
+        assert_eq!(replayer.next_messages().await.unwrap(), live);
+    }
+}
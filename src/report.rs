@@ -0,0 +1,148 @@
+use nymview_protocol::{Request, REQUEST_ID_HEADER};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::storage::Storage;
+use crate::upload::PastedImage;
+
+pub const REPORTS_FILE: &str = "reports.json";
+
+// Body of a REPORT request about a page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub url: String,
+    // SHA-256 of the page as received, so the collector can match copies
+    pub content_sha256: String,
+    pub excerpt: String,
+    pub comment: String,
+    // Seconds since the Unix epoch
+    pub timestamp: u64,
+    // SHA-256 of the PNG uploaded along with the report, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_sha256: Option<String>,
+}
+
+impl Report {
+    pub fn new(url: &str, content: &str, excerpt: &str, comment: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            content_sha256: sha256_hex(content.as_bytes()),
+            excerpt: excerpt.trim().to_string(),
+            comment: comment.trim().to_string(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0),
+            image_sha256: None,
+        }
+    }
+
+    pub fn with_image(mut self, png: &[u8]) -> Self {
+        self.image_sha256 = Some(sha256_hex(png));
+        self
+    }
+
+    // Exactly what goes over the mixnet, for the preview. The request ID is
+    // only given for collectors that read headers.
+    pub fn payload(&self, client_address: &str, request_id: Option<u64>) -> Result<String, String> {
+        let body = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Could not serialize report: {}", e))?;
+        let mut request = Request::new("REPORT", &self.url, client_address);
+        if let Some(request_id) = request_id {
+            request = request.with_header(REQUEST_ID_HEADER, &request_id.to_string());
+        }
+        Ok(request.with_body(&body).to_string())
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// A report being written. Sending needs a preview of the current fields;
+// editing them discards it.
+#[derive(Debug, Clone, Default)]
+pub struct ReportDraft {
+    pub excerpt: String,
+    pub comment: String,
+    pub image: Option<PastedImage>,
+    // The report, the request ID reserved for it and its payload
+    pub preview: Option<(Report, u64, String)>,
+    // The image's upload, previewed along with the report
+    pub upload_preview: Option<(u64, String)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentReport {
+    pub collector: String,
+    pub report: Report,
+}
+
+// Local copy of every report sent
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SentReports {
+    pub reports: Vec<SentReport>,
+}
+
+impl SentReports {
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage.read(REPORTS_FILE)
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &dyn Storage) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Could not serialize sent reports: {}", e))?;
+        storage.write(REPORTS_FILE, &content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        Report {
+            url: "nym://server.addr@gateway/page".to_string(),
+            content_sha256: sha256_hex(b"# Page"),
+            excerpt: "wrong".to_string(),
+            comment: "typo".to_string(),
+            timestamp: 1_700_000_000,
+            image_sha256: None,
+        }
+    }
+
+    #[test]
+    fn payload_is_a_report_request() {
+        let report = report();
+        let cases = [
+            ("client.addr@gateway", None),
+            ("client.addr@gateway", Some(7)),
+            ("", None),
+            ("", Some(7)),
+        ];
+        for (client_address, request_id) in cases {
+            let payload = report.payload(client_address, request_id).unwrap();
+            let request = Request::parse(&payload).unwrap();
+            assert_eq!(request.method, "REPORT", "payload {:?}", payload);
+            assert_eq!(request.path, report.url, "payload {:?}", payload);
+            assert_eq!(request.reply_to, client_address, "payload {:?}", payload);
+            assert_eq!(request.request_id(), request_id, "payload {:?}", payload);
+            let sent: Report = serde_json::from_str(&request.body).unwrap();
+            assert_eq!(sent.comment, "typo", "payload {:?}", payload);
+            assert_eq!(sent.content_sha256, report.content_sha256, "payload {:?}", payload);
+        }
+    }
+
+    #[test]
+    fn anonymous_payloads_leave_out_the_client_address() {
+        let payload = report().payload("", Some(7)).unwrap();
+        assert!(payload.starts_with("REPORT nym://server.addr@gateway/page\nRequest-Id: 7\n\n{"), "{}", payload);
+        assert!(!payload.contains("FROM"), "{}", payload);
+
+        let payload = report().payload("client.addr@gateway", None).unwrap();
+        assert!(payload.starts_with("REPORT nym://server.addr@gateway/page FROM client.addr@gateway\n\n{"), "{}", payload);
+    }
+}
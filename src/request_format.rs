@@ -0,0 +1,174 @@
+use nymview_protocol::Request;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const PLACEHOLDERS: [&str; 3] = ["path", "addr", "id"];
+
+// How requests to a server are written on the wire. NymView servers read
+// the default format; other mixnet services may expect something else.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RequestFormat {
+    #[default]
+    NymView,
+    // Just the path, e.g. for services that reply by SURB
+    BarePath,
+    // {"path": …, "reply_to": …, "id": …}
+    JsonEnvelope,
+    // Template with {path}, {addr} and {id} placeholders
+    Custom(String),
+}
+
+impl RequestFormat {
+    pub const PRESETS: [RequestFormat; 3] = [
+        RequestFormat::NymView,
+        RequestFormat::BarePath,
+        RequestFormat::JsonEnvelope,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::NymView => "NymView (GET … FROM …)",
+            Self::BarePath => "Bare path",
+            Self::JsonEnvelope => "JSON envelope",
+            Self::Custom(_) => "Custom template",
+        }
+    }
+
+    // The message sent for `request`, which is in NymView's own format
+    pub fn render(&self, request: &Request, id: u64) -> String {
+        match self {
+            Self::NymView => request.to_string(),
+            Self::BarePath => request.path.clone(),
+            Self::JsonEnvelope => json!({
+                "path": request.path,
+                "reply_to": request.reply_to,
+                "id": id,
+            })
+            .to_string(),
+            Self::Custom(template) => fill_template(template, request, id),
+        }
+    }
+
+    // Whether the server learns where to send its answer from the message
+    // itself; otherwise it can only answer over reply SURBs
+    pub fn carries_address(&self) -> bool {
+        match self {
+            Self::NymView | Self::JsonEnvelope => true,
+            Self::BarePath => false,
+            Self::Custom(template) => template.contains("{addr}"),
+        }
+    }
+}
+
+// In one pass, so a path containing e.g. "{addr}" stays as written
+fn fill_template(template: &str, request: &Request, id: u64) -> String {
+    let mut filled = String::with_capacity(template.len() + request.path.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let value = after.find('}').and_then(|end| match &after[1..end] {
+            "path" => Some((request.path.clone(), end + 1)),
+            "addr" => Some((request.reply_to.clone(), end + 1)),
+            "id" => Some((id.to_string(), end + 1)),
+            _ => None,
+        });
+        match value {
+            Some((value, length)) => {
+                filled.push_str(&value);
+                rest = &after[length..];
+            }
+            None => {
+                filled.push('{');
+                rest = &after[1..];
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+// Custom templates may only use the known placeholders
+pub fn validate_template(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("The template is empty".to_string());
+    }
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            return Err("Unclosed \"{\" in the template".to_string());
+        };
+        let name = &after[..end];
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "Unknown placeholder {{{}}}; use {{path}}, {{addr}} or {{id}}",
+                name
+            ));
+        }
+        rest = &after[end + 1..];
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> Request {
+        Request::get("/docs/page.md", "client.addr@gateway").with_header("Request-Id", "7")
+    }
+
+    #[test]
+    fn each_preset_renders_its_format() {
+        let rendered: Vec<String> = RequestFormat::PRESETS.iter().map(|format| format.render(&request(), 7)).collect();
+        assert_eq!(rendered[0], "GET /docs/page.md FROM client.addr@gateway\nRequest-Id: 7");
+        assert_eq!(rendered[1], "/docs/page.md");
+        let envelope: serde_json::Value = serde_json::from_str(&rendered[2]).unwrap();
+        assert_eq!(envelope, json!({ "path": "/docs/page.md", "reply_to": "client.addr@gateway", "id": 7 }));
+
+        let carries: Vec<bool> = RequestFormat::PRESETS.iter().map(RequestFormat::carries_address).collect();
+        assert_eq!(carries, vec![true, false, true]);
+    }
+
+    #[test]
+    fn custom_templates_fill_their_placeholders() {
+        let cases = [
+            ("FETCH {path} REPLY {addr} #{id}", "FETCH /docs/page.md REPLY client.addr@gateway #7"),
+            ("{path}{path}", "/docs/page.md/docs/page.md"),
+            ("{ {path} }", "{ /docs/page.md }"),
+            ("{pa{path}", "{pa/docs/page.md"),
+            ("{unknown} {path", "{unknown} {path"),
+            ("no placeholders", "no placeholders"),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(RequestFormat::Custom(template.to_string()).render(&request(), 7), expected, "template {:?}", template);
+        }
+        assert!(RequestFormat::Custom("{path} {addr}".to_string()).carries_address());
+        assert!(!RequestFormat::Custom("{path} {id}".to_string()).carries_address());
+    }
+
+    #[test]
+    fn placeholders_in_the_path_are_not_filled() {
+        let request = Request::get("/{addr}/{id}", "client.addr@gateway");
+        assert_eq!(RequestFormat::Custom("GET {path}".to_string()).render(&request, 7), "GET /{addr}/{id}");
+    }
+
+    #[test]
+    fn templates_are_validated() {
+        for valid in ["{path}", "GET {path} FROM {addr} {id}", "plain"] {
+            assert_eq!(validate_template(valid), Ok(()), "template {:?}", valid);
+        }
+        let cases = [
+            ("", "The template is empty"),
+            ("   ", "The template is empty"),
+            ("GET {path", "Unclosed \"{\" in the template"),
+            ("GET {path} {host}", "Unknown placeholder {host}; use {path}, {addr} or {id}"),
+            ("GET {}", "Unknown placeholder {}; use {path}, {addr} or {id}"),
+            ("{PATH}", "Unknown placeholder {PATH}; use {path}, {addr} or {id}"),
+        ];
+        for (template, error) in cases {
+            assert_eq!(validate_template(template), Err(error.to_string()), "template {:?}", template);
+        }
+    }
+}
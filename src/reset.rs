@@ -0,0 +1,394 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::bookmarks;
+use crate::config;
+use crate::encrypted_storage;
+use crate::gateway_stats;
+use crate::history;
+use crate::mixnet_browser;
+use crate::replay;
+use crate::report;
+use crate::settings;
+use crate::storage::Storage;
+
+// What NymView keeps between runs, grouped the way the reset dialog lists it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Settings,
+    WelcomePage,
+    GatewayStats,
+    Reports,
+    Bookmarks,
+    History,
+    Diagnostics,
+    Recordings,
+    ClientIdentity,
+    EncryptionKey,
+}
+
+impl Category {
+    // Also the removal order. The key goes last so a failure before it
+    // leaves the remaining documents readable.
+    pub const ALL: [Category; 10] = [
+        Category::Settings,
+        Category::WelcomePage,
+        Category::GatewayStats,
+        Category::Reports,
+        Category::Bookmarks,
+        Category::History,
+        Category::Diagnostics,
+        Category::Recordings,
+        Category::ClientIdentity,
+        Category::EncryptionKey,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Settings => "Settings, request templates and server filters",
+            Self::WelcomePage => "Edited welcome page",
+            Self::GatewayStats => "Gateway latency history",
+            Self::Reports => "Sent page reports",
+            Self::Bookmarks => "Bookmarks",
+            Self::History => "Browsing history",
+            Self::Diagnostics => "Exported diagnostics",
+            Self::Recordings => "Recorded pages for replay",
+            Self::ClientIdentity => "Client keys for a persistent Nym address",
+            Self::EncryptionKey => "Encryption key",
+        }
+    }
+
+    // Documents read through Storage without the key are unreadable once
+    // it is gone, so removing the key takes these along
+    pub fn is_encrypted(self) -> bool {
+        !matches!(self, Self::Diagnostics | Self::Recordings | Self::ClientIdentity | Self::EncryptionKey)
+    }
+
+    pub fn documents(self) -> &'static [&'static str] {
+        match self {
+            Self::Settings => &[settings::SETTINGS_FILE],
+            Self::WelcomePage => &[mixnet_browser::WELCOME_FILE],
+            Self::GatewayStats => &[gateway_stats::STATS_FILE],
+            Self::Reports => &[report::REPORTS_FILE],
+            Self::Bookmarks => &[bookmarks::BOOKMARKS_FILE],
+            Self::History => &[history::HISTORY_FILE],
+            // Written straight to disk, see files()
+            Self::Diagnostics | Self::Recordings | Self::ClientIdentity => &[],
+            Self::EncryptionKey => &[encrypted_storage::KEY_FILE],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub category: Category,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+// Categories that currently have something stored, with their size
+pub fn inventory(storage: &dyn Storage) -> Vec<Artifact> {
+    Category::ALL
+        .into_iter()
+        .filter_map(|category| {
+            let sizes: Vec<u64> = match category {
+                Category::Diagnostics => diagnostics_files()
+                    .iter()
+                    .map(|path| fs::metadata(path).map_or(0, |metadata| metadata.len()))
+                    .collect(),
+                Category::Recordings => fs::metadata(replay::default_file())
+                    .map(|metadata| vec![metadata.len()])
+                    .unwrap_or_default(),
+                Category::ClientIdentity => file_sizes(&identity_dir()),
+                _ => category.documents()
+                    .iter()
+                    .filter_map(|name| storage.read(name))
+                    .map(|content| content.len() as u64)
+                    .collect(),
+            };
+            (!sizes.is_empty()).then(|| Artifact {
+                category,
+                files: sizes.len(),
+                bytes: sizes.iter().sum(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResetSummary {
+    pub removed: Vec<Category>,
+    pub failed: Option<(Category, String)>,
+}
+
+impl ResetSummary {
+    pub fn describe(&self) -> String {
+        let removed = if self.removed.is_empty() {
+            "Nothing was removed.".to_string()
+        } else {
+            let labels: Vec<&str> = self.removed.iter().map(|category| category.label()).collect();
+            format!("Removed: {}.", labels.join(", "))
+        };
+        match &self.failed {
+            Some((category, error)) => format!(
+                "{} Stopped at {}: {}. Everything after it was left as it was.",
+                removed,
+                category.label(),
+                error,
+            ),
+            None => removed,
+        }
+    }
+}
+
+// Removes the selected categories in Category::ALL order and stops at the
+// first failure, so the categories after it are left untouched
+pub fn wipe(storage: &dyn Storage, selected: &[Category]) -> ResetSummary {
+    let mut summary = ResetSummary::default();
+    for category in Category::ALL.into_iter().filter(|category| selected.contains(category)) {
+        let result = match category {
+            Category::Diagnostics => diagnostics_files().iter().try_for_each(|path| {
+                fs::remove_file(path).map_err(|e| format!("Could not remove {}: {}", path.display(), e))
+            }),
+            // Only the recording the settings make; files named on the
+            // command line belong to whoever named them
+            Category::Recordings => {
+                let file = replay::default_file();
+                match fs::remove_file(&file) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        Err(format!("Could not remove {}: {}", file.display(), e))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            Category::ClientIdentity => {
+                let dir = identity_dir();
+                match fs::remove_dir_all(&dir) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        Err(format!("Could not remove {}: {}", dir.display(), e))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            _ => category.documents().iter().try_for_each(|name| storage.remove(name)),
+        };
+        match result {
+            Ok(()) => summary.removed.push(category),
+            Err(e) => {
+                summary.failed = Some((category, e));
+                break;
+            }
+        }
+    }
+    summary
+}
+
+// "812 B", "3.4 KB", "1.2 MB"
+pub fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+fn identity_dir() -> PathBuf {
+    config::get_config_dir(mixnet_browser::CLIENT_IDENTITY_DIR)
+}
+
+// Sizes of all files below `dir`
+fn file_sizes(dir: &Path) -> Vec<u64> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .flat_map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => file_sizes(&entry.path()),
+            Ok(metadata) => vec![metadata.len()],
+            Err(_) => Vec::new(),
+        })
+        .collect()
+}
+
+fn diagnostics_files() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(config::get_config_dir("mixnet_browser")) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("diagnostics-") && name.ends_with(".json"))
+        })
+        .collect()
+}
+
+// State of the "Reset NymView…" window
+#[derive(Debug, Clone, Default)]
+pub struct ResetDialog {
+    pub artifacts: Vec<(Artifact, bool)>,
+    // Must read RESET before keys can be removed
+    pub confirmation: String,
+    pub summary: Option<String>,
+}
+
+impl ResetDialog {
+    pub fn new(storage: &dyn Storage) -> Self {
+        Self {
+            artifacts: inventory(storage).into_iter().map(|artifact| (artifact, false)).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn selected(&self) -> Vec<Category> {
+        let key_selected = self.is_selected(Category::EncryptionKey);
+        self.artifacts
+            .iter()
+            .filter(|(artifact, checked)| *checked || (key_selected && artifact.category.is_encrypted()))
+            .map(|(artifact, _)| artifact.category)
+            .collect()
+    }
+
+    pub fn is_selected(&self, category: Category) -> bool {
+        self.artifacts.iter().any(|(artifact, checked)| *checked && artifact.category == category)
+    }
+
+    // Keys cannot be recovered once deleted
+    pub fn needs_confirmation(&self) -> bool {
+        self.is_selected(Category::EncryptionKey) || self.is_selected(Category::ClientIdentity)
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        !self.needs_confirmation() || self.confirmation.trim() == "RESET"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    // Refuses to remove one document
+    struct FailingStorage {
+        inner: MemoryStorage,
+        fails: &'static str,
+    }
+
+    impl Storage for FailingStorage {
+        fn read(&self, name: &str) -> Option<String> {
+            self.inner.read(name)
+        }
+
+        fn write(&self, name: &str, content: &str) -> Result<(), String> {
+            self.inner.write(name, content)
+        }
+
+        fn remove(&self, name: &str) -> Result<(), String> {
+            if name == self.fails {
+                return Err("Permission denied".to_string());
+            }
+            self.inner.remove(name)
+        }
+
+        fn path(&self, name: &str) -> Option<PathBuf> {
+            self.inner.path(name)
+        }
+    }
+
+    // Only categories kept in Storage, so the tests never touch the disk
+    const STORED: [Category; 7] = [
+        Category::Settings,
+        Category::WelcomePage,
+        Category::GatewayStats,
+        Category::Reports,
+        Category::Bookmarks,
+        Category::History,
+        Category::EncryptionKey,
+    ];
+
+    fn filled(storage: &dyn Storage) {
+        for category in STORED {
+            for name in category.documents() {
+                storage.write(name, "{}").unwrap();
+            }
+        }
+    }
+
+    fn present(storage: &dyn Storage) -> Vec<Category> {
+        STORED
+            .into_iter()
+            .filter(|category| category.documents().iter().all(|name| storage.read(name).is_some()))
+            .collect()
+    }
+
+    #[test]
+    fn only_the_selected_categories_are_removed_in_order() {
+        let storage = MemoryStorage::default();
+        filled(&storage);
+        let summary = wipe(&storage, &[Category::History, Category::Settings]);
+
+        assert_eq!(summary.removed, vec![Category::Settings, Category::History]);
+        assert_eq!(summary.failed, None);
+        assert_eq!(present(&storage), vec![
+            Category::WelcomePage,
+            Category::GatewayStats,
+            Category::Reports,
+            Category::Bookmarks,
+            Category::EncryptionKey,
+        ]);
+    }
+
+    #[test]
+    fn a_failure_stops_the_reset_and_leaves_the_rest() {
+        let storage = FailingStorage { inner: MemoryStorage::default(), fails: bookmarks::BOOKMARKS_FILE };
+        filled(&storage);
+        let summary = wipe(&storage, &[Category::Settings, Category::Bookmarks, Category::History, Category::EncryptionKey]);
+
+        assert_eq!(summary.removed, vec![Category::Settings]);
+        assert_eq!(summary.failed, Some((Category::Bookmarks, "Permission denied".to_string())));
+        // The key outlives the failure, so what is left stays readable
+        assert_eq!(present(&storage), vec![
+            Category::WelcomePage,
+            Category::GatewayStats,
+            Category::Reports,
+            Category::Bookmarks,
+            Category::History,
+            Category::EncryptionKey,
+        ]);
+        assert_eq!(
+            summary.describe(),
+            "Removed: Settings, request templates and server filters. Stopped at Bookmarks: Permission denied. \
+             Everything after it was left as it was."
+        );
+    }
+
+    #[test]
+    fn removing_the_key_takes_the_encrypted_documents_along() {
+        let storage = MemoryStorage::default();
+        filled(&storage);
+        let mut dialog = ResetDialog::new(&storage);
+        for (artifact, checked) in &mut dialog.artifacts {
+            *checked = artifact.category == Category::Bookmarks;
+        }
+        assert_eq!(dialog.selected(), vec![Category::Bookmarks]);
+        assert!(dialog.is_confirmed());
+
+        for (artifact, checked) in &mut dialog.artifacts {
+            *checked = artifact.category == Category::EncryptionKey;
+        }
+        let selected = dialog.selected();
+        assert!(STORED.iter().all(|category| selected.contains(category)), "{:?}", selected);
+        assert!(!dialog.is_confirmed());
+        dialog.confirmation = " RESET ".to_string();
+        assert!(dialog.is_confirmed());
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(3482), "3.4 KB");
+        assert_eq!(format_size(1_258_291), "1.2 MB");
+    }
+}
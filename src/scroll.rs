@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ScrollPolicy {
+    // Reload and back/forward always start at the top
+    AlwaysTop,
+    // Return to where the page was left
+    #[default]
+    Restore,
+    // Restore only if the page was left recently
+    RestoreIfRecent,
+}
+
+// Scroll offsets of pages the user has left, keyed by URL. Shared by
+// reload and back/forward so both honour the same policy.
+#[derive(Debug, Clone, Default)]
+pub struct ScrollMemory {
+    offsets: HashMap<String, (f32, Instant)>,
+}
+
+impl ScrollMemory {
+    pub fn remember(&mut self, url: &str, offset: f32) {
+        if url.is_empty() {
+            return;
+        }
+        if offset > 0.0 {
+            self.offsets.insert(url.to_string(), (offset, Instant::now()));
+        } else {
+            self.offsets.remove(url);
+        }
+    }
+
+    pub fn offset_for(&self, url: &str, policy: ScrollPolicy, recent: Duration) -> Option<f32> {
+        let (offset, left_at) = self.offsets.get(url)?;
+        match policy {
+            ScrollPolicy::AlwaysTop => None,
+            ScrollPolicy::Restore => Some(*offset),
+            ScrollPolicy::RestoreIfRecent => (left_at.elapsed() <= recent).then_some(*offset),
+        }
+    }
+}
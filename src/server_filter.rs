@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FilterMode {
+    #[default]
+    Off,
+    // Block servers on the deny list
+    Deny,
+    // Refuse everything not on the allow list
+    AllowOnly,
+}
+
+// Connection-wide server allow/deny list, checked for every outgoing request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerFilter {
+    pub mode: FilterMode,
+    pub allowed: Vec<String>,
+    pub denied: Vec<String>,
+}
+
+impl ServerFilter {
+    pub fn check(&self, server: &str) -> Result<(), String> {
+        let server = server.trim();
+        match self.mode {
+            FilterMode::Off => Ok(()),
+            FilterMode::Deny if self.denied.iter().any(|s| s == server) => {
+                Err("Blocked: this server is on your deny list".to_string())
+            }
+            FilterMode::Deny => Ok(()),
+            FilterMode::AllowOnly if self.allowed.iter().any(|s| s == server) => Ok(()),
+            FilterMode::AllowOnly => {
+                Err("Blocked: only servers on your allow list may be contacted".to_string())
+            }
+        }
+    }
+
+    // The list edited for the current mode
+    pub fn active_list_mut(&mut self) -> Option<&mut Vec<String>> {
+        self.list_mut(self.mode)
+    }
+
+    pub fn list_mut(&mut self, mode: FilterMode) -> Option<&mut Vec<String>> {
+        match mode {
+            FilterMode::Off => None,
+            FilterMode::Deny => Some(&mut self.denied),
+            FilterMode::AllowOnly => Some(&mut self.allowed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(mode: FilterMode) -> ServerFilter {
+        ServerFilter {
+            mode,
+            allowed: vec!["allowed.addr@gateway".to_string()],
+            denied: vec!["denied.addr@gateway".to_string()],
+        }
+    }
+
+    #[test]
+    fn off_lets_everything_through() {
+        let filter = filter(FilterMode::Off);
+        assert!(filter.check("denied.addr@gateway").is_ok());
+        assert!(filter.check("other.addr@gateway").is_ok());
+    }
+
+    #[test]
+    fn deny_blocks_only_listed_servers() {
+        let filter = filter(FilterMode::Deny);
+        assert!(filter.check("denied.addr@gateway").unwrap_err().contains("deny list"));
+        assert!(filter.check("  denied.addr@gateway ").is_err());
+        assert!(filter.check("other.addr@gateway").is_ok());
+        assert!(filter.check("allowed.addr@gateway").is_ok());
+    }
+
+    #[test]
+    fn allow_only_blocks_everything_unlisted() {
+        let filter = filter(FilterMode::AllowOnly);
+        assert!(filter.check("allowed.addr@gateway").is_ok());
+        assert!(filter.check(" allowed.addr@gateway").is_ok());
+        assert!(filter.check("other.addr@gateway").unwrap_err().contains("allow list"));
+        assert!(filter.check("denied.addr@gateway").is_err());
+        assert!(filter.check("").is_err());
+    }
+
+    #[test]
+    fn each_mode_edits_its_own_list() {
+        let mut filter = filter(FilterMode::Off);
+        assert!(filter.active_list_mut().is_none());
+        filter.mode = FilterMode::Deny;
+        assert_eq!(filter.active_list_mut().unwrap(), &vec!["denied.addr@gateway".to_string()]);
+        filter.mode = FilterMode::AllowOnly;
+        assert_eq!(filter.active_list_mut().unwrap(), &vec!["allowed.addr@gateway".to_string()]);
+    }
+}
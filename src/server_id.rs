@@ -0,0 +1,105 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+// A server address shared by everything that refers to it. Nym addresses
+// are ~130 characters and history, requests and metrics repeat the same
+// few of them, so they hold a reference instead of their own copy.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServerId(Arc<str>);
+
+impl Deref for ServerId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+// Lets maps keyed by ServerId be looked up with a plain &str
+impl Borrow<str> for ServerId {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ServerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// Stored as the full address, so saved data does not depend on the table
+impl Serialize for ServerId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ServerId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|address| ServerId(address.into()))
+    }
+}
+
+// Hands out one shared ServerId per distinct address
+#[derive(Debug, Clone, Default)]
+pub struct ServerIds {
+    ids: HashSet<ServerId>,
+}
+
+impl ServerIds {
+    pub fn intern(&mut self, address: &str) -> ServerId {
+        if let Some(id) = self.ids.get(address) {
+            return id.clone();
+        }
+        let id = ServerId(address.into());
+        self.ids.insert(id.clone());
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn one_address_is_stored_once() {
+        let mut ids = ServerIds::default();
+        let first = ids.intern("a.b@gateway");
+        let again = ids.intern(&String::from("a.b@gateway"));
+        let other = ids.intern("c.d@gateway");
+        assert!(Arc::ptr_eq(&first.0, &again.0));
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert_eq!(&*first, "a.b@gateway");
+    }
+
+    #[test]
+    fn serialized_as_the_plain_address() {
+        let mut ids = ServerIds::default();
+        let id = ids.intern("a.b@gateway");
+        let json = serde_json::to_string(&vec![id.clone(), id.clone()]).unwrap();
+        assert_eq!(json, r#"["a.b@gateway","a.b@gateway"]"#);
+
+        // Loaded ids are separate copies until interned again
+        let loaded: Vec<ServerId> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, vec![id.clone(), id.clone()]);
+        assert!(!Arc::ptr_eq(&loaded[0].0, &id.0));
+        let interned: Vec<ServerId> = loaded.iter().map(|loaded| ids.intern(loaded)).collect();
+        assert!(interned.iter().all(|interned| Arc::ptr_eq(&interned.0, &id.0)));
+    }
+
+    #[test]
+    fn maps_are_looked_up_by_str() {
+        let mut ids = ServerIds::default();
+        let mut latencies = HashMap::new();
+        latencies.insert(ids.intern("a.b@gateway"), 3);
+        assert_eq!(latencies.get("a.b@gateway"), Some(&3));
+        assert_eq!(latencies.get("c.d@gateway"), None);
+    }
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::request_format::RequestFormat;
+use crate::scroll::ScrollPolicy;
+use crate::server_filter::ServerFilter;
+use crate::storage::Storage;
+use crate::templates::RequestTemplate;
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+pub const DEFAULT_WINDOW_SIZE: [f32; 2] = [700.0, 800.0];
+
+// Last window placement, restored at startup
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub maximized: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Replace spinners and other animations with static text
+    pub reduce_motion: bool,
+    // Capability string sent as the "Client:" request header; off by default
+    // and only ever sent to servers that read headers
+    pub send_client_id: bool,
+    pub client_id: String,
+    // Ask servers for zstd-compressed replies (Accept-Encoding)
+    pub accept_compression: bool,
+    // Load a page's images from other servers without asking, which tells
+    // those servers which page was visited
+    pub cross_server_images: bool,
+    // Path opened when navigating to a bare server address, keyed by server
+    pub landing_paths: HashMap<String, String>,
+    // Quick-action request templates, keyed by server
+    pub request_templates: HashMap<String, Vec<RequestTemplate>>,
+    // Wire format for servers that are not NymView servers, keyed by server
+    pub request_formats: HashMap<String, RequestFormat>,
+    // Servers known to read header lines (request IDs, compression, client
+    // identifier). Everyone else gets the bare request line, since older
+    // servers take everything after FROM as the reply address.
+    pub header_servers: HashSet<String>,
+    // Select the whole address instead of placing the cursor on focus
+    pub select_address_on_click: bool,
+    pub select_address_on_shortcut: bool,
+    pub window: Option<WindowGeometry>,
+    pub server_filter: ServerFilter,
+    // Content appearance, applied live to rendered pages. Link underlines
+    // cannot be switched: links are drawn by egui's Link and Hyperlink
+    // widgets, which underline them only while hovered or focused and have
+    // no style setting for it.
+    pub content_max_width: Option<f32>,
+    pub heading_scale: f32,
+    pub paragraph_spacing: f32,
+    pub content_font: Option<String>,
+    // Name of a bundled theme or path to a TOML theme file
+    pub content_theme: String,
+    // Strip trailing whitespace (and with it stray hard breaks) from responses
+    pub trim_trailing_whitespace: bool,
+    // Where reload and back/forward leave the page scrolled
+    pub scroll_policy: ScrollPolicy,
+    pub scroll_restore_minutes: u64,
+    // Thin bar along the top of long pages showing how far they are read
+    pub reading_progress: bool,
+    pub reading_progress_percent: bool,
+    // Suggest a new gateway once the median round trip exceeds this
+    pub slow_gateway_threshold_secs: u64,
+    // Answered pages saved for back/forward in the next session; 0 saves none
+    pub history_limit: usize,
+    // Also save the pages themselves, so back/forward into an earlier
+    // session works without asking the servers again
+    pub history_keep_pages: bool,
+    // Query parameters removed from followed links before they are sent
+    pub stripped_query_params: Vec<String>,
+    // Where "Report page…" sends reports; empty disables reporting
+    pub report_address: String,
+    // Stay disconnected, also across restarts, until the user goes online
+    pub offline_mode: bool,
+    // Reuse the client keys in the identity directory instead of a new
+    // ephemeral address per connection
+    pub persistent_identity: bool,
+    // Leave FROM out of requests; servers answer over reply SURBs
+    pub anonymous_requests: bool,
+    // Write every reply, with the request it answers, to the replay file
+    pub record_session: bool,
+    // Answer requests from the replay file instead of the mixnet
+    pub replay_session: bool,
+    // How long each replayed reply is held back
+    pub replay_latency_ms: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            reduce_motion: false,
+            send_client_id: false,
+            client_id: default_client_id(),
+            accept_compression: true,
+            cross_server_images: false,
+            landing_paths: HashMap::new(),
+            request_templates: HashMap::new(),
+            request_formats: HashMap::new(),
+            header_servers: HashSet::new(),
+            select_address_on_click: false,
+            select_address_on_shortcut: true,
+            window: None,
+            server_filter: ServerFilter::default(),
+            content_max_width: None,
+            heading_scale: 1.0,
+            paragraph_spacing: 1.0,
+            content_font: None,
+            content_theme: "default".to_string(),
+            trim_trailing_whitespace: false,
+            scroll_policy: ScrollPolicy::default(),
+            scroll_restore_minutes: 10,
+            reading_progress: true,
+            reading_progress_percent: false,
+            slow_gateway_threshold_secs: 20,
+            history_limit: 100,
+            history_keep_pages: false,
+            stripped_query_params: default_stripped_query_params(),
+            report_address: String::new(),
+            offline_mode: false,
+            persistent_identity: false,
+            anonymous_requests: false,
+            record_session: false,
+            replay_session: false,
+            replay_latency_ms: 1000,
+        }
+    }
+}
+
+pub fn default_client_id() -> String {
+    format!("NymView/{}", env!("CARGO_PKG_VERSION"))
+}
+
+// Parameters that only tell readers apart; none of them select content
+pub fn default_stripped_query_params() -> Vec<String> {
+    [
+        "utm_source", "utm_medium", "utm_campaign", "utm_term", "utm_content",
+        "fbclid", "gclid", "mc_cid", "mc_eid",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
+}
+
+impl Settings {
+    pub fn load(storage: &dyn Storage) -> Self {
+        match storage.read(SETTINGS_FILE) {
+            Some(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                eprintln!("Invalid settings file, using defaults: {}", e);
+                Self::default()
+            }),
+            None => Self::default(),
+        }
+    }
+
+    pub fn save(&self, storage: &dyn Storage) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Could not serialize settings: {}", e))?;
+        storage.write(SETTINGS_FILE, &content)
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::config;
+
+// Where the browser keeps its named documents (settings.json, welcome.md, ...).
+// Every read and write of persistent state goes through this.
+pub trait Storage: Send + Sync {
+    // None when the document does not exist or cannot be read
+    fn read(&self, name: &str) -> Option<String>;
+    // Like read, but a document that exists and cannot be read is an error
+    // rather than missing
+    fn read_checked(&self, name: &str) -> Result<Option<String>, String> {
+        Ok(self.read(name))
+    }
+    fn write(&self, name: &str, content: &str) -> Result<(), String>;
+    // Removing a document that does not exist is not an error
+    fn remove(&self, name: &str) -> Result<(), String>;
+    // Moves a document as it is stored, readable or not. Renaming one that
+    // does not exist is not an error.
+    fn rename(&self, from: &str, to: &str) -> Result<(), String> {
+        if let Some(content) = self.read(from) {
+            self.write(to, &content)?;
+        }
+        self.remove(from)
+    }
+    // Location on disk, for documents the user opens in other programs
+    fn path(&self, name: &str) -> Option<PathBuf>;
+
+    fn is_encrypted(&self) -> bool {
+        false
+    }
+}
+
+// Files in a directory, by default the browser's config directory
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+impl Default for FileStorage {
+    fn default() -> Self {
+        Self::new(config::get_config_dir("mixnet_browser"))
+    }
+}
+
+impl Storage for FileStorage {
+    fn read(&self, name: &str) -> Option<String> {
+        fs::read_to_string(self.dir.join(name)).ok()
+    }
+
+    fn write(&self, name: &str, content: &str) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Could not create config directory: {}", e))?;
+        write_atomically(&self.dir.join(name), content.as_bytes())
+            .map_err(|e| format!("Could not save {}: {}", name, e))
+    }
+
+    fn remove(&self, name: &str) -> Result<(), String> {
+        match fs::remove_file(self.dir.join(name)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Could not remove {}: {}", name, e))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), String> {
+        match fs::rename(self.dir.join(from), self.dir.join(to)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Could not rename {}: {}", from, e))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        Some(self.dir.join(name))
+    }
+}
+
+// Writes `<path>.tmp` and renames it over `path`, so a crash or a full
+// disk leaves the old file in place rather than a truncated one
+pub fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+    let written = fs::File::create(&temporary)
+        .and_then(|mut file| file.write_all(bytes).and_then(|_| file.sync_all()))
+        .and_then(|_| fs::rename(&temporary, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+    written
+}
+
+// Keeps everything in memory for the lifetime of the process; nothing
+// touches the disk
+#[derive(Default)]
+pub struct MemoryStorage {
+    documents: Mutex<HashMap<String, String>>,
+}
+
+impl Storage for MemoryStorage {
+    fn read(&self, name: &str) -> Option<String> {
+        self.documents.lock().unwrap().get(name).cloned()
+    }
+
+    fn write(&self, name: &str, content: &str) -> Result<(), String> {
+        self.documents.lock().unwrap().insert(name.to_string(), content.to_string());
+        Ok(())
+    }
+
+    fn remove(&self, name: &str) -> Result<(), String> {
+        self.documents.lock().unwrap().remove(name);
+        Ok(())
+    }
+
+    fn path(&self, _name: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_replace_the_file_without_leaving_a_temporary_one() {
+        let dir = std::env::temp_dir().join(format!("nymview-storage-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let storage = FileStorage::new(dir.clone());
+        storage.write("settings.json", "{\"old\": true}").unwrap();
+        storage.write("settings.json", "{}").unwrap();
+
+        let names: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        let content = storage.read("settings.json");
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(content.as_deref(), Some("{}"));
+        assert_eq!(names, ["settings.json"]);
+    }
+
+    #[test]
+    fn a_failed_write_keeps_the_old_file() {
+        let dir = std::env::temp_dir().join(format!("nymview-storage-failed-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let storage = FileStorage::new(dir.clone());
+        storage.write("history.json", "[1]").unwrap();
+        // The temporary file cannot be created where a directory is
+        fs::create_dir(dir.join("history.json.tmp")).unwrap();
+
+        let written = storage.write("history.json", "[");
+        let content = storage.read("history.json");
+        let _ = fs::remove_dir_all(&dir);
+        assert!(written.is_err());
+        assert_eq!(content.as_deref(), Some("[1]"));
+    }
+}
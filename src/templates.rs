@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Reusable request path with {variable} placeholders, e.g. "search?q={query}"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestTemplate {
+    pub name: String,
+    pub path: String,
+}
+
+impl RequestTemplate {
+    // Placeholder names in order of first appearance
+    pub fn variables(&self) -> Vec<String> {
+        let mut variables: Vec<String> = Vec::new();
+        let mut rest = self.path.as_str();
+        while let Some(start) = rest.find('{') {
+            let after = &rest[start + 1..];
+            let Some(end) = after.find('}') else {
+                break;
+            };
+            let name = after[..end].trim();
+            if !name.is_empty() && !variables.iter().any(|v| v == name) {
+                variables.push(name.to_string());
+            }
+            rest = &after[end + 1..];
+        }
+        variables
+    }
+
+    pub fn expand(&self, values: &HashMap<String, String>) -> String {
+        let mut path = self.path.clone();
+        for variable in self.variables() {
+            let value = values.get(&variable).map(String::as_str).unwrap_or("");
+            path = path.replace(&format!("{{{}}}", variable), value.trim());
+        }
+        path
+    }
+}
+
+// Template picked from the quick actions, waiting for its variable values
+pub struct PendingTemplate {
+    pub template: RequestTemplate,
+    pub values: HashMap<String, String>,
+}
+
+impl PendingTemplate {
+    pub fn new(template: RequestTemplate) -> Self {
+        let values = template
+            .variables()
+            .into_iter()
+            .map(|variable| (variable, String::new()))
+            .collect();
+        Self { template, values }
+    }
+}
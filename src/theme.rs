@@ -0,0 +1,170 @@
+use egui::Color32;
+use toml_edit::{DocumentMut, Item, Value};
+
+// Content theme read from a small TOML file. Every key is optional:
+//
+//   [headings]
+//   color = "#1e3a8a"
+//   scale = 1.2
+//
+//   [links]
+//   color = "#2563eb"
+//
+//   [code]
+//   background = "#f3f4f6"
+//
+//   [blockquote]
+//   bar = "#9ca3af"
+//
+//   [table]
+//   stripe = "#f9fafb"
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Theme {
+    pub heading_color: Option<Color32>,
+    pub heading_scale: Option<f32>,
+    pub link_color: Option<Color32>,
+    pub code_background: Option<Color32>,
+    pub blockquote_bar: Option<Color32>,
+    pub table_stripe: Option<Color32>,
+}
+
+// Selectable by name in settings; anything else is a file path
+pub const BUNDLED: [(&str, &str); 2] = [
+    ("default", ""),
+    ("high-contrast", HIGH_CONTRAST),
+];
+
+// For the light visuals the app runs with: body and code text stay dark,
+// so every background here is light
+const HIGH_CONTRAST: &str = r##"
+[headings]
+color = "#000000"
+scale = 1.25
+
+[links]
+color = "#0000c0"
+
+[code]
+background = "#e6e6e6"
+
+[blockquote]
+bar = "#000000"
+
+[table]
+stripe = "#d9d9d9"
+"##;
+
+const KEYS: [(&str, &str); 6] = [
+    ("headings", "color"),
+    ("headings", "scale"),
+    ("links", "color"),
+    ("code", "background"),
+    ("blockquote", "bar"),
+    ("table", "stripe"),
+];
+
+impl Theme {
+    pub fn bundled(name: &str) -> Option<Self> {
+        BUNDLED
+            .iter()
+            .find(|(bundled, _)| *bundled == name)
+            .map(|(_, source)| Self::parse(source).expect("bundled theme is valid"))
+    }
+
+    // Errors name every offending key, e.g. "links.colour: unknown key"
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let document: DocumentMut = source.parse()
+            .map_err(|e: toml_edit::TomlError| format!("Not valid TOML: {}", e.message()))?;
+
+        let mut problems = Vec::new();
+        for (section, item) in document.iter() {
+            match item.as_table_like() {
+                Some(table) => {
+                    for (key, _) in table.iter() {
+                        if !KEYS.contains(&(section, key)) {
+                            problems.push(format!("{}.{}: unknown key", section, key));
+                        }
+                    }
+                }
+                None => problems.push(format!("{}: expected a [{}] section", section, section)),
+            }
+        }
+
+        let mut color = |section: &str, key: &str| {
+            let value = document.get(section).and_then(|table| table.get(key))?;
+            let parsed = value_str(value).and_then(parse_color);
+            if parsed.is_none() {
+                problems.push(format!("{}.{}: expected a color like \"#1e3a8a\"", section, key));
+            }
+            parsed
+        };
+        let mut theme = Self {
+            heading_color: color("headings", "color"),
+            heading_scale: None,
+            link_color: color("links", "color"),
+            code_background: color("code", "background"),
+            blockquote_bar: color("blockquote", "bar"),
+            table_stripe: color("table", "stripe"),
+        };
+
+        if let Some(value) = document.get("headings").and_then(|table| table.get("scale")) {
+            let scale = value.as_value().and_then(|value| match value {
+                Value::Float(scale) => Some(*scale.value() as f32),
+                Value::Integer(scale) => Some(*scale.value() as f32),
+                _ => None,
+            });
+            match scale {
+                Some(scale) if (0.5..=3.0).contains(&scale) => theme.heading_scale = Some(scale),
+                _ => problems.push("headings.scale: expected a number from 0.5 to 3".to_string()),
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(theme)
+        } else {
+            Err(problems.join("\n"))
+        }
+    }
+
+    // Overrides the visuals egui_commonmark draws with. Headings are drawn
+    // as strong text, so their color also applies to bold text.
+    pub fn apply(&self, style: &mut egui::Style) {
+        let visuals = &mut style.visuals;
+        if let Some(color) = self.heading_color {
+            visuals.widgets.active.fg_stroke.color = color;
+        }
+        if let Some(color) = self.link_color {
+            visuals.hyperlink_color = color;
+        }
+        if let Some(color) = self.code_background {
+            visuals.code_bg_color = color;
+            visuals.extreme_bg_color = color;
+        }
+        if let Some(color) = self.blockquote_bar {
+            visuals.widgets.noninteractive.bg_stroke.color = color;
+        }
+        if let Some(color) = self.table_stripe {
+            visuals.faint_bg_color = color;
+        }
+        if let Some(scale) = self.heading_scale {
+            if let Some(font_id) = style.text_styles.get_mut(&egui::TextStyle::Heading) {
+                font_id.size *= scale;
+            }
+        }
+    }
+}
+
+fn value_str(item: &Item) -> Option<&str> {
+    item.as_value().and_then(Value::as_str)
+}
+
+// "#rrggbb" or "#rrggbbaa"
+fn parse_color(text: &str) -> Option<Color32> {
+    let hex = text.strip_prefix('#')?;
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Some(Color32::from_rgba_unmultiplied(channel(0)?, channel(2)?, channel(4)?, alpha))
+}
@@ -0,0 +1,122 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Timestamps further ahead than this come from a skewed clock rather than
+// rounding, and are called out in details()
+const SKEW_TOLERANCE: Duration = Duration::from_secs(60);
+
+// "just now", "5 minutes ago", "3 days ago"
+pub fn relative(time: SystemTime) -> String {
+    // Unset timestamps deserialize as the epoch
+    if time <= UNIX_EPOCH {
+        return "at an unknown time".to_string();
+    }
+    // A time in the future (clock changes) reads as just now
+    let seconds = SystemTime::now()
+        .duration_since(time)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+
+    let (value, unit) = match seconds {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86_399 => (seconds / 3600, "hour"),
+        86_400..=31_535_999 => (seconds / 86_400, "day"),
+        _ => (seconds / 31_536_000, "year"),
+    };
+    format!("{} {}{} ago", value, unit, if value == 1 { "" } else { "s" })
+}
+
+// "2026-10-16 14:03 UTC"
+pub fn absolute(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or(0);
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let minutes = seconds % 86_400 / 60;
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, minutes / 60, minutes % 60)
+}
+
+// Hover text for a timestamp. One lying in the future shows both clocks,
+// since relative() reads it as "just now".
+pub fn details(time: SystemTime) -> String {
+    let now = SystemTime::now();
+    match time.duration_since(now) {
+        Ok(ahead) if ahead > SKEW_TOLERANCE => format!(
+            "{}, {} minutes ahead of this computer's clock ({})",
+            absolute(time),
+            ahead.as_secs() / 60,
+            absolute(now),
+        ),
+        _ => absolute(time),
+    }
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian date (Howard Hinnant's
+// days_from_civil inverse)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ago(seconds: u64) -> SystemTime {
+        SystemTime::now() - Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn relative_times() {
+        assert_eq!(relative(ago(0)), "just now");
+        assert_eq!(relative(ago(59)), "just now");
+        assert_eq!(relative(ago(60)), "1 minute ago");
+        assert_eq!(relative(ago(2 * 3600 + 5)), "2 hours ago");
+        assert_eq!(relative(ago(86_400)), "1 day ago");
+        assert_eq!(relative(ago(364 * 86_400)), "364 days ago");
+        assert_eq!(relative(ago(50 * 31_536_000 + 60)), "50 years ago");
+    }
+
+    #[test]
+    fn future_and_unset_times() {
+        assert_eq!(relative(SystemTime::now() + Duration::from_secs(3600)), "just now");
+        assert_eq!(relative(UNIX_EPOCH), "at an unknown time");
+        assert_eq!(relative(UNIX_EPOCH - Duration::from_secs(1)), "at an unknown time");
+        assert_eq!(relative(UNIX_EPOCH + Duration::from_secs(1)), format!(
+            "{} years ago",
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() / 31_536_000
+        ));
+    }
+
+    #[test]
+    fn absolute_dates() {
+        let at = |seconds: u64| absolute(UNIX_EPOCH + Duration::from_secs(seconds));
+        assert_eq!(at(0), "1970-01-01 00:00 UTC");
+        assert_eq!(at(951_868_800), "2000-03-01 00:00 UTC");
+        assert_eq!(at(1_709_251_140), "2024-02-29 23:59 UTC");
+        assert_eq!(at(1_792_159_439), "2026-10-16 14:03 UTC");
+        // Times before the epoch cannot be shown and read as the epoch
+        assert_eq!(absolute(UNIX_EPOCH - Duration::from_secs(86_400)), "1970-01-01 00:00 UTC");
+        assert_eq!(civil_from_days(-25_202), (1901, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn details_call_out_a_clock_ahead() {
+        let skewed = SystemTime::now() + Duration::from_secs(10 * 60 + 30);
+        let text = details(skewed);
+        assert!(text.starts_with(&absolute(skewed)), "{}", text);
+        assert!(text.contains("10 minutes ahead of this computer's clock"), "{}", text);
+
+        let close = SystemTime::now() + Duration::from_secs(30);
+        assert_eq!(details(close), absolute(close));
+        let past = ago(3600);
+        assert_eq!(details(past), absolute(past));
+    }
+}
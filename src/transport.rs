@@ -0,0 +1,374 @@
+use nym_sdk::mixnet;
+use nym_sdk::mixnet::MixnetMessageSender;
+use std::fmt;
+use std::io::ErrorKind;
+use std::future::Future;
+use std::pin::Pin;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+// What the browser's mixnet task needs from a connection. Implemented by
+// the real MixnetClient and, in tests or behind the "memory-transport"
+// feature, by an in-memory fake.
+pub trait Transport: Send {
+    fn address(&self) -> String;
+    // Resolves with the next batch of incoming messages, None once closed.
+    // Messages are passed on as bytes since replies may carry binary data.
+    fn next_messages(&mut self) -> BoxFuture<'_, Option<Vec<Vec<u8>>>>;
+    // With reply_surbs > 0 the message goes out without our address and the
+    // recipient answers over the included reply SURBs
+    fn send(&mut self, recipient: String, message: String, reply_surbs: u32) -> BoxFuture<'_, Result<(), SendFailure>>;
+    fn disconnect(self: Box<Self>) -> BoxFuture<'static, ()>;
+}
+
+impl Transport for mixnet::MixnetClient {
+    fn address(&self) -> String {
+        self.nym_address().to_string()
+    }
+
+    fn next_messages(&mut self) -> BoxFuture<'_, Option<Vec<Vec<u8>>>> {
+        Box::pin(async move {
+            self.wait_for_messages().await.map(|messages| {
+                messages.into_iter().map(|received| received.message).collect()
+            })
+        })
+    }
+
+    fn send(&mut self, recipient: String, message: String, reply_surbs: u32) -> BoxFuture<'_, Result<(), SendFailure>> {
+        Box::pin(async move {
+            let recipient = recipient
+                .parse::<mixnet::Recipient>()
+                .map_err(|e| SendFailure::InvalidRecipient(e.to_string()))?;
+            let sent = if reply_surbs > 0 {
+                self.send_message(recipient, message, mixnet::IncludedSurbs::new(reply_surbs)).await
+            } else {
+                self.send_plain_message(recipient, message).await
+            };
+            sent.map_err(|e| SendFailure::classify(&e))
+        })
+    }
+
+    fn disconnect(self: Box<Self>) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            (*self).disconnect().await;
+        })
+    }
+}
+
+// Why a send did not go out. Only Transient failures are worth retrying;
+// the others fail the same way until something changes on our side.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SendFailure {
+    // Malformed or unknown recipient address
+    InvalidRecipient(String),
+    // Gateways meter bandwidth; once it runs out every send fails until
+    // the allowance is topped up
+    BandwidthExhausted(String),
+    // The client lost its gateway connection or is shutting down
+    NotConnected(String),
+    // Queue or lane pressure inside the client
+    Transient(String),
+    // Anything not recognised. Not retried, since the same request could
+    // fail the same way every time.
+    Other(String),
+}
+
+impl SendFailure {
+    pub fn classify(error: &nym_sdk::Error) -> Self {
+        match error {
+            // The client's input channel is gone, so it has shut down
+            nym_sdk::Error::MessageSendingFailure => Self::NotConnected(error.to_string()),
+            nym_sdk::Error::IoError(io) => match io.kind() {
+                ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted => Self::Transient(error.to_string()),
+                ErrorKind::NotConnected
+                | ErrorKind::BrokenPipe
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted => Self::NotConnected(error.to_string()),
+                _ => Self::Other(error.to_string()),
+            },
+            _ => Self::classify_message(&error.to_string()),
+        }
+    }
+
+    // For SDK errors without a variant of their own, which are told apart
+    // only by their message text
+    fn classify_message(error: &str) -> Self {
+        let lower = error.to_lowercase();
+        let mentions = |hints: &[&str]| hints.iter().any(|hint| lower.contains(hint));
+
+        if lower.contains("bandwidth")
+            && mentions(&["exhaust", "out of", "not enough", "insufficient", "no remaining"]) {
+            Self::BandwidthExhausted(error.to_string())
+        } else if mentions(&["recipient", "malformed", "invalid address"]) {
+            Self::InvalidRecipient(error.to_string())
+        } else if mentions(&["not connected", "disconnected", "shutdown", "shut down", "shutting down", "channel closed", "connection closed"]) {
+            Self::NotConnected(error.to_string())
+        } else if mentions(&["queue", "lane", "full", "busy", "timeout", "timed out", "try again", "temporar"]) {
+            Self::Transient(error.to_string())
+        } else {
+            Self::Other(error.to_string())
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Transient(_))
+    }
+}
+
+impl fmt::Display for SendFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidRecipient(e) => write!(f, "Invalid address - {}", e),
+            Self::BandwidthExhausted(e) => write!(f, "Bandwidth exhausted - {}", e),
+            Self::NotConnected(e) => write!(f, "Not connected to the mixnet - {}", e),
+            Self::Transient(e) | Self::Other(e) => write!(f, "Send error: {}", e),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "memory-transport"))]
+pub use memory::{MemoryResponses, MemoryTransport};
+
+#[cfg(any(test, feature = "memory-transport"))]
+mod memory {
+    use super::{BoxFuture, SendFailure, Transport};
+    use std::collections::{HashMap, HashSet, VecDeque};
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use nymview_protocol::{compression, ErrorStatus, Request, Response};
+    use tokio::sync::Notify;
+
+    // Page contents served by a MemoryTransport, keyed by path ("/", "/about").
+    // Shared so a test harness can change responses while the browser runs.
+    #[derive(Clone, Default)]
+    pub struct MemoryResponses {
+        pages: Arc<Mutex<HashMap<String, String>>>,
+        // Paths whose requests are never answered, to let them time out
+        silent: Arc<Mutex<HashSet<String>>>,
+        // Requests to these paths are answered only once released
+        held: Arc<Mutex<HashMap<String, Vec<String>>>>,
+        // Replies to released requests, for the transport to pick up
+        released: Arc<Mutex<VecDeque<Vec<u8>>>>,
+        wake: Arc<Notify>,
+        // Every request received, in order
+        received: Arc<Mutex<Vec<String>>>,
+        // How the next sends fail, one each
+        failures: Arc<Mutex<VecDeque<SendFailure>>>,
+    }
+
+    impl MemoryResponses {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn insert(&self, path: &str, content: &str) {
+            self.pages.lock().unwrap().insert(path.to_string(), content.to_string());
+        }
+
+        #[cfg_attr(not(test), allow(dead_code))]
+        pub fn silence(&self, path: &str) {
+            self.silent.lock().unwrap().insert(path.to_string());
+        }
+
+        #[cfg_attr(not(test), allow(dead_code))]
+        pub fn hold(&self, path: &str) {
+            self.held.lock().unwrap().entry(path.to_string()).or_default();
+        }
+
+        // Answers the requests held for `path` and stops holding it
+        #[cfg_attr(not(test), allow(dead_code))]
+        pub fn release(&self, path: &str) {
+            let requests = self.held.lock().unwrap().remove(path).unwrap_or_default();
+            let replies = requests.iter().filter_map(|message| self.answer(message));
+            self.released.lock().unwrap().extend(replies);
+            self.wake.notify_one();
+        }
+
+        #[cfg_attr(not(test), allow(dead_code))]
+        pub fn fail_sends(&self, failures: Vec<SendFailure>) {
+            self.failures.lock().unwrap().extend(failures);
+        }
+
+        #[cfg_attr(not(test), allow(dead_code))]
+        pub fn received(&self) -> Vec<String> {
+            self.received.lock().unwrap().clone()
+        }
+
+        // Serves every .md file in `dir` at "/<name>", index.md also at "/"
+        #[cfg_attr(not(feature = "memory-transport"), allow(dead_code))]
+        pub fn load_dir(&self, dir: &Path) -> std::io::Result<()> {
+            for entry in std::fs::read_dir(dir)?.flatten() {
+                let path = entry.path();
+                if path.extension().is_some_and(|extension| extension == "md") {
+                    if let (Some(name), Ok(content)) = (path.file_stem().and_then(|s| s.to_str()), std::fs::read_to_string(&path)) {
+                        if name == "index" {
+                            self.insert("/", &content);
+                        }
+                        self.insert(&format!("/{}", name), &content);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        // Answers a request the way mixnet_server does, unless it is
+        // silenced or held
+        fn respond(&self, message: &str) -> Option<Vec<u8>> {
+            self.received.lock().unwrap().push(message.to_string());
+            if let Ok(request) = Request::parse(message) {
+                if self.silent.lock().unwrap().contains(&request.path) {
+                    return None;
+                }
+                if let Some(held) = self.held.lock().unwrap().get_mut(&request.path) {
+                    held.push(message.to_string());
+                    return None;
+                }
+            }
+            self.answer(message)
+        }
+
+        fn answer(&self, message: &str) -> Option<Vec<u8>> {
+            let request = match Request::parse(message) {
+                Ok(request) => request,
+                Err(e) => return Some(Response::Error(ErrorStatus::ServerError, e).to_string().into_bytes()),
+            };
+            let response = match request.method.as_str() {
+                "GET" => match self.pages.lock().unwrap().get(&request.path) {
+                    Some(content) => Response::Ok(content.clone()).to_string(),
+                    None => Response::Error(
+                        ErrorStatus::NotFound,
+                        format!("Page '{}' not found", request.path.trim_start_matches('/')),
+                    )
+                    .to_string(),
+                },
+                "PING" => nymview_protocol::PONG_WITH_HEADERS.to_string(),
+                _ => Response::Error(ErrorStatus::ServerError, "Invalid request format".to_string()).to_string(),
+            };
+            let response = if request.accepts_encoding(nymview_protocol::ZSTD) {
+                compression::compress(response.as_bytes()).unwrap_or_else(|| response.into_bytes())
+            } else {
+                response.into_bytes()
+            };
+            Some(match request.request_id() {
+                Some(request_id) => nymview_protocol::tag_reply(request_id, &response),
+                None => response,
+            })
+        }
+    }
+
+    pub struct MemoryTransport {
+        address: String,
+        responses: MemoryResponses,
+        inbox: VecDeque<Vec<u8>>,
+    }
+
+    impl MemoryTransport {
+        pub fn new(address: &str, responses: MemoryResponses) -> Self {
+            Self {
+                address: address.to_string(),
+                responses,
+                inbox: VecDeque::new(),
+            }
+        }
+    }
+
+    impl Transport for MemoryTransport {
+        fn address(&self) -> String {
+            self.address.clone()
+        }
+
+        fn next_messages(&mut self) -> BoxFuture<'_, Option<Vec<Vec<u8>>>> {
+            Box::pin(async move {
+                // Replies are queued by send() on the same task, so an empty
+                // inbox just waits for the next request or a release
+                loop {
+                    self.inbox.extend(self.responses.released.lock().unwrap().drain(..));
+                    if !self.inbox.is_empty() {
+                        return Some(self.inbox.drain(..).collect());
+                    }
+                    self.responses.wake.notified().await;
+                }
+            })
+        }
+
+        fn send(&mut self, recipient: String, message: String, _reply_surbs: u32) -> BoxFuture<'_, Result<(), SendFailure>> {
+            Box::pin(async move {
+                if let Some(failure) = self.responses.failures.lock().unwrap().pop_front() {
+                    return Err(failure);
+                }
+                // Messages to our own address come straight back
+                if recipient == self.address {
+                    self.inbox.push_back(message.into_bytes());
+                    return Ok(());
+                }
+                self.inbox.extend(self.responses.respond(&message));
+                Ok(())
+            })
+        }
+
+        fn disconnect(self: Box<Self>) -> BoxFuture<'static, ()> {
+            Box::pin(async {})
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn send_errors_are_classified_by_their_kind() {
+        let io_error = |kind| nym_sdk::Error::IoError(io::Error::new(kind, "socket"));
+        let cases = [
+            (nym_sdk::Error::MessageSendingFailure, SendFailure::NotConnected as fn(String) -> SendFailure),
+            (io_error(ErrorKind::WouldBlock), SendFailure::Transient),
+            (io_error(ErrorKind::TimedOut), SendFailure::Transient),
+            (io_error(ErrorKind::BrokenPipe), SendFailure::NotConnected),
+            (io_error(ErrorKind::ConnectionReset), SendFailure::NotConnected),
+            // An I/O error is never about bandwidth, whatever it says
+            (nym_sdk::Error::IoError(io::Error::other("bandwidth exhausted")), SendFailure::Other),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(SendFailure::classify(&error), expected(error.to_string()), "error {:?}", error);
+        }
+    }
+
+    #[test]
+    fn other_send_errors_are_classified_by_their_message() {
+        let cases = [
+            ("Gateway bandwidth exhausted", SendFailure::BandwidthExhausted as fn(String) -> SendFailure),
+            ("Not enough bandwidth remaining", SendFailure::BandwidthExhausted),
+            ("bandwidth: no remaining allowance", SendFailure::BandwidthExhausted),
+            ("Malformed recipient", SendFailure::InvalidRecipient),
+            ("invalid address: abc", SendFailure::InvalidRecipient),
+            ("Client is not connected", SendFailure::NotConnected),
+            ("gateway disconnected", SendFailure::NotConnected),
+            ("channel closed", SendFailure::NotConnected),
+            ("Client is shutting down", SendFailure::NotConnected),
+            ("client shut down", SendFailure::NotConnected),
+            ("Input queue is full", SendFailure::Transient),
+            ("lane busy", SendFailure::Transient),
+            ("operation timed out, try again", SendFailure::Transient),
+            ("Temporarily unavailable", SendFailure::Transient),
+            ("bandwidth metering failed", SendFailure::Other),
+            ("something else", SendFailure::Other),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(SendFailure::classify_message(error), expected(error.to_string()), "error {:?}", error);
+        }
+    }
+
+    #[test]
+    fn only_transient_failures_are_retried() {
+        assert!(SendFailure::Transient("queue full".to_string()).is_transient());
+        for failure in [
+            SendFailure::InvalidRecipient(String::new()),
+            SendFailure::BandwidthExhausted(String::new()),
+            SendFailure::NotConnected(String::new()),
+            SendFailure::Other(String::new()),
+        ] {
+            assert!(!failure.is_transient(), "{:?}", failure);
+        }
+    }
+}
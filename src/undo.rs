@@ -0,0 +1,166 @@
+use std::time::{Duration, Instant};
+use crate::bookmarks::{Bookmark, Bookmarks};
+use crate::server_filter::FilterMode;
+use crate::settings::Settings;
+use crate::templates::RequestTemplate;
+
+// How long a removal can be taken back
+pub const UNDO_WINDOW: Duration = Duration::from_secs(30);
+
+// Something deleted from settings, with enough context to put it back
+// where it was
+#[derive(Debug, Clone)]
+pub enum Removed {
+    Template { server: String, index: usize, template: RequestTemplate },
+    FilterEntry { mode: FilterMode, index: usize, server: String },
+    Bookmark { index: usize, bookmark: Bookmark },
+}
+
+impl Removed {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Template { template, .. } => format!("Removed template \"{}\"", template.name),
+            Self::FilterEntry { mode: FilterMode::AllowOnly, .. } => "Removed a server from the allow list".to_string(),
+            Self::FilterEntry { .. } => "Removed a server from the deny list".to_string(),
+            Self::Bookmark { bookmark, .. } => format!("Deleted bookmark \"{}\"", bookmark.title),
+        }
+    }
+
+    // Puts the entry back; true when it was a bookmark, false for settings.
+    // Either still needs saving afterwards.
+    pub fn restore(self, settings: &mut Settings, bookmarks: &mut Bookmarks) -> bool {
+        match self {
+            Self::Template { server, index, template } => {
+                let templates = settings.request_templates.entry(server).or_default();
+                templates.insert(index.min(templates.len()), template);
+            }
+            Self::FilterEntry { mode, index, server } => {
+                if let Some(list) = settings.server_filter.list_mut(mode) {
+                    if !list.contains(&server) {
+                        list.insert(index.min(list.len()), server);
+                    }
+                }
+            }
+            Self::Bookmark { index, bookmark } => {
+                // Starred again in the meantime
+                if bookmarks.position(&bookmark.url).is_none() {
+                    bookmarks.bookmarks.insert(index.min(bookmarks.bookmarks.len()), bookmark);
+                }
+                return true;
+            }
+        }
+        false
+    }
+}
+
+// Recent removals, each undoable on its own until it expires. Kept in
+// memory only, so nothing survives a restart.
+#[derive(Debug, Clone, Default)]
+pub struct UndoStack {
+    entries: Vec<(Removed, Instant)>,
+}
+
+impl UndoStack {
+    pub fn push(&mut self, removed: Removed) {
+        self.entries.push((removed, Instant::now()));
+    }
+
+    // Drops expired entries; the rest keep their order
+    pub fn expire(&mut self) {
+        self.entries.retain(|(_, at)| at.elapsed() < UNDO_WINDOW);
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Removed> {
+        self.entries.iter().map(|(removed, _)| removed)
+    }
+
+    pub fn take(&mut self, index: usize) -> Option<Removed> {
+        (index < self.entries.len()).then(|| self.entries.remove(index).0)
+    }
+
+    pub fn dismiss(&mut self, index: usize) {
+        self.take(index);
+    }
+
+    // Time until the oldest entry expires, for scheduling a repaint
+    pub fn next_expiry(&self) -> Option<Duration> {
+        self.entries
+            .iter()
+            .map(|(_, at)| UNDO_WINDOW.saturating_sub(at.elapsed()))
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(title: &str) -> Bookmark {
+        Bookmark { title: title.to_string(), url: format!("nym://server.addr@gateway/{}", title), folder: String::new() }
+    }
+
+    fn titles(bookmarks: &Bookmarks) -> Vec<&str> {
+        bookmarks.bookmarks.iter().map(|bookmark| bookmark.title.as_str()).collect()
+    }
+
+    // As if pushed `age` ago
+    fn push_aged(stack: &mut UndoStack, removed: Removed, age: Duration) {
+        stack.entries.push((removed, Instant::now() - age));
+    }
+
+    #[test]
+    fn expired_entries_go_and_the_rest_can_still_be_restored() {
+        let mut settings = Settings::default();
+        let mut bookmarks = Bookmarks { bookmarks: vec![bookmark("a"), bookmark("d")] };
+        let mut stack = UndoStack::default();
+        push_aged(&mut stack, Removed::Bookmark { index: 0, bookmark: bookmark("old") }, UNDO_WINDOW + Duration::from_secs(1));
+        push_aged(&mut stack, Removed::Bookmark { index: 1, bookmark: bookmark("b") }, UNDO_WINDOW / 2);
+        stack.push(Removed::Bookmark { index: 1, bookmark: bookmark("c") });
+        stack.push(Removed::FilterEntry { mode: FilterMode::Deny, index: 0, server: "x.y@gateway".to_string() });
+
+        stack.expire();
+        let left: Vec<String> = stack.entries().map(Removed::describe).collect();
+        assert_eq!(left, vec![
+            "Deleted bookmark \"b\"",
+            "Deleted bookmark \"c\"",
+            "Removed a server from the deny list",
+        ]);
+        let next = stack.next_expiry().unwrap();
+        assert!(next <= UNDO_WINDOW / 2 && next > UNDO_WINDOW / 2 - Duration::from_secs(5), "{:?}", next);
+
+        // Undone newest first, so each lands where it was
+        assert!(stack.take(1).unwrap().restore(&mut settings, &mut bookmarks));
+        assert!(stack.take(0).unwrap().restore(&mut settings, &mut bookmarks));
+        assert_eq!(titles(&bookmarks), vec!["a", "b", "c", "d"]);
+        assert!(!stack.take(0).unwrap().restore(&mut settings, &mut bookmarks));
+        assert_eq!(settings.server_filter.denied, vec!["x.y@gateway".to_string()]);
+        assert!(stack.take(0).is_none());
+        assert_eq!(stack.next_expiry(), None);
+    }
+
+    #[test]
+    fn restoring_never_duplicates() {
+        let mut settings = Settings::default();
+        let mut bookmarks = Bookmarks { bookmarks: vec![bookmark("a"), bookmark("b")] };
+        Removed::Bookmark { index: 0, bookmark: bookmark("b") }.restore(&mut settings, &mut bookmarks);
+        assert_eq!(titles(&bookmarks), vec!["a", "b"]);
+
+        settings.server_filter.allowed.push("x.y@gateway".to_string());
+        Removed::FilterEntry { mode: FilterMode::AllowOnly, index: 0, server: "x.y@gateway".to_string() }
+            .restore(&mut settings, &mut bookmarks);
+        assert_eq!(settings.server_filter.allowed, vec!["x.y@gateway".to_string()]);
+    }
+
+    #[test]
+    fn indexes_past_the_end_append() {
+        let mut settings = Settings::default();
+        let mut bookmarks = Bookmarks::default();
+        let template = RequestTemplate { name: "search".to_string(), path: "/search?q={query}".to_string() };
+        Removed::Template { server: "x.y@gateway".to_string(), index: 5, template: template.clone() }
+            .restore(&mut settings, &mut bookmarks);
+        Removed::Bookmark { index: 3, bookmark: bookmark("a") }.restore(&mut settings, &mut bookmarks);
+
+        assert_eq!(settings.request_templates["x.y@gateway"], vec![template]);
+        assert_eq!(titles(&bookmarks), vec!["a"]);
+    }
+}
@@ -0,0 +1,112 @@
+use eframe::App;
+use egui::{Color32, TextEdit};
+use std::sync::Arc;
+use crate::encrypted_storage::EncryptedStorage;
+use crate::mixnet_browser::NymMixnetBrowser;
+use crate::storage::Storage;
+
+// Asks for the passphrase of encrypted local data, then hands over to the
+// browser. Runs inside the same eframe app since the event loop cannot be
+// started twice.
+pub struct UnlockApp {
+    inner: Arc<dyn Storage>,
+    passphrase: String,
+    error: Option<String>,
+    confirm_start_fresh: bool,
+    browser: Option<NymMixnetBrowser>,
+}
+
+impl UnlockApp {
+    pub fn new(inner: Arc<dyn Storage>) -> Self {
+        Self {
+            inner,
+            passphrase: String::new(),
+            error: None,
+            confirm_start_fresh: false,
+            browser: None,
+        }
+    }
+
+    fn unlock(&mut self) {
+        match EncryptedStorage::unlock(self.inner.clone(), &self.passphrase) {
+            Ok(storage) => self.open_browser(storage),
+            Err(e) => {
+                self.error = Some(e);
+                self.passphrase.clear();
+            }
+        }
+    }
+
+    // Discards the encrypted data by replacing the key with one for the
+    // passphrase just entered
+    fn start_fresh(&mut self) {
+        match EncryptedStorage::enable(self.inner.clone(), &self.passphrase) {
+            Ok(storage) => self.open_browser(storage),
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    fn open_browser(&mut self, storage: EncryptedStorage) {
+        self.passphrase.clear();
+        self.browser = Some(NymMixnetBrowser::with_storage(Arc::new(storage)));
+    }
+
+    fn show_prompt(&mut self, ui: &mut egui::Ui) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(120.0);
+            ui.heading("🔒 NymView is locked");
+            ui.label("Your local data is encrypted. Enter your passphrase to continue.");
+            ui.add_space(8.0);
+
+            let response = ui.add(TextEdit::singleline(&mut self.passphrase)
+                .password(true)
+                .hint_text("Passphrase")
+                .desired_width(240.0));
+            response.request_focus();
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let has_passphrase = !self.passphrase.is_empty();
+
+            if ui.add_enabled(has_passphrase, egui::Button::new("Unlock")).clicked()
+                || (submitted && has_passphrase && !self.confirm_start_fresh) {
+                self.unlock();
+            }
+
+            if let Some(error) = &self.error {
+                ui.colored_label(Color32::RED, error);
+            }
+
+            ui.add_space(16.0);
+            if !self.confirm_start_fresh {
+                if ui.small_button("Forgot passphrase? Start fresh…").clicked() {
+                    self.confirm_start_fresh = true;
+                }
+            } else {
+                ui.colored_label(
+                    Color32::DARK_RED,
+                    "Starting fresh discards your encrypted settings and data. \
+                     The passphrase above becomes the new one.",
+                );
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(has_passphrase, egui::Button::new("Discard and start fresh")).clicked() {
+                        self.start_fresh();
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_start_fresh = false;
+                    }
+                });
+            }
+        });
+    }
+}
+
+impl App for UnlockApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if let Some(browser) = &mut self.browser {
+            browser.update(ctx, frame);
+            return;
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.show_prompt(ui);
+        });
+    }
+}
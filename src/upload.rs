@@ -0,0 +1,98 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use egui::{ColorImage, Context, TextureHandle, TextureOptions};
+use image::{ImageFormat, RgbaImage};
+use nymview_protocol::{Request, BASE64, BODY_ENCODING_HEADER, CONTENT_TYPE_HEADER, PUTBIN, REQUEST_ID_HEADER};
+use std::fmt;
+use std::io::Cursor;
+
+// Largest encoded image that is sent; base64 adds a third to it on the wire
+pub const MAX_UPLOAD_SIZE: usize = 2 * 1024 * 1024;
+
+// An image pasted from the clipboard. The texture shows it; the PNG is
+// what gets uploaded.
+#[derive(Clone)]
+pub struct PastedImage {
+    pub texture: TextureHandle,
+    pub png: Vec<u8>,
+}
+
+impl fmt::Debug for PastedImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [width, height] = self.texture.size();
+        write!(f, "PastedImage({}×{}, {} bytes)", width, height, self.png.len())
+    }
+}
+
+// Blocking while the clipboard is read and the image encoded; run off
+// the UI thread
+pub fn paste_image(ctx: &Context) -> Result<PastedImage, String> {
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|e| format!("Could not open the clipboard: {}", e))?;
+    let data = clipboard.get_image().map_err(|e| match e {
+        arboard::Error::ContentNotAvailable => "The clipboard holds no image".to_string(),
+        e => format!("Could not read the clipboard: {}", e),
+    })?;
+    let image = ColorImage::from_rgba_unmultiplied([data.width, data.height], &data.bytes);
+    let png = encode_png(&image)?;
+    let texture = ctx.load_texture("pasted-image", image, TextureOptions::LINEAR);
+    Ok(PastedImage { texture, png })
+}
+
+pub fn encode_png(image: &ColorImage) -> Result<Vec<u8>, String> {
+    let [width, height] = image.size;
+    let rgba: Vec<u8> = image.pixels.iter().flat_map(|pixel| pixel.to_srgba_unmultiplied()).collect();
+    let buffer = RgbaImage::from_raw(width as u32, height as u32, rgba)
+        .ok_or("Image size does not match its pixels")?;
+    let mut png = Vec::new();
+    buffer
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Could not encode image: {}", e))?;
+    if png.len() > MAX_UPLOAD_SIZE {
+        return Err(format!(
+            "The image is {} KB; at most {} KB can be sent",
+            png.len() / 1024,
+            MAX_UPLOAD_SIZE / 1024
+        ));
+    }
+    Ok(png)
+}
+
+// A PUTBIN storing `bytes` at `path`. The request ID is only given for
+// servers that read headers.
+pub fn upload_request(path: &str, reply_to: &str, content_type: &str, bytes: &[u8], request_id: Option<u64>) -> Request {
+    let mut request = Request::new(PUTBIN, path, reply_to)
+        .with_header(CONTENT_TYPE_HEADER, content_type)
+        .with_header(BODY_ENCODING_HEADER, BASE64);
+    if let Some(request_id) = request_id {
+        request = request.with_header(REQUEST_ID_HEADER, &request_id.to_string());
+    }
+    request.with_body(&STANDARD.encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::Color32;
+
+    #[test]
+    fn encoded_png_decodes_to_the_same_pixels() {
+        let image = ColorImage::new([2, 1], vec![Color32::RED, Color32::from_rgba_unmultiplied(0, 0, 255, 128)]);
+        let png = encode_png(&image).unwrap();
+        let decoded = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (2, 1));
+        assert_eq!(decoded.as_raw().as_slice(), &[255, 0, 0, 255, 0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn upload_request_carries_the_bytes_base64_encoded() {
+        let request = upload_request("/page.md", "client.addr@gw", "image/png", &[0, 1, 2, 255], Some(7));
+        let parsed = Request::parse(&request.to_string()).unwrap();
+        assert_eq!(parsed.method, PUTBIN);
+        assert_eq!(parsed.path, "/page.md");
+        assert_eq!(parsed.header(CONTENT_TYPE_HEADER), Some("image/png"));
+        assert_eq!(parsed.header(BODY_ENCODING_HEADER), Some(BASE64));
+        assert_eq!(parsed.request_id(), Some(7));
+        assert_eq!(STANDARD.decode(parsed.body).unwrap(), vec![0, 1, 2, 255]);
+    }
+}